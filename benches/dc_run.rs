@@ -7,6 +7,43 @@ use spice_rs::{
     Circuit,
 };
 
+/// A `size` by `size` grid of 1Ω resistors, like a power grid,
+/// driven at one corner and grounded at the opposite corner.
+fn resistor_mesh(size: usize) -> Circuit {
+    let mut circuit = Circuit::default();
+    let ground = circuit.push_node();
+    let mesh: Vec<Vec<_>> = (0..size)
+        .map(|_| (0..size).map(|_| circuit.push_node()).collect())
+        .collect();
+
+    circuit.add_element(Box::new(DCVoltageSource::new(1.0, mesh[0][0], ground)));
+    circuit.add_element(Box::new(Resistor::new(
+        1.0,
+        mesh[size - 1][size - 1],
+        ground,
+    )));
+    for row in 0..size {
+        for column in 0..size {
+            if column + 1 < size {
+                circuit.add_element(Box::new(Resistor::new(
+                    1.0,
+                    mesh[row][column],
+                    mesh[row][column + 1],
+                )));
+            }
+            if row + 1 < size {
+                circuit.add_element(Box::new(Resistor::new(
+                    1.0,
+                    mesh[row][column],
+                    mesh[row + 1][column],
+                )));
+            }
+        }
+    }
+
+    circuit
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let mut graph = Circuit::default();
     let v0 = graph.push_node();
@@ -15,12 +52,22 @@ fn criterion_benchmark(c: &mut Criterion) {
     let v3 = graph.push_node();
     graph.add_element(Box::new(Resistor::new(2.0, v1, v0)));
     graph.add_element(Box::new(Resistor::new(4.0, v1, v0)));
-    graph.add_element(Box::new(DCVoltageSource::new(10.0, v2, v1, 0)));
+    graph.add_element(Box::new(DCVoltageSource::new(10.0, v2, v1)));
     graph.add_element(Box::new(Resistor::new(6.0, v2, v0)));
     graph.add_element(Box::new(Resistor::new(2.0, v2, v3)));
     graph.add_element(Box::new(DCCurrentSource::new(3.0, v3, v0)));
 
     c.bench_function("dc_run", |b| b.iter(|| dc_op(black_box(&graph))));
+
+    let mut group = c.benchmark_group("dc_run_mesh");
+    group.sample_size(10);
+    for size in [30, 100, 200] {
+        let mesh = resistor_mesh(size);
+        group.bench_function(format!("{size}x{size}"), |b| {
+            b.iter(|| dc_op(black_box(&mesh)))
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,154 @@
+use nalgebra::Complex;
+
+use crate::{runners::operating_point::OperatingPoint, NodeId};
+
+use super::{
+    source::Source, stamp::StampContext, waveform::Waveform, Element, ParameterError, Terminal,
+};
+
+#[derive(Default, Debug, Clone)]
+pub struct ACCurrentSource {
+    source: Source,
+    terminals: [Terminal; 2],
+}
+
+impl ACCurrentSource {
+    /// A source of the `current` phasor flowing into the positive node in an AC analysis,
+    /// and an open circuit otherwise.
+    pub fn new(current: Complex<f64>, positive_node: NodeId, negative_node: NodeId) -> Self {
+        Self {
+            source: Source::from_phasor(current),
+            terminals: [
+                Terminal::new(positive_node, super::Polarity::Positive),
+                Terminal::new(negative_node, super::Polarity::Negative),
+            ],
+        }
+    }
+
+    /// Adds a DC `current`, which biases the circuit the AC current is superimposed on.
+    #[must_use]
+    pub fn with_dc(mut self, current: f64) -> Self {
+        self.source.dc = current;
+        self
+    }
+
+    /// Follows the `waveform` during a transient analysis instead of the DC current.
+    #[must_use]
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.source.waveform = Some(waveform);
+        self
+    }
+}
+
+impl Element for ACCurrentSource {
+    fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    /// Stamps its DC current flowing into the positive node onto the z_vector.
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        let terminals = self.terminals();
+        let current = self.source.value(context.time());
+        context.add_current(terminals[0].node, terminals[1].node, current);
+    }
+
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        _omega: f64,
+    ) {
+        let terminals = self.terminals();
+        context.add_current(terminals[0].node, terminals[1].node, self.ac_current());
+    }
+
+    fn breakpoints(&self, stop: f64) -> Vec<f64> {
+        self.source.breakpoints(stop)
+    }
+
+    /// `dc`, `acmag` and `acphase`, the DC value and the magnitude and phase of the AC phasor.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        self.source.parameter(name)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        self.source.set_parameter(name, value)
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        self.source.dc
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        self.source.ac
+    }
+
+    fn resistance(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::new(f64::INFINITY, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{
+            capacitor::Capacitor, dc_current_source::DCCurrentSource, resistor::Resistor,
+            waveform::Waveform,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep, transient::transient},
+        Circuit,
+    };
+
+    use super::ACCurrentSource;
+
+    /// Each analysis uses its own part of the sources: the DC current in the operating point,
+    /// the phasors in the AC analysis and the waveform in the transient analysis.
+    #[test]
+    fn analyses() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let v1 = circuit.push_node();
+        circuit.add_element(Box::new(
+            ACCurrentSource::new(Complex::new(0.0, 1e-3), v1, ground).with_dc(2e-3),
+        ));
+        let ramp = Waveform::PiecewiseLinear(vec![(0.0, 0.0), (1e-3, 4e-3)]);
+        circuit.add_element(Box::new(
+            DCCurrentSource::new(1e-3, v1, ground)
+                .with_ac(1e-3, 180.0)
+                .with_waveform(ramp),
+        ));
+        circuit.add_element(Box::new(Resistor::new(1e3, v1, ground)));
+        circuit.add_element(Box::new(Capacitor::new(1e-12, v1, ground)));
+
+        let op = dc_op(&circuit).unwrap();
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 1.0,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+        let tran = transient(&circuit, 5e-4, 1e-3).unwrap();
+
+        assert_relative_eq!(op.voltage(v1), 3.0);
+        let phasor = result.points[0].voltage(v1);
+        assert_relative_eq!(phasor.re, -1.0, epsilon = 1e-6);
+        assert_relative_eq!(phasor.im, 1.0, epsilon = 1e-6);
+        assert_relative_eq!(tran.points[0].voltage(v1), 2.0, epsilon = 1e-6);
+        assert_relative_eq!(tran.at(1e-3).unwrap().voltage(v1), 6.0, epsilon = 1e-4);
+    }
+}
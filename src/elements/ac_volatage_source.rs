@@ -1,25 +1,44 @@
 use nalgebra::Complex;
 
-use crate::NodeId;
+use crate::{runners::operating_point::OperatingPoint, BranchId, NodeId};
 
-use super::{Element, Terminal};
+use super::{
+    source::Source, stamp::StampContext, waveform::Waveform, Element, ParameterError, Terminal,
+};
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 pub struct ACVoltageSource {
-    voltage: Complex<f32>,
+    source: Source,
     terminals: [Terminal; 2],
+    branch: BranchId,
 }
 
 impl ACVoltageSource {
-    pub fn new(voltage: Complex<f32>, positive_node: NodeId, negative_node: NodeId) -> Self {
+    /// A source of the `voltage` phasor in an AC analysis, and 0V otherwise.
+    pub fn new(voltage: Complex<f64>, positive_node: NodeId, negative_node: NodeId) -> Self {
         Self {
-            voltage,
+            source: Source::from_phasor(voltage),
             terminals: [
                 Terminal::new(positive_node, super::Polarity::Positive),
                 Terminal::new(negative_node, super::Polarity::Negative),
             ],
+            branch: BranchId::default(),
         }
     }
+
+    /// Adds a DC `voltage`, which biases the circuit the AC voltage is superimposed on.
+    #[must_use]
+    pub fn with_dc(mut self, voltage: f64) -> Self {
+        self.source.dc = voltage;
+        self
+    }
+
+    /// Follows the `waveform` during a transient analysis instead of the DC voltage.
+    #[must_use]
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.source.waveform = Some(waveform);
+        self
+    }
 }
 
 impl Element for ACVoltageSource {
@@ -27,29 +46,67 @@ impl Element for ACVoltageSource {
         &self.terminals
     }
 
-    fn stamp(&self, _a_matrix: &mut Vec<f32>, _z_vector: &mut Vec<f32>, _n: usize, _m: usize) {}
+    /// Stamps the DC voltage, which is a short circuit without one.
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        let terminals = self.terminals();
+        let voltage = self.source.value(context.time());
+        context.add_branch(self.branch, terminals[0].node, terminals[1].node);
+        context.add_branch_voltage(self.branch, voltage);
+    }
 
-    fn dc_voltage(&self) -> f32 {
-        0.0
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        _omega: f64,
+    ) {
+        let terminals = self.terminals();
+        context.add_branch(self.branch, terminals[0].node, terminals[1].node);
+        context.add_branch_voltage(self.branch, self.ac_voltage());
+    }
+
+    fn is_b_c_element(&self) -> bool {
+        true
+    }
+
+    fn set_branch(&mut self, branch: BranchId) {
+        self.branch = branch;
+    }
+
+    fn breakpoints(&self, stop: f64) -> Vec<f64> {
+        self.source.breakpoints(stop)
+    }
+
+    /// `dc`, `acmag` and `acphase`, the DC value and the magnitude and phase of the AC phasor.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        self.source.parameter(name)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        self.source.set_parameter(name, value)
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        self.source.dc
     }
 
-    fn ac_voltage(&self) -> Complex<f32> {
-        self.voltage
+    fn ac_voltage(&self) -> Complex<f64> {
+        self.source.ac
     }
 
-    fn dc_current(&self) -> f32 {
+    fn dc_current(&self) -> f64 {
         0.0
     }
 
-    fn ac_current(&self) -> Complex<f32> {
+    fn ac_current(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn resistance(&self) -> f32 {
+    fn resistance(&self) -> f64 {
         0.0
     }
 
-    fn impedance(&self, _frequency: f32) -> Complex<f32> {
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
         Complex::ZERO
     }
 }
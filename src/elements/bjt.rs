@@ -0,0 +1,847 @@
+use nalgebra::Complex;
+
+use crate::{
+    runners::{integration::Integration, operating_point::OperatingPoint},
+    NodeId,
+};
+
+use super::{
+    junction::{
+        critical_voltage, depletion, limit, saturation_current, thermal_voltage, GMIN,
+        NOMINAL_TEMPERATURE,
+    },
+    stamp::StampContext,
+    Element, ParameterError, Terminal,
+};
+
+/// Whether the collector and emitter of a [`Bjt`] are n-type or p-type.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BjtKind {
+    #[default]
+    Npn,
+    Pnp,
+}
+
+impl BjtKind {
+    /// `1` for NPN and `-1` for PNP, which flips every junction voltage and current.
+    pub fn sign(&self) -> f64 {
+        match self {
+            Self::Npn => 1.0,
+            Self::Pnp => -1.0,
+        }
+    }
+}
+
+/// The parameters of a [`Bjt`], named after their SPICE `.model` parameters.
+///
+/// These are the Ebers-Moll parameters with the Early effect, which is the Gummel-Poon
+/// model without high injection and base-emitter or base-collector leakage.
+/// The defaults are the same as in SPICE.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BjtModel {
+    /// `IS`, the transport saturation current in Amperes.
+    pub saturation_current: f64,
+    /// `BF`, the ideal maximum forward current gain.
+    pub forward_beta: f64,
+    /// `BR`, the ideal maximum reverse current gain.
+    pub reverse_beta: f64,
+    /// `NF`, the forward emission coefficient.
+    pub forward_emission_coefficient: f64,
+    /// `NR`, the reverse emission coefficient.
+    pub reverse_emission_coefficient: f64,
+    /// `VAF`, the forward Early voltage in Volts, infinite for no Early effect.
+    pub forward_early_voltage: f64,
+    /// `VAR`, the reverse Early voltage in Volts, infinite for no Early effect.
+    pub reverse_early_voltage: f64,
+    /// `RB`, the base resistance in Ohms.
+    pub base_resistance: f64,
+    /// `RC`, the collector resistance in Ohms.
+    pub collector_resistance: f64,
+    /// `RE`, the emitter resistance in Ohms.
+    pub emitter_resistance: f64,
+    /// `CJE`, the zero-bias base-emitter junction capacitance in Farads.
+    pub emitter_capacitance: f64,
+    /// `VJE`, the base-emitter junction potential in Volts.
+    pub emitter_potential: f64,
+    /// `MJE`, the base-emitter grading coefficient.
+    pub emitter_grading: f64,
+    /// `CJC`, the zero-bias base-collector junction capacitance in Farads.
+    pub collector_capacitance: f64,
+    /// `VJC`, the base-collector junction potential in Volts.
+    pub collector_potential: f64,
+    /// `MJC`, the base-collector grading coefficient.
+    pub collector_grading: f64,
+    /// `CJS`, the zero-bias collector-substrate capacitance in Farads.
+    pub substrate_capacitance: f64,
+    /// `VJS`, the collector-substrate junction potential in Volts.
+    pub substrate_potential: f64,
+    /// `MJS`, the collector-substrate grading coefficient.
+    pub substrate_grading: f64,
+    /// `TF`, the forward transit time in seconds.
+    pub forward_transit_time: f64,
+    /// `TR`, the reverse transit time in seconds.
+    pub reverse_transit_time: f64,
+    /// `EG`, the energy gap in electronvolts, which sets how fast the saturation current
+    /// grows with temperature.
+    pub energy_gap: f64,
+    /// `XTI`, the temperature exponent of the saturation current.
+    pub saturation_current_exponent: f64,
+}
+
+impl Default for BjtModel {
+    fn default() -> Self {
+        Self {
+            saturation_current: 1e-16,
+            forward_beta: 100.0,
+            reverse_beta: 1.0,
+            forward_emission_coefficient: 1.0,
+            reverse_emission_coefficient: 1.0,
+            forward_early_voltage: f64::INFINITY,
+            reverse_early_voltage: f64::INFINITY,
+            base_resistance: 0.0,
+            collector_resistance: 0.0,
+            emitter_resistance: 0.0,
+            emitter_capacitance: 0.0,
+            emitter_potential: 0.75,
+            emitter_grading: 0.33,
+            collector_capacitance: 0.0,
+            collector_potential: 0.75,
+            collector_grading: 0.33,
+            substrate_capacitance: 0.0,
+            substrate_potential: 0.75,
+            substrate_grading: 0.0,
+            forward_transit_time: 0.0,
+            reverse_transit_time: 0.0,
+            energy_gap: 1.11,
+            saturation_current_exponent: 3.0,
+        }
+    }
+}
+
+impl BjtModel {
+    /// The value of the parameter with the lowercase SPICE `name`, such as `bf`.
+    pub fn parameter(&self, name: &str) -> Option<f64> {
+        let mut model = *self;
+        model.parameter_mut(name).copied()
+    }
+
+    /// The parameter with the lowercase SPICE `name`, such as `bf`, to change.
+    pub fn parameter_mut(&mut self, name: &str) -> Option<&mut f64> {
+        Some(match name {
+            "is" => &mut self.saturation_current,
+            "bf" => &mut self.forward_beta,
+            "br" => &mut self.reverse_beta,
+            "nf" => &mut self.forward_emission_coefficient,
+            "nr" => &mut self.reverse_emission_coefficient,
+            "vaf" | "va" => &mut self.forward_early_voltage,
+            "var" | "vb" => &mut self.reverse_early_voltage,
+            "rb" => &mut self.base_resistance,
+            "rc" => &mut self.collector_resistance,
+            "re" => &mut self.emitter_resistance,
+            "cje" => &mut self.emitter_capacitance,
+            "vje" | "pe" => &mut self.emitter_potential,
+            "mje" | "me" => &mut self.emitter_grading,
+            "cjc" => &mut self.collector_capacitance,
+            "vjc" | "pc" => &mut self.collector_potential,
+            "mjc" | "mc" => &mut self.collector_grading,
+            "cjs" | "ccs" => &mut self.substrate_capacitance,
+            "vjs" | "ps" => &mut self.substrate_potential,
+            "mjs" | "ms" => &mut self.substrate_grading,
+            "tf" => &mut self.forward_transit_time,
+            "tr" => &mut self.reverse_transit_time,
+            "eg" => &mut self.energy_gap,
+            "xti" => &mut self.saturation_current_exponent,
+            _ => return None,
+        })
+    }
+}
+
+/// The currents of a [`Bjt`] and their derivatives at a pair of junction voltages,
+/// oriented as in an NPN transistor.
+#[derive(Debug, Clone, Copy)]
+struct Currents {
+    /// The base-emitter diode current, before dividing by `BF`.
+    base_emitter: f64,
+    /// The base-collector diode current, before dividing by `BR`.
+    base_collector: f64,
+    /// The current transported from the collector to the emitter.
+    transport: f64,
+    /// `d(base_emitter / BF) / d(vbe)`
+    g_pi: f64,
+    /// `d(base_collector / BR) / d(vbc)`
+    g_mu: f64,
+    /// `d(transport) / d(vbe)` at a constant `vce`.
+    g_m: f64,
+    /// `d(transport) / d(vce)` at a constant `vbe`.
+    g_o: f64,
+    /// `d(base_emitter) / d(vbe)`, for the diffusion capacitance.
+    g_be: f64,
+    /// `d(base_collector) / d(vbc)`, for the diffusion capacitance.
+    g_bc: f64,
+}
+
+/// A bipolar junction transistor following the Ebers-Moll transport model,
+/// with the Early effect, series resistances and junction and diffusion capacitances.
+///
+/// The substrate is ground unless it is given, and only connects
+/// to the collector through the collector-substrate capacitance. The saturation current
+/// and the thermal voltage follow the temperature of the transistor, which is 27°C
+/// unless it is given.
+#[derive(Debug, Clone, Copy)]
+pub struct Bjt {
+    kind: BjtKind,
+    model: BjtModel,
+    /// In °C.
+    temperature: f64,
+    /// The collector, base, emitter and substrate.
+    terminals: [Terminal; 4],
+    /// The collector, base and emitter behind their series resistances,
+    /// which are internal nodes if there is a resistance.
+    internal: [NodeId; 3],
+}
+
+impl Default for Bjt {
+    fn default() -> Self {
+        Self::new(
+            BjtKind::default(),
+            NodeId::default(),
+            NodeId::default(),
+            NodeId::default(),
+        )
+    }
+}
+
+impl Bjt {
+    pub fn new(kind: BjtKind, collector: NodeId, base: NodeId, emitter: NodeId) -> Self {
+        Self {
+            kind,
+            model: BjtModel::default(),
+            temperature: NOMINAL_TEMPERATURE,
+            terminals: [
+                Terminal::new_positive(collector),
+                Terminal::new_neutral(base),
+                Terminal::new_negative(emitter),
+                Terminal::new_neutral(NodeId(0)),
+            ],
+            internal: [collector, base, emitter],
+        }
+    }
+
+    /// Sets the parameters of the transistor, before it is added to the circuit.
+    #[must_use]
+    pub fn with_model(mut self, model: BjtModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Connects the substrate to `substrate` instead of ground.
+    #[must_use]
+    pub fn with_substrate(mut self, substrate: NodeId) -> Self {
+        self.terminals[3] = Terminal::new_neutral(substrate);
+        self
+    }
+
+    /// Sets the temperature of the transistor in °C.
+    #[must_use]
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn kind(&self) -> BjtKind {
+        self.kind
+    }
+
+    pub fn model(&self) -> &BjtModel {
+        &self.model
+    }
+
+    /// The temperature in °C.
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    /// `IS` at the temperature of the transistor.
+    fn saturation_current(&self) -> f64 {
+        saturation_current(
+            self.model.saturation_current,
+            self.temperature,
+            self.model.energy_gap,
+            self.model.saturation_current_exponent,
+            1.0,
+        )
+    }
+
+    /// The base-emitter and base-collector voltages of the junctions, without the series
+    /// resistances, oriented so that they are positive when forward biased.
+    pub fn junction_voltages(&self, solution: &OperatingPoint) -> (f64, f64) {
+        let [collector, base, emitter] = self.internal;
+        let sign = self.kind.sign();
+
+        (
+            sign * solution.voltage_between(base, emitter),
+            sign * solution.voltage_between(base, collector),
+        )
+    }
+
+    /// The series resistances of the collector, base and emitter.
+    fn resistances(&self) -> [f64; 3] {
+        let model = &self.model;
+        [
+            model.collector_resistance,
+            model.base_resistance,
+            model.emitter_resistance,
+        ]
+    }
+
+    fn currents(&self, vbe: f64, vbc: f64) -> Currents {
+        let model = &self.model;
+        let forward_vt = model.forward_emission_coefficient * thermal_voltage(self.temperature);
+        let reverse_vt = model.reverse_emission_coefficient * thermal_voltage(self.temperature);
+
+        let saturation_current = self.saturation_current();
+
+        let exponential = (vbe / forward_vt).exp();
+        let base_emitter = saturation_current * (exponential - 1.0) + GMIN * vbe;
+        let g_be = saturation_current * exponential / forward_vt + GMIN;
+        let exponential = (vbc / reverse_vt).exp();
+        let base_collector = saturation_current * (exponential - 1.0) + GMIN * vbc;
+        let g_bc = saturation_current * exponential / reverse_vt + GMIN;
+
+        // The normalized base charge of the Gummel-Poon model, without high injection
+        let q_b =
+            1.0 / (1.0 - vbc / model.forward_early_voltage - vbe / model.reverse_early_voltage);
+        let dq_b_dvbe = q_b * q_b / model.reverse_early_voltage;
+        let dq_b_dvbc = q_b * q_b / model.forward_early_voltage;
+
+        let transport = (base_emitter - base_collector) / q_b;
+        let g_o = (g_bc + transport * dq_b_dvbc) / q_b;
+        let g_m = (g_be - transport * dq_b_dvbe) / q_b - g_o;
+
+        Currents {
+            base_emitter,
+            base_collector,
+            transport,
+            g_pi: g_be / model.forward_beta,
+            g_mu: g_bc / model.reverse_beta,
+            g_m,
+            g_o,
+            g_be,
+            g_bc,
+        }
+    }
+
+    /// The charges of the base-emitter and base-collector junctions and their capacitances,
+    /// from the depletion regions and the transit of the diode currents.
+    fn junction_charges(&self, vbe: f64, vbc: f64, currents: &Currents) -> [(f64, f64); 2] {
+        let model = &self.model;
+        let (emitter_charge, emitter_capacitance) = depletion(
+            vbe,
+            model.emitter_capacitance,
+            model.emitter_potential,
+            model.emitter_grading,
+        );
+        let (collector_charge, collector_capacitance) = depletion(
+            vbc,
+            model.collector_capacitance,
+            model.collector_potential,
+            model.collector_grading,
+        );
+
+        [
+            (
+                emitter_charge + model.forward_transit_time * currents.base_emitter,
+                emitter_capacitance + model.forward_transit_time * currents.g_be,
+            ),
+            (
+                collector_charge + model.reverse_transit_time * currents.base_collector,
+                collector_capacitance + model.reverse_transit_time * currents.g_bc,
+            ),
+        ]
+    }
+
+    /// The substrate-collector voltage, positive when forward biased, and the charge
+    /// and capacitance of that junction.
+    fn substrate_charge(&self, solution: &OperatingPoint) -> (f64, (f64, f64)) {
+        let model = &self.model;
+        let voltage =
+            self.kind.sign() * solution.voltage_between(self.terminals[3].node, self.internal[0]);
+
+        (
+            voltage,
+            depletion(
+                voltage,
+                model.substrate_capacitance,
+                model.substrate_potential,
+                model.substrate_grading,
+            ),
+        )
+    }
+
+    /// Stamps the series resistances between the terminals and the internal nodes.
+    fn stamp_resistances<T: nalgebra::ComplexField<RealField = f64>>(
+        &self,
+        context: &mut StampContext<T>,
+    ) {
+        for ((terminal, internal), resistance) in self
+            .terminals
+            .iter()
+            .zip(self.internal)
+            .zip(self.resistances())
+        {
+            if terminal.node != internal {
+                context.add_conductance(terminal.node, internal, T::from_real(resistance.recip()));
+            }
+        }
+    }
+
+    /// Limits the junction voltages of the `estimate` against the ones of the last iteration
+    /// kept in the `state`, and stamps the transistor linearized at them.
+    ///
+    /// Returns the limited junction voltages and the currents at them.
+    fn stamp_limited(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+    ) -> (f64, f64, Currents) {
+        let model = &self.model;
+        let (vbe, vbc) = self.junction_voltages(estimate);
+        let forward_vt = model.forward_emission_coefficient * thermal_voltage(self.temperature);
+        let reverse_vt = model.reverse_emission_coefficient * thermal_voltage(self.temperature);
+        let (vbe, limited_be) = limit(
+            vbe,
+            state[0],
+            forward_vt,
+            critical_voltage(self.saturation_current(), forward_vt),
+        );
+        let (vbc, limited_bc) = limit(
+            vbc,
+            state[1],
+            reverse_vt,
+            critical_voltage(self.saturation_current(), reverse_vt),
+        );
+        if limited_be || limited_bc {
+            context.mark_limited();
+        }
+        state[0] = vbe;
+        state[1] = vbc;
+
+        (vbe, vbc, self.stamp_junctions(context, vbe, vbc))
+    }
+
+    /// Stamps the transistor linearized at the junction voltages `vbe` and `vbc`,
+    /// returning the currents at them.
+    fn stamp_junctions(&self, context: &mut StampContext<f64>, vbe: f64, vbc: f64) -> Currents {
+        let [collector, base, emitter] = self.internal;
+        let model = &self.model;
+        let sign = self.kind.sign();
+
+        self.stamp_resistances(context);
+        let currents = self.currents(vbe, vbc);
+        let vce = vbe - vbc;
+
+        context.add_conductance(base, emitter, currents.g_pi);
+        let current = currents.base_emitter / model.forward_beta - currents.g_pi * vbe;
+        context.add_current(emitter, base, sign * current);
+
+        context.add_conductance(base, collector, currents.g_mu);
+        let current = currents.base_collector / model.reverse_beta - currents.g_mu * vbc;
+        context.add_current(collector, base, sign * current);
+
+        context.add_transconductance(collector, emitter, base, emitter, currents.g_m);
+        context.add_conductance(collector, emitter, currents.g_o);
+        let current = currents.transport - currents.g_m * vbe - currents.g_o * vce;
+        context.add_current(emitter, collector, sign * current);
+
+        currents
+    }
+}
+
+impl Element for Bjt {
+    fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    /// Stamps the transistor linearized with both junctions at 0V.
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        self.stamp_junctions(context, 0.0, 0.0);
+    }
+
+    fn is_nonlinear(&self) -> bool {
+        true
+    }
+
+    /// The base-emitter and base-collector voltages the transistor was last linearized at.
+    fn state_len(&self) -> usize {
+        2
+    }
+
+    fn initialize_state(&self, solution: &OperatingPoint, state: &mut [f64]) {
+        let (vbe, vbc) = self.junction_voltages(solution);
+        state[0] = vbe;
+        state[1] = vbc;
+    }
+
+    /// Stamps the hybrid-π model linearized at the limited junction voltages,
+    /// which is a conductance across each junction and a transconductance from the
+    /// collector to the emitter, along with the current sources that carry the currents
+    /// at those voltages.
+    fn stamp_linearized(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+    ) {
+        self.stamp_limited(context, estimate, state);
+    }
+
+    /// Stamps the transistor together with the companion models of the charges of its
+    /// junctions.
+    fn stamp_transient(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+        integration: &Integration,
+    ) {
+        let [collector, base, emitter] = self.internal;
+        let sign = self.kind.sign();
+
+        let (vbe, vbc, currents) = self.stamp_limited(context, estimate, state);
+        let charges = self.junction_charges(vbe, vbc, &currents);
+        for (index, ((node, voltage), charge)) in [(emitter, vbe), (collector, vbc)]
+            .into_iter()
+            .zip(charges)
+            .enumerate()
+        {
+            let (conductance, current) = integration.companion(index, voltage, charge);
+            context.add_conductance(base, node, conductance);
+            context.add_current(node, base, sign * current);
+        }
+
+        // The substrate junction only stores charge, so it is linearized without limiting
+        let substrate = self.terminals[3].node;
+        let (voltage, charge) = self.substrate_charge(estimate);
+        let (conductance, current) = integration.companion(2, voltage, charge);
+        context.add_conductance(substrate, collector, conductance);
+        context.add_current(collector, substrate, sign * current);
+    }
+
+    /// The charges of the base-emitter, base-collector and substrate junctions.
+    fn charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        let (vbe, vbc) = self.junction_voltages(solution);
+        let currents = self.currents(vbe, vbc);
+        let [(emitter, _), (collector, _)] = self.junction_charges(vbe, vbc, &currents);
+        let (_, (substrate, _)) = self.substrate_charge(solution);
+
+        vec![emitter, collector, substrate]
+    }
+
+    /// Stamps the hybrid-π model at the operating point, with the capacitance
+    /// of every junction.
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        operating_point: &OperatingPoint,
+        omega: f64,
+    ) {
+        let [collector, base, emitter] = self.internal;
+
+        let (vbe, vbc) = self.junction_voltages(operating_point);
+        let currents = self.currents(vbe, vbc);
+        let [(_, emitter_capacitance), (_, collector_capacitance)] =
+            self.junction_charges(vbe, vbc, &currents);
+        let (_, (_, substrate_capacitance)) = self.substrate_charge(operating_point);
+
+        self.stamp_resistances(context);
+        context.add_conductance(
+            base,
+            emitter,
+            Complex::new(currents.g_pi, omega * emitter_capacitance),
+        );
+        context.add_conductance(
+            base,
+            collector,
+            Complex::new(currents.g_mu, omega * collector_capacitance),
+        );
+        context.add_transconductance(
+            collector,
+            emitter,
+            base,
+            emitter,
+            Complex::new(currents.g_m, 0.0),
+        );
+        context.add_conductance(collector, emitter, Complex::new(currents.g_o, 0.0));
+        context.add_conductance(
+            self.terminals[3].node,
+            collector,
+            Complex::new(0.0, omega * substrate_capacitance),
+        );
+    }
+
+    /// A node behind each of the collector, base and emitter resistances that is not zero.
+    fn internal_node_count(&self) -> usize {
+        self.resistances()
+            .iter()
+            .filter(|resistance| **resistance > 0.0)
+            .count()
+    }
+
+    fn set_internal_nodes(&mut self, nodes: &[NodeId]) {
+        let mut nodes = nodes.iter();
+        let resistances = self.resistances();
+        for (internal, resistance) in self.internal.iter_mut().zip(resistances) {
+            if resistance > 0.0 {
+                *internal = *nodes.next().expect("a node for every resistance");
+            }
+        }
+    }
+
+    /// `temp`, the temperature in °C, and the parameters of the [`BjtModel`].
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "temp" => Some(self.temperature),
+            _ => self.model.parameter(name),
+        }
+    }
+
+    /// `rb`, `rc` and `re` cannot change between zero and a resistance, which would add
+    /// or remove an internal node.
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        if name == "temp" {
+            self.temperature = value;
+            return Ok(());
+        }
+        let parameter = self
+            .model
+            .parameter_mut(name)
+            .ok_or(ParameterError::Unknown(name.to_string()))?;
+        if matches!(name, "rb" | "rc" | "re") && (value > 0.0) != (*parameter > 0.0) {
+            return Err(ParameterError::Fixed(name.to_string()));
+        }
+        *parameter = value;
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn resistance(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::new(f64::INFINITY, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource, dc_current_source::DCCurrentSource,
+            dc_voltage_source::DCVoltageSource, junction::THERMAL_VOLTAGE, resistor::Resistor,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep, transient::transient},
+        Circuit, NodeId,
+    };
+
+    use super::{Bjt, BjtKind, BjtModel};
+
+    /// A common emitter stage with a 1kΩ collector resistor to a `supply`
+    /// and a `base_current` driven into the base.
+    ///
+    /// Returns the circuit, the collector and the base.
+    fn common_emitter(
+        kind: BjtKind,
+        model: BjtModel,
+        supply: f64,
+        base_current: f64,
+    ) -> (Circuit, NodeId, NodeId) {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vcc = circuit.push_node();
+        let collector = circuit.push_node();
+        let base = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(supply, vcc, ground)));
+        circuit.add_element(Box::new(Resistor::new(1e3, vcc, collector)));
+        circuit.add_element(Box::new(DCCurrentSource::new(base_current, base, ground)));
+        circuit.add_element(Box::new(
+            Bjt::new(kind, collector, base, ground).with_model(model),
+        ));
+
+        (circuit, collector, base)
+    }
+
+    /// In the forward active region the collector current is `BF` times the base current.
+    #[test]
+    fn forward_active() {
+        let (circuit, collector, base) =
+            common_emitter(BjtKind::Npn, BjtModel::default(), 10.0, 10e-6);
+
+        let op = dc_op(&circuit).unwrap();
+
+        assert_relative_eq!(op.voltage(collector), 9.0, max_relative = 1e-3);
+        let vbe = op.voltage(base);
+        assert_relative_eq!(
+            vbe,
+            THERMAL_VOLTAGE * (10e-6 * 100.0 / 1e-16f64).ln(),
+            epsilon = 1e-3
+        );
+    }
+
+    /// A PNP transistor is an NPN transistor with every voltage and current flipped.
+    #[test]
+    fn pnp() {
+        let (npn, npn_collector, npn_base) =
+            common_emitter(BjtKind::Npn, BjtModel::default(), 10.0, 10e-6);
+        let (pnp, pnp_collector, pnp_base) =
+            common_emitter(BjtKind::Pnp, BjtModel::default(), -10.0, -10e-6);
+
+        let (npn, pnp) = (dc_op(&npn).unwrap(), dc_op(&pnp).unwrap());
+
+        assert_relative_eq!(pnp.voltage(pnp_collector), -npn.voltage(npn_collector));
+        assert_relative_eq!(pnp.voltage(pnp_base), -npn.voltage(npn_base));
+    }
+
+    /// The collector current grows with the collector-emitter voltage by `1 - vbc/VAF`.
+    #[test]
+    fn early_effect() {
+        let model = BjtModel {
+            forward_early_voltage: 50.0,
+            ..BjtModel::default()
+        };
+        let (circuit, collector, base) = common_emitter(BjtKind::Npn, model, 10.0, 10e-6);
+
+        let op = dc_op(&circuit).unwrap();
+
+        let collector_current = (10.0 - op.voltage(collector)) / 1e3;
+        let vbc = op.voltage(base) - op.voltage(collector);
+        // The base-collector junction is reverse biased, so its current is negligible
+        assert_relative_eq!(
+            collector_current,
+            1e-3 * (1.0 - vbc / 50.0),
+            max_relative = 1e-3
+        );
+    }
+
+    /// Too much base current saturates the transistor, leaving little across it.
+    #[test]
+    fn saturation() {
+        let (circuit, collector, _) = common_emitter(BjtKind::Npn, BjtModel::default(), 10.0, 1e-3);
+
+        let op = dc_op(&circuit).unwrap();
+
+        assert!(op.voltage(collector) < 0.2);
+    }
+
+    /// The series resistances get their own internal nodes.
+    #[test]
+    fn series_resistances() {
+        let model = BjtModel {
+            base_resistance: 100.0,
+            emitter_resistance: 1.0,
+            ..BjtModel::default()
+        };
+        let (circuit, collector, base) = common_emitter(BjtKind::Npn, model, 10.0, 10e-6);
+        assert_eq!(circuit.node_count(), 6);
+
+        let op = dc_op(&circuit).unwrap();
+
+        assert_relative_eq!(op.voltage(collector), 9.0, max_relative = 1e-3);
+        // The base resistor drops 1mV and the emitter resistor about 1mV more
+        let (internal_base, internal_emitter) = (NodeId(4), NodeId(5));
+        assert_relative_eq!(
+            op.voltage(base) - op.voltage(internal_base),
+            1e-3,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(op.voltage(internal_emitter), 1.01e-3, epsilon = 1e-5);
+    }
+
+    /// The voltage gain of a common emitter stage is `-gm * RC` with `gm = Ic / Vt`,
+    /// falling off once the base-collector capacitance shorts the collector to the base.
+    #[test]
+    fn small_signal_gain() {
+        let model = BjtModel {
+            collector_capacitance: 1e-12,
+            ..BjtModel::default()
+        };
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vcc = circuit.push_node();
+        let collector = circuit.push_node();
+        let base = circuit.push_node();
+        let bias = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(10.0, vcc, ground)));
+        circuit.add_element(Box::new(Resistor::new(1e3, vcc, collector)));
+        circuit.add_element(Box::new(DCVoltageSource::new(0.7, bias, ground)));
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            base,
+            bias,
+        )));
+        circuit.add_element(Box::new(
+            Bjt::new(BjtKind::Npn, collector, base, ground).with_model(model),
+        ));
+
+        let sweep = Sweep::Linear {
+            start: 1e6,
+            stop: 1e6,
+            points: 1,
+        };
+        let op = dc_op(&circuit).unwrap();
+        let result = ac(&circuit, sweep).unwrap();
+
+        let gm = 1e-16 / THERMAL_VOLTAGE * (0.7 / THERMAL_VOLTAGE).exp();
+        let omega = TAU * 1e6;
+        let vbc = 0.7 - op.voltage(collector);
+        let capacitance = 1e-12 * (1.0 - vbc / 0.75).powf(-0.33);
+        // The capacitor feeds the input forward to the collector, in parallel with `-gm`
+        let expected =
+            Complex::new(-gm, omega * capacitance) / Complex::new(1e-3, omega * capacitance);
+        let actual = result.points[0].voltage(collector);
+        assert_relative_eq!(actual.re, expected.re, max_relative = 1e-3);
+        assert_relative_eq!(actual.im, expected.im, max_relative = 1e-3);
+    }
+
+    /// A transistor with charge in its junctions stays at its operating point
+    /// when nothing changes.
+    #[test]
+    fn transient_steady_state() {
+        let model = BjtModel {
+            emitter_capacitance: 1e-12,
+            collector_capacitance: 1e-12,
+            substrate_capacitance: 1e-12,
+            forward_transit_time: 1e-9,
+            ..BjtModel::default()
+        };
+        let (circuit, collector, _) = common_emitter(BjtKind::Npn, model, 10.0, 10e-6);
+
+        let result = transient(&circuit, 1e-9, 1e-7).unwrap();
+
+        for (_, point) in result.iter() {
+            assert_relative_eq!(point.voltage(collector), 9.0, max_relative = 1e-3);
+        }
+    }
+}
@@ -1,25 +1,44 @@
 use nalgebra::Complex;
 
-use crate::NodeId;
+use crate::{
+    runners::{integration::Integration, operating_point::OperatingPoint},
+    NodeId,
+};
 
-use super::{resistor::Resistor, Element, Terminal};
+use super::{stamp::StampContext, Element, ParameterError, Terminal};
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Capacitor {
-    capacitance: f32,
+    capacitance: f64,
+    /// The voltage a transient analysis that skips the operating point starts at, in Volts.
+    initial_voltage: Option<f64>,
     terminals: [Terminal; 2],
 }
 
 impl Capacitor {
-    pub fn new(capacitance: f32, positive_node: NodeId, negative_node: NodeId) -> Self {
+    pub fn new(capacitance: f64, positive_node: NodeId, negative_node: NodeId) -> Self {
         Self {
             capacitance,
+            initial_voltage: None,
             terminals: [
                 Terminal::new(positive_node, super::Polarity::Positive),
                 Terminal::new(negative_node, super::Polarity::Negative),
             ],
         }
     }
+
+    /// Starts a transient analysis that skips the operating point with `voltage`
+    /// across the capacitor, as with `IC=` in SPICE.
+    #[must_use]
+    pub fn with_initial_voltage(mut self, voltage: f64) -> Self {
+        self.initial_voltage = Some(voltage);
+        self
+    }
+
+    /// The initial voltage in Volts, if it is given.
+    pub fn initial_voltage(&self) -> Option<f64> {
+        self.initial_voltage
+    }
 }
 
 impl Element for Capacitor {
@@ -27,40 +46,101 @@ impl Element for Capacitor {
         &self.terminals
     }
 
-    fn stamp(&self, a_matrix: &mut Vec<f32>, z_vector: &mut Vec<f32>, n: usize, m: usize) {
-        let nodes: Vec<NodeId> = self.terminals().iter().map(|x| x.node).collect();
-        Resistor::new(f32::MAX, nodes[0], nodes[1]).stamp(a_matrix, z_vector, n, m);
+    /// A capacitor is an open circuit at DC, so nothing is stamped.
+    ///
+    /// Stamping a huge resistor instead would leave the matrix ill-conditioned.
+    fn stamp(&self, _context: &mut StampContext<f64>) {}
+
+    /// Stamps the companion model of the charge, which is a conductance of `C/h` in parallel
+    /// with a current source for backward Euler.
+    fn stamp_transient(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        _state: &mut [f64],
+        integration: &Integration,
+    ) {
+        let (positive, negative) = (self.terminals[0].node, self.terminals[1].node);
+        let voltage = estimate.voltage_between(positive, negative);
+        let (conductance, current) =
+            integration.companion(0, voltage, (self.capacitance * voltage, self.capacitance));
+
+        context.add_conductance(positive, negative, conductance);
+        context.add_current(negative, positive, current);
+    }
+
+    /// `C * v`
+    fn charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        let (positive, negative) = (self.terminals[0].node, self.terminals[1].node);
+        vec![self.capacitance * solution.voltage_between(positive, negative)]
     }
 
-    fn dc_voltage(&self) -> f32 {
+    /// `C * v` at the initial voltage, if it is given.
+    fn initial_charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        match self.initial_voltage {
+            Some(voltage) => vec![self.capacitance * voltage],
+            None => self.charges(solution),
+        }
+    }
+
+    /// `c`, the capacitance in Farads, and `ic`, the initial voltage in Volts if it is given.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "c" | "capacitance" => Some(self.capacitance),
+            "ic" => self.initial_voltage,
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "c" | "capacitance" => self.capacitance = value,
+            "ic" => self.initial_voltage = Some(value),
+            _ => return Err(ParameterError::Unknown(name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
         0.0
     }
 
-    fn ac_voltage(&self) -> Complex<f32> {
+    fn ac_voltage(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn dc_current(&self) -> f32 {
+    fn dc_current(&self) -> f64 {
         0.0
     }
 
-    fn ac_current(&self) -> Complex<f32> {
+    fn ac_current(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn resistance(&self) -> f32 {
-        f32::MAX
+    fn resistance(&self) -> f64 {
+        f64::INFINITY
     }
 
     /// The rectangular impedence of a capacitor is equal to `0 - j/Cω`,
-    /// where `C` is the capacitance in Farads and `ω` is the frequency in Hertz.
-    fn impedance(&self, frequency: f32) -> Complex<f32> {
-        Complex::new(0.0, -1.0 / (self.capacitance * frequency))
+    /// where `C` is the capacitance in Farads and `ω` is the angular frequency in radians
+    /// per second.
+    fn impedance_at_omega(&self, omega: f64) -> Complex<f64> {
+        Complex::new(0.0, -1.0 / (self.capacitance * omega))
+    }
+
+    /// `jωC`, which is stamped directly so that it is an open circuit at `ω = 0`
+    /// rather than the inverse of an infinite impedance.
+    fn admittance_at_omega(&self, omega: f64) -> Complex<f64> {
+        Complex::new(0.0, self.capacitance * omega)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::PI;
+
+    use approx::assert_relative_eq;
     use nalgebra::Complex;
 
     use crate::{elements::Element, NodeId};
@@ -72,8 +152,13 @@ mod tests {
     fn impedance() {
         let capacitor = Capacitor::new(10.0, NodeId(0), NodeId(1));
         assert_eq!(
-            capacitor.impedance(1000.0),
-            Complex::<f32>::new(0.0, -0.0001)
-        )
+            capacitor.impedance_at_omega(1000.0),
+            Complex::<f64>::new(0.0, -0.0001)
+        );
+        // A 1µF capacitor has a reactance of 159.15Ω at 1kHz
+        let capacitor = Capacitor::new(1e-6, NodeId(0), NodeId(1));
+        assert_relative_eq!(capacitor.impedance(1000.0).im, -159.154_943, epsilon = 1e-6);
+        assert_relative_eq!(capacitor.admittance(1000.0).im, 2.0 * PI * 1e-3);
+        assert_eq!(capacitor.admittance(0.0), Complex::ZERO);
     }
 }
@@ -0,0 +1,160 @@
+use nalgebra::{Complex, ComplexField};
+
+use crate::{runners::operating_point::OperatingPoint, BranchId, ElementId, NodeId};
+
+use super::{
+    stamp::{StampContext, Unknown},
+    Element, NoBranchCurrent, ParameterError, Terminal,
+};
+
+/// A current source of `gain` times the branch current of a control element,
+/// such as a [`DCVoltageSource`](super::dc_voltage_source::DCVoltageSource) or an
+/// [`Inductor`](super::inductor::Inductor), the `F` element of SPICE.
+///
+/// The current flows from the positive node, through the source, to the negative node.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct CurrentControlledCurrentSource {
+    gain: f64,
+    terminals: [Terminal; 2],
+    control: BranchId,
+}
+
+impl CurrentControlledCurrentSource {
+    /// Errors if the `control` element has no branch current, such as a resistor.
+    pub fn new(
+        gain: f64,
+        positive_node: NodeId,
+        negative_node: NodeId,
+        control: ElementId,
+    ) -> Result<Self, NoBranchCurrent> {
+        Ok(Self {
+            gain,
+            terminals: [
+                Terminal::new_positive(positive_node),
+                Terminal::new_negative(negative_node),
+            ],
+            control: control.branch().ok_or(NoBranchCurrent(control.index()))?,
+        })
+    }
+
+    /// Stamps `gain * i(control)` leaving the positive node and entering the negative node.
+    fn stamp_gain<T: ComplexField<RealField = f64>>(&self, context: &mut StampContext<T>) {
+        let control = Unknown::Branch(self.control);
+        let (positive, negative) = (self.terminals[0].node, self.terminals[1].node);
+
+        context.add(Unknown::Node(positive), control, T::from_real(self.gain));
+        context.add(Unknown::Node(negative), control, T::from_real(-self.gain));
+    }
+}
+
+impl Element for CurrentControlledCurrentSource {
+    fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        self.stamp_gain(context);
+    }
+
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        _omega: f64,
+    ) {
+        self.stamp_gain(context);
+    }
+
+    /// `gain`, the current gain.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "gain" => Some(self.gain),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "gain" => self.gain = value,
+            _ => return Err(ParameterError::Unknown(name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn resistance(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::new(f64::INFINITY, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource, dc_voltage_source::DCVoltageSource,
+            resistor::Resistor,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep},
+        Circuit,
+    };
+
+    use super::CurrentControlledCurrentSource;
+
+    /// A current mirror with a gain of 2, sensing the current through a 0V source.
+    #[test]
+    fn current_gain() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let bias = circuit.push_node();
+        let vin = circuit.push_node();
+        let sense = circuit.push_node();
+        let vout = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(1.0, bias, ground)));
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            vin,
+            bias,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, vin, sense)));
+        let ammeter = circuit.add_element(Box::new(DCVoltageSource::new(0.0, sense, ground)));
+        // Twice the input current flows from ground through the load into the output
+        circuit.add_element(Box::new(
+            CurrentControlledCurrentSource::new(2.0, vout, ground, ammeter).unwrap(),
+        ));
+        circuit.add_element(Box::new(Resistor::new(500.0, vout, ground)));
+
+        let op = dc_op(&circuit).unwrap();
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 1.0,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_relative_eq!(op.voltage(vout), -1.0);
+        assert_relative_eq!(result.points[0].voltage(vout).re, -1.0);
+    }
+}
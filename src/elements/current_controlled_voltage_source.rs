@@ -0,0 +1,197 @@
+use nalgebra::{Complex, ComplexField};
+
+use crate::{runners::operating_point::OperatingPoint, BranchId, ElementId, NodeId};
+
+use super::{
+    stamp::{StampContext, Unknown},
+    Element, NoBranchCurrent, ParameterError, Terminal,
+};
+
+/// A voltage source of `transresistance` times the branch current of a control element,
+/// such as a [`DCVoltageSource`](super::dc_voltage_source::DCVoltageSource) or an
+/// [`Inductor`](super::inductor::Inductor), the `H` element of SPICE.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct CurrentControlledVoltageSource {
+    transresistance: f64,
+    terminals: [Terminal; 2],
+    control: BranchId,
+    branch: BranchId,
+}
+
+impl CurrentControlledVoltageSource {
+    /// Errors if the `control` element has no branch current, such as a resistor.
+    pub fn new(
+        transresistance: f64,
+        positive_node: NodeId,
+        negative_node: NodeId,
+        control: ElementId,
+    ) -> Result<Self, NoBranchCurrent> {
+        Ok(Self {
+            transresistance,
+            terminals: [
+                Terminal::new_positive(positive_node),
+                Terminal::new_negative(negative_node),
+            ],
+            control: control.branch().ok_or(NoBranchCurrent(control.index()))?,
+            branch: BranchId::default(),
+        })
+    }
+
+    /// Stamps the equation of the branch,
+    /// `v(positive) - v(negative) - transresistance * i(control) = 0`.
+    fn stamp_transresistance<T: ComplexField<RealField = f64>>(
+        &self,
+        context: &mut StampContext<T>,
+    ) {
+        let (positive, negative) = (self.terminals[0].node, self.terminals[1].node);
+
+        context.add_branch(self.branch, positive, negative);
+        context.add(
+            Unknown::Branch(self.branch),
+            Unknown::Branch(self.control),
+            T::from_real(-self.transresistance),
+        );
+    }
+}
+
+impl Element for CurrentControlledVoltageSource {
+    fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        self.stamp_transresistance(context);
+    }
+
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        _omega: f64,
+    ) {
+        self.stamp_transresistance(context);
+    }
+
+    fn is_b_c_element(&self) -> bool {
+        true
+    }
+
+    fn set_branch(&mut self, branch: BranchId) {
+        self.branch = branch;
+    }
+
+    /// `gain`, the transresistance in Ohms.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "gain" | "transresistance" => Some(self.transresistance),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "gain" | "transresistance" => self.transresistance = value,
+            _ => return Err(ParameterError::Unknown(name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn resistance(&self) -> f64 {
+        0.0
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource, dc_voltage_source::DCVoltageSource,
+            inductor::Inductor, resistor::Resistor,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep},
+        Circuit,
+    };
+
+    use super::{CurrentControlledVoltageSource, NoBranchCurrent};
+
+    /// The currents through a 0V source and an inductor, converted to voltages.
+    #[test]
+    fn transresistance() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let bias = circuit.push_node();
+        let vin = circuit.push_node();
+        let sense = circuit.push_node();
+        let vout = circuit.push_node();
+        let inverted = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(2.0, bias, ground)));
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            vin,
+            bias,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, vin, sense)));
+        let ammeter = circuit.add_element(Box::new(DCVoltageSource::new(0.0, sense, ground)));
+        let choke = circuit.push_node();
+        circuit.add_element(Box::new(Resistor::new(1e3, vin, choke)));
+        let inductor = circuit.add_element(Box::new(Inductor::new(1e-3, choke, ground)));
+        circuit.add_element(Box::new(
+            CurrentControlledVoltageSource::new(1e3, vout, ground, ammeter).unwrap(),
+        ));
+        circuit.add_element(Box::new(
+            CurrentControlledVoltageSource::new(-1.0, inverted, ground, inductor).unwrap(),
+        ));
+
+        let op = dc_op(&circuit).unwrap();
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 1.0,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_relative_eq!(op.voltage(vout).abs(), 2.0);
+        assert_relative_eq!(op.voltage(vout), 1e3 * op.branch_current(ammeter).unwrap());
+        assert_relative_eq!(op.voltage(inverted), -op.branch_current(inductor).unwrap());
+        let current = result.points[0].branch_current(ammeter).unwrap();
+        assert_relative_eq!(result.points[0].voltage(vout).re, 1e3 * current.re);
+        assert_relative_eq!(result.points[0].voltage(vout).im, 1e3 * current.im);
+    }
+
+    #[test]
+    fn control_without_branch() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vin = circuit.push_node();
+        let resistor = circuit.add_element(Box::new(Resistor::new(1.0, vin, ground)));
+
+        assert_eq!(
+            CurrentControlledVoltageSource::new(1.0, vin, ground, resistor).err(),
+            Some(NoBranchCurrent(0))
+        );
+    }
+}
@@ -1,20 +1,25 @@
 use nalgebra::Complex;
 
-use crate::NodeId;
+use crate::{runners::operating_point::OperatingPoint, NodeId};
 
-use super::{Element, Terminal};
+use super::{
+    source::Source, stamp::StampContext, waveform::Waveform, Element, ParameterError, Terminal,
+};
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 pub struct DCCurrentSource {
-    current: f32,
+    source: Source,
     terminals: [Terminal; 2],
-    resistance: f32,
+    resistance: f64,
 }
 
 impl DCCurrentSource {
-    pub fn new(current: f32, positive_node: NodeId, negative_node: NodeId) -> Self {
+    pub fn new(current: f64, positive_node: NodeId, negative_node: NodeId) -> Self {
         Self {
-            current,
+            source: Source {
+                dc: current,
+                ..Source::default()
+            },
             terminals: [
                 Terminal::new(positive_node, super::Polarity::Positive),
                 Terminal::new(negative_node, super::Polarity::Negative),
@@ -24,10 +29,29 @@ impl DCCurrentSource {
     }
 
     #[must_use]
-    pub fn with_resistance(mut self, resistance: f32) -> Self {
+    pub fn with_resistance(mut self, resistance: f64) -> Self {
         self.resistance = resistance;
         self
     }
+
+    /// Sets the DC current, such as to sweep it.
+    pub fn set_dc(&mut self, current: f64) {
+        self.source.dc = current;
+    }
+
+    /// Adds a small-signal current of `magnitude` with a `phase` in degrees in an AC analysis.
+    #[must_use]
+    pub fn with_ac(mut self, magnitude: f64, phase: f64) -> Self {
+        self.source.set_ac(magnitude, phase);
+        self
+    }
+
+    /// Follows the `waveform` during a transient analysis instead of the DC current.
+    #[must_use]
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.source.waveform = Some(waveform);
+        self
+    }
 }
 
 impl Element for DCCurrentSource {
@@ -35,41 +59,58 @@ impl Element for DCCurrentSource {
         &self.terminals
     }
 
-    fn stamp(&self, _a_matrix: &mut Vec<f32>, z_vector: &mut Vec<f32>, _n: usize, _m: usize) {
-        let terminal_1 = self.terminals()[0];
-        let terminal_2 = self.terminals()[1];
-        let node_1 = terminal_1.node.0;
-        let node_2 = terminal_2.node.0;
+    /// Stamps its current flowing into the positive node onto the z_vector.
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        let terminals = self.terminals();
+        let current = self.source.value(context.time());
+        context.add_current(terminals[0].node, terminals[1].node, current);
+    }
 
-        if node_1 > 0 {
-            z_vector[node_1 - 1] += terminal_1.sign() * self.dc_current();
-        }
-        if node_2 > 0 {
-            z_vector[node_2 - 1] += terminal_2.sign() * self.dc_current();
-        }
+    /// Stamps its AC current flowing into the positive node, which is an open circuit without one.
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        _omega: f64,
+    ) {
+        let terminals = self.terminals();
+        context.add_current(terminals[0].node, terminals[1].node, self.ac_current());
+    }
+
+    fn breakpoints(&self, stop: f64) -> Vec<f64> {
+        self.source.breakpoints(stop)
     }
 
-    fn dc_voltage(&self) -> f32 {
+    /// `dc`, `acmag` and `acphase`, the DC value and the magnitude and phase of the AC phasor.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        self.source.parameter(name)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        self.source.set_parameter(name, value)
+    }
+
+    fn dc_voltage(&self) -> f64 {
         0.0
     }
 
-    fn ac_voltage(&self) -> Complex<f32> {
+    fn ac_voltage(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn dc_current(&self) -> f32 {
-        self.current
+    fn dc_current(&self) -> f64 {
+        self.source.dc
     }
 
-    fn ac_current(&self) -> Complex<f32> {
-        Complex::ZERO
+    fn ac_current(&self) -> Complex<f64> {
+        self.source.ac
     }
 
-    fn resistance(&self) -> f32 {
+    fn resistance(&self) -> f64 {
         self.resistance
     }
 
-    fn impedance(&self, _frequency: f32) -> Complex<f32> {
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
         Complex::ZERO
     }
 }
@@ -1,27 +1,51 @@
 use nalgebra::Complex;
 
-use crate::NodeId;
+use crate::{runners::operating_point::OperatingPoint, BranchId, NodeId};
 
-use super::{Element, Terminal};
+use super::{
+    source::Source, stamp::StampContext, waveform::Waveform, Element, ParameterError, Terminal,
+};
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 pub struct DCVoltageSource {
-    voltage: f32,
+    source: Source,
     terminals: [Terminal; 2],
-    index: usize,
+    branch: BranchId,
 }
 
 impl DCVoltageSource {
-    pub fn new(voltage: f32, positive_node: NodeId, negative_node: NodeId, index: usize) -> Self {
+    pub fn new(voltage: f64, positive_node: NodeId, negative_node: NodeId) -> Self {
         Self {
-            voltage,
+            source: Source {
+                dc: voltage,
+                ..Source::default()
+            },
             terminals: [
                 Terminal::new(positive_node, super::Polarity::Positive),
                 Terminal::new(negative_node, super::Polarity::Negative),
             ],
-            index,
+            branch: BranchId::default(),
         }
     }
+
+    /// Sets the DC voltage, such as to sweep it.
+    pub fn set_dc(&mut self, voltage: f64) {
+        self.source.dc = voltage;
+    }
+
+    /// Adds a small-signal voltage of `magnitude` with a `phase` in degrees in an AC analysis.
+    #[must_use]
+    pub fn with_ac(mut self, magnitude: f64, phase: f64) -> Self {
+        self.source.set_ac(magnitude, phase);
+        self
+    }
+
+    /// Follows the `waveform` during a transient analysis instead of the DC voltage.
+    #[must_use]
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.source.waveform = Some(waveform);
+        self
+    }
 }
 
 impl Element for DCVoltageSource {
@@ -31,78 +55,67 @@ impl Element for DCVoltageSource {
 
     /// Stamps itself onto the B and C matrix, which are both apart of the A matrix,
     /// and onto the z_vector.
-    fn stamp(&self, a_matrix: &mut Vec<f32>, z_vector: &mut Vec<f32>, n: usize, m: usize) {
-        let terminal_1 = self.terminals()[0];
-        let terminal_2 = self.terminals()[1];
-        let node_1 = terminal_1.node.0;
-        let node_2 = terminal_2.node.0;
-
-        let n = n - 1;
-        match (node_1 > 0, node_2 > 0) {
-            // Not connected to ground
-            (true, true) => {
-                // B matrix
-                a_matrix[(n + m) * (n + self.index) + node_1 - 1] += terminal_1.sign();
-                a_matrix[(n + m) * (n + self.index) + node_2 - 1] += terminal_2.sign();
-
-                // C matrix
-                a_matrix[(n + m) * (node_1 - 1) + n + self.index] += terminal_1.sign();
-                a_matrix[(n + m) * (node_2 - 1) + n + self.index] += terminal_2.sign();
-
-                // z vector
-                z_vector[n + self.index] = self.dc_voltage();
-            }
-            // Only node 2 is connected to ground
-            (true, false) => {
-                // B matrix
-                a_matrix[(n + m) * (n + self.index) + node_1 - 1] += terminal_1.sign();
-
-                // C matrix
-                a_matrix[(n + m) * (node_1 - 1) + n + self.index] += terminal_1.sign();
-
-                // z vector
-                z_vector[n + self.index] = self.dc_voltage();
-            }
-            // Only node 1 is connected to ground
-            (false, true) => {
-                // B matrix
-                a_matrix[(n + m) * (n + self.index) + node_2 - 1] += terminal_2.sign();
-
-                // C matrix
-                a_matrix[(n + m) * (node_2 - 1) + n + self.index] += terminal_2.sign();
-
-                // z vector
-                z_vector[n + self.index] = self.dc_voltage();
-            }
-            _ => (),
-        }
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        let terminals = self.terminals();
+        let voltage = self.source.value(context.time());
+        context.add_branch(self.branch, terminals[0].node, terminals[1].node);
+        context.add_branch_voltage(self.branch, voltage);
+    }
+
+    /// Without an AC voltage, the source is stamped as a short circuit.
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        _omega: f64,
+    ) {
+        let terminals = self.terminals();
+        context.add_branch(self.branch, terminals[0].node, terminals[1].node);
+        context.add_branch_voltage(self.branch, self.ac_voltage());
     }
 
     fn is_b_c_element(&self) -> bool {
         true
     }
 
-    fn dc_voltage(&self) -> f32 {
-        self.voltage
+    fn set_branch(&mut self, branch: BranchId) {
+        self.branch = branch;
     }
 
-    fn ac_voltage(&self) -> Complex<f32> {
-        Complex::ZERO
+    fn breakpoints(&self, stop: f64) -> Vec<f64> {
+        self.source.breakpoints(stop)
+    }
+
+    /// `dc`, `acmag` and `acphase`, the DC value and the magnitude and phase of the AC phasor.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        self.source.parameter(name)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        self.source.set_parameter(name, value)
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        self.source.dc
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        self.source.ac
     }
 
-    fn dc_current(&self) -> f32 {
+    fn dc_current(&self) -> f64 {
         0.0
     }
 
-    fn ac_current(&self) -> Complex<f32> {
+    fn ac_current(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn resistance(&self) -> f32 {
+    fn resistance(&self) -> f64 {
         0.0
     }
 
-    fn impedance(&self, _frequency: f32) -> Complex<f32> {
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
         Complex::ZERO
     }
 }
@@ -0,0 +1,623 @@
+use nalgebra::Complex;
+
+use crate::{
+    runners::{integration::Integration, operating_point::OperatingPoint},
+    NodeId,
+};
+
+use super::{
+    junction::{
+        critical_voltage, depletion, limit, saturation_current, thermal_voltage, GMIN,
+        NOMINAL_TEMPERATURE,
+    },
+    stamp::StampContext,
+    Element, ParameterError, Terminal,
+};
+
+/// The parameters of a [`Diode`], named after their SPICE `.model` parameters.
+///
+/// The defaults are the same as in SPICE.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiodeModel {
+    /// `IS`, the saturation current in Amperes.
+    pub saturation_current: f64,
+    /// `N`, the emission coefficient.
+    pub emission_coefficient: f64,
+    /// `RS`, the series resistance in Ohms.
+    pub series_resistance: f64,
+    /// `CJO`, the zero-bias junction capacitance in Farads.
+    pub junction_capacitance: f64,
+    /// `VJ`, the junction potential in Volts.
+    pub junction_potential: f64,
+    /// `M`, the grading coefficient of the junction.
+    pub grading_coefficient: f64,
+    /// `TT`, the transit time in seconds, which gives the diffusion capacitance.
+    pub transit_time: f64,
+    /// `BV`, the reverse breakdown voltage in Volts, infinite for no breakdown.
+    pub breakdown_voltage: f64,
+    /// `IBV`, the reverse current at the breakdown voltage in Amperes.
+    pub breakdown_current: f64,
+    /// `EG`, the energy gap in electronvolts, which sets how fast the saturation current
+    /// grows with temperature.
+    pub energy_gap: f64,
+    /// `XTI`, the temperature exponent of the saturation current.
+    pub saturation_current_exponent: f64,
+}
+
+impl Default for DiodeModel {
+    fn default() -> Self {
+        Self {
+            saturation_current: 1e-14,
+            emission_coefficient: 1.0,
+            series_resistance: 0.0,
+            junction_capacitance: 0.0,
+            junction_potential: 1.0,
+            grading_coefficient: 0.5,
+            transit_time: 0.0,
+            breakdown_voltage: f64::INFINITY,
+            breakdown_current: 1e-3,
+            energy_gap: 1.11,
+            saturation_current_exponent: 3.0,
+        }
+    }
+}
+
+impl DiodeModel {
+    /// The value of the parameter with the lowercase SPICE `name`, such as `is`.
+    pub fn parameter(&self, name: &str) -> Option<f64> {
+        let mut model = *self;
+        model.parameter_mut(name).copied()
+    }
+
+    /// The parameter with the lowercase SPICE `name`, such as `is`, to change.
+    pub fn parameter_mut(&mut self, name: &str) -> Option<&mut f64> {
+        Some(match name {
+            "is" => &mut self.saturation_current,
+            "n" => &mut self.emission_coefficient,
+            "rs" => &mut self.series_resistance,
+            "cjo" | "cj0" => &mut self.junction_capacitance,
+            "vj" => &mut self.junction_potential,
+            "m" => &mut self.grading_coefficient,
+            "tt" => &mut self.transit_time,
+            "bv" => &mut self.breakdown_voltage,
+            "ibv" => &mut self.breakdown_current,
+            "eg" => &mut self.energy_gap,
+            "xti" => &mut self.saturation_current_exponent,
+            _ => return None,
+        })
+    }
+}
+
+/// A pn junction diode following the Shockley equation, with a series resistance,
+/// junction and diffusion capacitance and reverse breakdown.
+///
+/// The current flows from the anode to the cathode. The saturation current and the thermal
+/// voltage follow the temperature of the diode, which is 27°C unless it is given.
+#[derive(Debug, Clone, Copy)]
+pub struct Diode {
+    model: DiodeModel,
+    /// In °C.
+    temperature: f64,
+    terminals: [Terminal; 2],
+    /// The anode side of the junction, which is an internal node
+    /// when there is a series resistance and the anode otherwise.
+    junction: NodeId,
+}
+
+impl Default for Diode {
+    fn default() -> Self {
+        Self::new(NodeId::default(), NodeId::default())
+    }
+}
+
+impl Diode {
+    pub fn new(anode: NodeId, cathode: NodeId) -> Self {
+        Self {
+            model: DiodeModel::default(),
+            temperature: NOMINAL_TEMPERATURE,
+            terminals: [
+                Terminal::new(anode, super::Polarity::Positive),
+                Terminal::new(cathode, super::Polarity::Negative),
+            ],
+            junction: anode,
+        }
+    }
+
+    /// Sets the parameters of the diode, before it is added to the circuit.
+    #[must_use]
+    pub fn with_model(mut self, model: DiodeModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Sets the temperature of the diode in °C.
+    #[must_use]
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn model(&self) -> &DiodeModel {
+        &self.model
+    }
+
+    /// The temperature in °C.
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    /// The voltage across the junction, without the series resistance.
+    pub fn junction_voltage(&self, solution: &OperatingPoint) -> f64 {
+        solution.voltage_between(self.junction, self.terminals[1].node)
+    }
+
+    /// `N * kT/q`
+    fn thermal_voltage(&self) -> f64 {
+        self.model.emission_coefficient * thermal_voltage(self.temperature)
+    }
+
+    /// `IS` at the temperature of the diode.
+    fn saturation_current(&self) -> f64 {
+        let model = &self.model;
+        saturation_current(
+            model.saturation_current,
+            self.temperature,
+            model.energy_gap,
+            model.saturation_current_exponent,
+            model.emission_coefficient,
+        )
+    }
+
+    /// The breakdown voltage, shifted so that the current at `BV` is `IBV`.
+    fn breakdown_knee(&self) -> f64 {
+        let model = &self.model;
+        model.breakdown_voltage
+            - self.thermal_voltage() * (model.breakdown_current / self.saturation_current()).ln()
+    }
+
+    /// The current through the junction and its derivative at `voltage`,
+    /// including [`GMIN`].
+    ///
+    /// Below `-3 * N * kT/q` the exponential is replaced by a smooth cubic,
+    /// until the current grows exponentially again past the breakdown voltage.
+    fn current(&self, voltage: f64) -> (f64, f64) {
+        let vt = self.thermal_voltage();
+        let saturation_current = self.saturation_current();
+
+        let (current, conductance) = if voltage >= -3.0 * vt {
+            let exponential = (voltage / vt).exp();
+            (
+                saturation_current * (exponential - 1.0),
+                saturation_current * exponential / vt,
+            )
+        } else if voltage >= -self.breakdown_knee() {
+            let cubic = (3.0 * vt / (voltage * std::f64::consts::E)).powi(3);
+            (
+                -saturation_current * (1.0 + cubic),
+                3.0 * saturation_current * cubic / voltage,
+            )
+        } else {
+            let exponential = (-(self.breakdown_knee() + voltage) / vt).exp();
+            (
+                -saturation_current * exponential,
+                saturation_current * exponential / vt,
+            )
+        };
+
+        (current + GMIN * voltage, conductance + GMIN)
+    }
+
+    /// The charge stored in the junction and its capacitance at `voltage`,
+    /// from the depletion region and the diffusion of the `current`.
+    fn charge(&self, voltage: f64, current: f64, conductance: f64) -> (f64, f64) {
+        let model = &self.model;
+        let (charge, capacitance) = depletion(
+            voltage,
+            model.junction_capacitance,
+            model.junction_potential,
+            model.grading_coefficient,
+        );
+
+        (
+            charge + model.transit_time * current,
+            capacitance + model.transit_time * conductance,
+        )
+    }
+
+    /// Limits the junction voltage of the `estimate` against the one of the last iteration
+    /// kept in the `state`, and stamps the series resistance and the junction linearized at it.
+    ///
+    /// Returns the limited junction voltage.
+    fn stamp_junction(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+    ) -> f64 {
+        let (anode, cathode) = (self.terminals[0].node, self.terminals[1].node);
+        let vt = self.thermal_voltage();
+        let critical = critical_voltage(self.saturation_current(), vt);
+
+        let voltage = self.junction_voltage(estimate);
+        let knee = self.breakdown_knee();
+        let (voltage, limited) = if voltage < (-knee + 10.0 * vt).min(0.0) {
+            // Limit the step past the breakdown voltage the same way as a forward step
+            let (reverse, limited) = limit(-(voltage + knee), -(state[0] + knee), vt, critical);
+            (-(reverse + knee), limited)
+        } else {
+            limit(voltage, state[0], vt, critical)
+        };
+        if limited {
+            context.mark_limited();
+        }
+        state[0] = voltage;
+
+        if self.junction != anode {
+            context.add_conductance(anode, self.junction, self.model.series_resistance.recip());
+        }
+        let (current, conductance) = self.current(voltage);
+        context.add_conductance(self.junction, cathode, conductance);
+        context.add_current(cathode, self.junction, current - conductance * voltage);
+
+        voltage
+    }
+}
+
+impl Element for Diode {
+    fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    /// Stamps the diode linearized at 0V.
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        let (anode, cathode) = (self.terminals[0].node, self.terminals[1].node);
+        if self.junction != anode {
+            context.add_conductance(anode, self.junction, self.model.series_resistance.recip());
+        }
+        let (_, conductance) = self.current(0.0);
+        context.add_conductance(self.junction, cathode, conductance);
+    }
+
+    fn is_nonlinear(&self) -> bool {
+        true
+    }
+
+    /// The junction voltage the diode was last linearized at.
+    fn state_len(&self) -> usize {
+        1
+    }
+
+    fn initialize_state(&self, solution: &OperatingPoint, state: &mut [f64]) {
+        state[0] = self.junction_voltage(solution);
+    }
+
+    /// Stamps the junction as a conductance of `dI/dV` in parallel with a current source,
+    /// so that together they carry the diode current at the limited junction voltage.
+    fn stamp_linearized(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+    ) {
+        self.stamp_junction(context, estimate, state);
+    }
+
+    /// Stamps the junction together with the companion model of its charge,
+    /// linearized at the same voltage.
+    fn stamp_transient(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+        integration: &Integration,
+    ) {
+        let voltage = self.stamp_junction(context, estimate, state);
+        let cathode = self.terminals[1].node;
+
+        let (current, conductance) = self.current(voltage);
+        let charge = self.charge(voltage, current, conductance);
+
+        let (conductance, current) = integration.companion(0, voltage, charge);
+        context.add_conductance(self.junction, cathode, conductance);
+        context.add_current(cathode, self.junction, current);
+    }
+
+    /// The depletion and diffusion charge of the junction.
+    fn charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        let voltage = self.junction_voltage(solution);
+        let (current, conductance) = self.current(voltage);
+        vec![self.charge(voltage, current, conductance).0]
+    }
+
+    /// Stamps the small-signal conductance and capacitance of the junction
+    /// at the operating point, behind the series resistance.
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        operating_point: &OperatingPoint,
+        omega: f64,
+    ) {
+        let (anode, cathode) = (self.terminals[0].node, self.terminals[1].node);
+        if self.junction != anode {
+            let conductance = self.model.series_resistance.recip();
+            context.add_conductance(anode, self.junction, Complex::new(conductance, 0.0));
+        }
+
+        let voltage = self.junction_voltage(operating_point);
+        let (current, conductance) = self.current(voltage);
+        let (_, capacitance) = self.charge(voltage, current, conductance);
+        context.add_conductance(
+            self.junction,
+            cathode,
+            Complex::new(conductance, omega * capacitance),
+        );
+    }
+
+    /// A node between the series resistance and the junction, if there is a resistance.
+    fn internal_node_count(&self) -> usize {
+        usize::from(self.model.series_resistance > 0.0)
+    }
+
+    fn set_internal_nodes(&mut self, nodes: &[NodeId]) {
+        self.junction = nodes[0];
+    }
+
+    /// `temp`, the temperature in °C, and the parameters of the [`DiodeModel`].
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "temp" => Some(self.temperature),
+            _ => self.model.parameter(name),
+        }
+    }
+
+    /// `rs` cannot change between zero and a resistance, which would add or remove
+    /// the internal node.
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        if name == "temp" {
+            self.temperature = value;
+            return Ok(());
+        }
+        if name == "rs" && (value > 0.0) != (self.model.series_resistance > 0.0) {
+            return Err(ParameterError::Fixed(name.to_string()));
+        }
+        let parameter = self
+            .model
+            .parameter_mut(name)
+            .ok_or(ParameterError::Unknown(name.to_string()))?;
+        *parameter = value;
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn resistance(&self) -> f64 {
+        self.model.series_resistance
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::new(self.model.series_resistance, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use approx::assert_relative_eq;
+    use nalgebra::{Complex, DVector};
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource,
+            capacitor::Capacitor,
+            dc_voltage_source::DCVoltageSource,
+            junction::{GMIN, THERMAL_VOLTAGE},
+            resistor::Resistor,
+            Element, ParameterError,
+        },
+        runners::{
+            ac::ac,
+            dc_op::dc_op,
+            integration::Method,
+            node_labels,
+            operating_point::OperatingPoint,
+            sweep::Sweep,
+            transient::{integrate, TimeSpan},
+        },
+        Circuit, NodeId,
+    };
+
+    use super::{Diode, DiodeModel};
+
+    /// `IS * (exp(v/Vt) - 1)` of the default model, for forward and slightly reverse voltages.
+    fn shockley(voltage: f64) -> f64 {
+        1e-14 * ((voltage / THERMAL_VOLTAGE).exp() - 1.0) + GMIN * voltage
+    }
+
+    /// A diode in series with a resistor across a `voltage` source,
+    /// returning the circuit and the node between them.
+    fn biased(voltage: f64, model: DiodeModel) -> (Circuit, NodeId) {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let supply = circuit.push_node();
+        let anode = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(voltage, supply, ground)));
+        circuit.add_element(Box::new(Resistor::new(1e3, supply, anode)));
+        circuit.add_element(Box::new(Diode::new(anode, ground).with_model(model)));
+
+        (circuit, anode)
+    }
+
+    /// 5V through 1kΩ settles where the resistor and the diode carry the same current.
+    #[test]
+    fn forward_bias() {
+        let (circuit, anode) = biased(5.0, DiodeModel::default());
+
+        let op = dc_op(&circuit).unwrap();
+
+        let voltage = op.voltage(anode);
+        assert!(voltage > 0.6 && voltage < 0.8);
+        assert_relative_eq!(
+            (5.0 - voltage) / 1e3,
+            shockley(voltage),
+            max_relative = 1e-3
+        );
+    }
+
+    /// A reverse biased diode only lets the saturation current through.
+    #[test]
+    fn reverse_bias() {
+        let (circuit, anode) = biased(-5.0, DiodeModel::default());
+
+        let op = dc_op(&circuit).unwrap();
+
+        assert_relative_eq!(op.voltage(anode), -5.0, epsilon = 1e-6);
+    }
+
+    /// Past the breakdown voltage the current grows exponentially, reaching `IBV` at `BV`.
+    #[test]
+    fn breakdown() {
+        let model = DiodeModel {
+            breakdown_voltage: 5.1,
+            ..DiodeModel::default()
+        };
+        let (circuit, anode) = biased(-10.0, model);
+
+        let op = dc_op(&circuit).unwrap();
+
+        let voltage = op.voltage(anode);
+        let current = (-10.0 - voltage) / 1e3;
+        let expected = -(5.1 + THERMAL_VOLTAGE * (-current / 1e-3).ln());
+        assert_relative_eq!(voltage, expected, epsilon = 1e-3);
+    }
+
+    /// The series resistance gets its own internal node, so the junction sees
+    /// the supply minus the drop across the resistance.
+    #[test]
+    fn series_resistance() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let anode = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(1.0, anode, ground)));
+        let model = DiodeModel {
+            series_resistance: 10.0,
+            ..DiodeModel::default()
+        };
+        circuit.add_element(Box::new(Diode::new(anode, ground).with_model(model)));
+        assert_eq!(circuit.node_count(), 3);
+
+        let op = dc_op(&circuit).unwrap();
+
+        let junction = op.voltage(NodeId(2));
+        assert_relative_eq!(
+            (1.0 - junction) / 10.0,
+            shockley(junction),
+            max_relative = 1e-3
+        );
+    }
+
+    /// The small-signal model is the conductance at the operating point
+    /// in parallel with the junction capacitance.
+    #[test]
+    fn small_signal() {
+        let model = DiodeModel {
+            junction_capacitance: 1e-9,
+            ..DiodeModel::default()
+        };
+        let (mut circuit, anode) = biased(5.0, model);
+        let ground = NodeId(0);
+        let input = circuit.push_node();
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            input,
+            ground,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, input, anode)));
+
+        let op = dc_op(&circuit).unwrap();
+        let frequency = 1e6;
+        let sweep = Sweep::Linear {
+            start: frequency,
+            stop: frequency,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        let voltage = op.voltage(anode);
+        let conductance = 1e-14 / THERMAL_VOLTAGE * (voltage / THERMAL_VOLTAGE).exp() + GMIN;
+        // Above half of VJ the capacitance is extrapolated along its tangent
+        let capacitance = 1e-9 * (0.25 + 0.5 * voltage) / 0.5f64.powf(1.5);
+        let admittance = Complex::new(conductance + 2e-3, TAU * frequency * capacitance);
+        let expected = Complex::new(1e-3, 0.0) / admittance;
+        let actual = result.points[0].voltage(anode);
+        assert_relative_eq!(actual.re, expected.re, max_relative = 1e-9);
+        assert_relative_eq!(actual.im, expected.im, max_relative = 1e-9);
+    }
+
+    /// A charged 1µF capacitor discharging through a diode,
+    /// where every backward Euler step balances the capacitor and diode currents.
+    #[test]
+    fn capacitor_discharge() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let anode = circuit.push_node();
+        circuit.add_element(Box::new(Capacitor::new(1e-6, anode, ground)));
+        circuit.add_element(Box::new(Diode::new(anode, ground)));
+        circuit.options.method = Method::BackwardEuler;
+
+        let initial = OperatingPoint::new(node_labels(&circuit), DVector::from_vec(vec![0.8]));
+        let result = integrate(&circuit, initial, &TimeSpan::new(1e-6, 1e-4)).unwrap();
+
+        for (times, points) in result.times.windows(2).zip(result.points.windows(2)) {
+            let (previous, next) = (points[0].voltage(anode), points[1].voltage(anode));
+            assert!(next < previous);
+            assert_relative_eq!(
+                1e-6 * (previous - next) / (times[1] - times[0]),
+                shockley(next),
+                max_relative = 1e-3
+            );
+        }
+        assert!(result.points.last().unwrap().voltage(anode) < 0.65);
+    }
+
+    /// The model parameters are reached by their SPICE names, and the series resistance
+    /// cannot be taken away once it has an internal node.
+    #[test]
+    fn parameters() {
+        let model = DiodeModel {
+            series_resistance: 10.0,
+            ..DiodeModel::default()
+        };
+        let mut diode = Diode::new(NodeId(1), NodeId(0)).with_model(model);
+
+        assert_eq!(diode.parameter("temp"), Some(27.0));
+        diode.set_parameter("temp", 50.0).unwrap();
+        assert_eq!(diode.temperature(), 50.0);
+        diode.set_parameter("is", 1e-12).unwrap();
+        assert_eq!(diode.parameter("is"), Some(1e-12));
+        diode.set_parameter("rs", 20.0).unwrap();
+        assert_eq!(
+            diode.set_parameter("rs", 0.0),
+            Err(ParameterError::Fixed("rs".to_string()))
+        );
+        assert_eq!(
+            diode.set_parameter("w", 1.0),
+            Err(ParameterError::Unknown("w".to_string()))
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use thiserror::Error;
+
+use crate::{Circuit, ElementId, NodeId};
+
+use super::{inductor::Inductor, mutual_inductance::MutualInductance};
+
+/// A lossless transformer with a `turns_ratio` of primary to secondary turns `N1/N2`, made of
+/// two [`Inductor`]s with an inductance ratio of `(N1/N2)²`, coupled by a [`MutualInductance`]
+/// of `k = 1`.
+///
+/// So the primary voltage is `N1/N2` times the secondary voltage at every frequency, and the
+/// power flowing into the primary flows out of the secondary, apart from the current that
+/// magnetizes the primary inductance. Like any coupled inductors, the transformer passes
+/// no DC, as its windings are short circuits there.
+#[derive(Debug, Clone, Copy)]
+pub struct IdealTransformer {
+    turns_ratio: f64,
+    primary_inductance: f64,
+    /// The positive and negative nodes of the primary, then of the secondary.
+    nodes: [NodeId; 4],
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq)]
+pub enum TransformerError {
+    #[error("the turns ratio {0} is not a positive number")]
+    InvalidTurnsRatio(f64),
+    #[error("the primary inductance {0} is not a positive number")]
+    InvalidInductance(f64),
+}
+
+/// The elements an [`IdealTransformer`] adds to a circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Windings {
+    /// The primary inductor, whose branch current flows into its positive node.
+    pub primary: ElementId,
+    /// The secondary inductor, whose branch current flows into its positive node.
+    pub secondary: ElementId,
+    /// The [`MutualInductance`] of `k = 1` between the windings.
+    pub coupling: ElementId,
+}
+
+impl IdealTransformer {
+    /// * `turns_ratio` - `N1/N2`. The polarity of the secondary is inverted by swapping its
+    ///   nodes rather than by a negative ratio.
+    /// * `primary_inductance` - The inductance of the primary in Henries, which is made large
+    ///   compared to the impedances around it for the transformer to be close to ideal.
+    ///
+    /// Errors if either of them is not a positive, finite number.
+    pub fn new(
+        turns_ratio: f64,
+        primary_inductance: f64,
+        primary_positive: NodeId,
+        primary_negative: NodeId,
+        secondary_positive: NodeId,
+        secondary_negative: NodeId,
+    ) -> Result<Self, TransformerError> {
+        let positive = |value: f64| value.is_finite() && value > 0.0;
+        if !positive(turns_ratio) {
+            return Err(TransformerError::InvalidTurnsRatio(turns_ratio));
+        }
+        if !positive(primary_inductance) {
+            return Err(TransformerError::InvalidInductance(primary_inductance));
+        }
+
+        Ok(Self {
+            turns_ratio,
+            primary_inductance,
+            nodes: [
+                primary_positive,
+                primary_negative,
+                secondary_positive,
+                secondary_negative,
+            ],
+        })
+    }
+
+    /// The turns ratio `N1/N2`.
+    pub fn turns_ratio(&self) -> f64 {
+        self.turns_ratio
+    }
+
+    /// Adds the windings of the transformer and their coupling to the `circuit`.
+    pub fn add_to(&self, circuit: &mut Circuit) -> Windings {
+        let [primary_positive, primary_negative, secondary_positive, secondary_negative] =
+            self.nodes;
+        let secondary_inductance = self.primary_inductance / self.turns_ratio.powi(2);
+
+        let primary = circuit.add_element(Box::new(Inductor::new(
+            self.primary_inductance,
+            primary_positive,
+            primary_negative,
+        )));
+        let secondary = circuit.add_element(Box::new(Inductor::new(
+            secondary_inductance,
+            secondary_positive,
+            secondary_negative,
+        )));
+        let coupling = MutualInductance::new(circuit, 1.0, &[primary, secondary])
+            .expect("two distinct inductors can be coupled by k = 1");
+        let coupling = circuit.add_element(Box::new(coupling));
+
+        Windings {
+            primary,
+            secondary,
+            coupling,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource, dc_voltage_source::DCVoltageSource,
+            resistor::Resistor,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep},
+        Circuit,
+    };
+
+    use super::{IdealTransformer, TransformerError};
+
+    /// A 2:1 step down transformer into a 10Ω load, which looks like 40Ω from the primary
+    /// in parallel with the reactance of the primary, and passes no DC.
+    #[test]
+    fn step_down() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vin = circuit.push_node();
+        let bias = circuit.push_node();
+        let primary = circuit.push_node();
+        let secondary = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(10.0, bias, ground)));
+        circuit.add_element(Box::new(Resistor::new(1.0, bias, vin)));
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            primary,
+            vin,
+        )));
+        let windings = IdealTransformer::new(2.0, 1.0, primary, ground, secondary, ground)
+            .unwrap()
+            .add_to(&mut circuit);
+        circuit.add_element(Box::new(Resistor::new(10.0, secondary, ground)));
+
+        let op = dc_op(&circuit).unwrap();
+        let omega = 1e3;
+        let sweep = Sweep::List(vec![omega / std::f64::consts::TAU]);
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_relative_eq!(op.voltage(secondary), 0.0);
+        assert_relative_eq!(op.branch_current(windings.primary).unwrap(), 10.0);
+        let point = &result.points[0];
+        let (v1, v2) = (point.voltage(primary), point.voltage(secondary));
+        assert_relative_eq!(v2.re, v1.re / 2.0, max_relative = 1e-9);
+        assert_relative_eq!(v2.im, v1.im / 2.0, max_relative = 1e-9);
+        let i1 = point.branch_current(windings.primary).unwrap();
+        let expected = v1 / 40.0 + v1 / Complex::new(0.0, omega);
+        assert_relative_eq!(i1.re, expected.re, epsilon = 1e-9);
+        assert_relative_eq!(i1.im, expected.im, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn invalid_transformer() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let primary = circuit.push_node();
+        let secondary = circuit.push_node();
+        let transformer = |ratio, inductance| {
+            IdealTransformer::new(ratio, inductance, primary, ground, secondary, ground)
+        };
+
+        assert_eq!(
+            transformer(0.0, 1.0).err(),
+            Some(TransformerError::InvalidTurnsRatio(0.0))
+        );
+        assert_eq!(
+            transformer(-2.0, 1.0).err(),
+            Some(TransformerError::InvalidTurnsRatio(-2.0))
+        );
+        assert!(matches!(
+            transformer(f64::NAN, 1.0),
+            Err(TransformerError::InvalidTurnsRatio(_))
+        ));
+        assert_eq!(
+            transformer(2.0, 0.0).err(),
+            Some(TransformerError::InvalidInductance(0.0))
+        );
+        assert_eq!(
+            transformer(2.0, f64::INFINITY).err(),
+            Some(TransformerError::InvalidInductance(f64::INFINITY))
+        );
+    }
+}
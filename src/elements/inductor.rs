@@ -1,32 +1,51 @@
 use nalgebra::Complex;
 
-use crate::NodeId;
+use crate::{
+    runners::{integration::Integration, operating_point::OperatingPoint},
+    BranchId, NodeId,
+};
 
-use super::{dc_voltage_source::DCVoltageSource, Element, Terminal};
+use super::{stamp::StampContext, Element, ParameterError, Terminal};
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Inductor {
-    inductance: f32,
+    inductance: f64,
+    /// The current a transient analysis that skips the operating point starts with, in Amperes.
+    initial_current: Option<f64>,
     terminals: [Terminal; 2],
-    index: usize,
+    branch: BranchId,
 }
 
 impl Inductor {
-    pub fn new(
-        inductance: f32,
-        positive_node: NodeId,
-        negative_node: NodeId,
-        index: usize,
-    ) -> Self {
+    pub fn new(inductance: f64, positive_node: NodeId, negative_node: NodeId) -> Self {
         Self {
             inductance,
+            initial_current: None,
             terminals: [
                 Terminal::new(positive_node, super::Polarity::Positive),
                 Terminal::new(negative_node, super::Polarity::Negative),
             ],
-            index,
+            branch: BranchId::default(),
         }
     }
+
+    /// Starts a transient analysis that skips the operating point with `current` flowing
+    /// through the inductor, as with `IC=` in SPICE.
+    #[must_use]
+    pub fn with_initial_current(mut self, current: f64) -> Self {
+        self.initial_current = Some(current);
+        self
+    }
+
+    /// The inductance in Henries.
+    pub fn inductance(&self) -> f64 {
+        self.inductance
+    }
+
+    /// The initial current in Amperes, if it is given.
+    pub fn initial_current(&self) -> Option<f64> {
+        self.initial_current
+    }
 }
 
 impl Element for Inductor {
@@ -34,44 +53,127 @@ impl Element for Inductor {
         &self.terminals
     }
 
-    fn stamp(&self, a_matrix: &mut Vec<f32>, z_vector: &mut Vec<f32>, n: usize, m: usize) {
-        let nodes: Vec<NodeId> = self.terminals().iter().map(|x| x.node).collect();
-        DCVoltageSource::new(0.0, nodes[0], nodes[1], self.index).stamp(a_matrix, z_vector, n, m);
+    /// An inductor is a short circuit at DC, so it is stamped as a 0V source.
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        let terminals = self.terminals();
+        context.add_branch(self.branch, terminals[0].node, terminals[1].node);
+    }
+
+    /// Stamps the companion model of the flux, which is a resistor of `L/h` in series with
+    /// a voltage source for backward Euler.
+    ///
+    /// The resistor is stamped onto the D matrix, so the branch current stays an unknown.
+    fn stamp_transient(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        _state: &mut [f64],
+        integration: &Integration,
+    ) {
+        let terminals = self.terminals();
+        let current = estimate
+            .branch(self.branch)
+            .expect("the inductor is part of the solved circuit");
+        let (resistance, voltage) =
+            integration.companion(0, current, (self.inductance * current, self.inductance));
+
+        context.add_branch(self.branch, terminals[0].node, terminals[1].node);
+        context.add_branch_voltage(self.branch, voltage);
+        context.add_branch_impedance(self.branch, resistance);
+    }
+
+    /// Stamps the branch equation `v+ - v- - jωL * i = 0`, with the impedance on the D matrix.
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        omega: f64,
+    ) {
+        let terminals = self.terminals();
+        context.add_branch(self.branch, terminals[0].node, terminals[1].node);
+        context.add_branch_impedance(self.branch, self.impedance_at_omega(omega));
     }
 
     fn is_b_c_element(&self) -> bool {
         true
     }
 
-    fn dc_voltage(&self) -> f32 {
+    fn set_branch(&mut self, branch: BranchId) {
+        self.branch = branch;
+    }
+
+    /// `L * i`
+    fn charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        let current = solution
+            .branch(self.branch)
+            .expect("the inductor is part of the solved circuit");
+        vec![self.inductance * current]
+    }
+
+    /// `L * i` at the initial current, if it is given.
+    fn initial_charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        match self.initial_current {
+            Some(current) => vec![self.inductance * current],
+            None => self.charges(solution),
+        }
+    }
+
+    /// `l`, the inductance in Henries, and `ic`, the initial current in Amperes
+    /// if it is given.
+    ///
+    /// A [`MutualInductance`](super::mutual_inductance::MutualInductance) follows the new
+    /// inductance when it is set through [`Circuit::set_parameter`](crate::Circuit::set_parameter).
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "l" | "inductance" => Some(self.inductance),
+            "ic" => self.initial_current,
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "l" | "inductance" => self.inductance = value,
+            "ic" => self.initial_current = Some(value),
+            _ => return Err(ParameterError::Unknown(name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
         0.0
     }
 
-    fn ac_voltage(&self) -> Complex<f32> {
+    fn ac_voltage(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn dc_current(&self) -> f32 {
+    fn dc_current(&self) -> f64 {
         0.0
     }
 
-    fn ac_current(&self) -> Complex<f32> {
+    fn ac_current(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn resistance(&self) -> f32 {
+    fn resistance(&self) -> f64 {
         0.0
     }
 
     /// The rectangular impedence of an inductor is equal to `0 + jωL`,
-    /// where `L` is the inductance in Henries and `ω` is the frequency in Hertz.
-    fn impedance(&self, frequency: f32) -> Complex<f32> {
-        Complex::new(0.0, self.inductance * frequency)
+    /// where `L` is the inductance in Henries and `ω` is the angular frequency in radians
+    /// per second.
+    fn impedance_at_omega(&self, omega: f64) -> Complex<f64> {
+        Complex::new(0.0, self.inductance * omega)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::PI;
+
+    use approx::assert_relative_eq;
     use nalgebra::Complex;
 
     use crate::{elements::Element, NodeId};
@@ -81,10 +183,13 @@ mod tests {
     /// Test if the impedance for the inductor is correctly calculated.
     #[test]
     fn impedance() {
-        let inductor = Inductor::new(10.0, NodeId(0), NodeId(1), 0);
+        let inductor = Inductor::new(10.0, NodeId(0), NodeId(1));
         assert_eq!(
-            inductor.impedance(1000.0),
-            Complex::<f32>::new(0.0, 10000.0)
-        )
+            inductor.impedance_at_omega(1000.0),
+            Complex::<f64>::new(0.0, 10000.0)
+        );
+        // A 1mH inductor has a reactance of 6.28Ω at 1kHz
+        let inductor = Inductor::new(1e-3, NodeId(0), NodeId(1));
+        assert_relative_eq!(inductor.impedance(1000.0).im, 2.0 * PI);
     }
 }
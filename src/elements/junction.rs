@@ -0,0 +1,167 @@
+//! Helpers shared by the semiconductor elements to model their pn junctions.
+
+use std::f64::consts::SQRT_2;
+
+/// The thermal voltage `kT/q` at the nominal temperature of 27°C.
+pub(crate) const THERMAL_VOLTAGE: f64 = 0.025_864_925_786_328_75;
+
+/// The temperature in °C at which the model parameters are given, `TNOM` in SPICE.
+pub(crate) const NOMINAL_TEMPERATURE: f64 = 27.0;
+
+/// The thermal voltage `kT/q` at `temperature` in °C.
+pub(crate) fn thermal_voltage(temperature: f64) -> f64 {
+    THERMAL_VOLTAGE * (temperature + 273.15) / (NOMINAL_TEMPERATURE + 273.15)
+}
+
+/// The saturation current at `temperature` in °C of a junction whose `saturation_current`
+/// is given at the nominal temperature, as in SPICE:
+/// `IS(T) = IS * (T/Tnom)^(XTI/N) * exp((T/Tnom - 1) * EG / (N * kT/q))`.
+///
+/// * `energy_gap` - The band gap `EG` in electronvolts.
+/// * `exponent` - The temperature exponent `XTI`.
+/// * `emission` - The emission coefficient `N`.
+pub(crate) fn saturation_current(
+    saturation_current: f64,
+    temperature: f64,
+    energy_gap: f64,
+    exponent: f64,
+    emission: f64,
+) -> f64 {
+    let ratio = (temperature + 273.15) / (NOMINAL_TEMPERATURE + 273.15);
+    let vt = emission * thermal_voltage(temperature);
+
+    saturation_current * ratio.powf(exponent / emission) * ((ratio - 1.0) * energy_gap / vt).exp()
+}
+
+/// A tiny conductance in parallel with every junction, which keeps the matrix
+/// from becoming singular when the junction is off.
+pub(crate) const GMIN: f64 = 1e-12;
+
+/// The fraction of the junction potential above which the depletion capacitance is
+/// extrapolated linearly instead of growing without bound, known as `FC` in SPICE.
+const FORWARD_BIAS_COEFFICIENT: f64 = 0.5;
+
+/// The voltage at which `saturation_current * exp(v/vt)` curves the most,
+/// above which [`limit`] starts limiting the steps of the junction voltage.
+pub(crate) fn critical_voltage(saturation_current: f64, vt: f64) -> f64 {
+    vt * (vt / (SQRT_2 * saturation_current)).ln()
+}
+
+/// Limits a Newton-Raphson step of a junction voltage from `old` to `new`, like `pnjlim`
+/// in SPICE, so the exponential current does not overflow or oscillate.
+///
+/// Above the `critical` voltage, steps of more than `2 * vt` are shrunk logarithmically.
+/// Returns the limited voltage and whether it was limited.
+pub(crate) fn limit(new: f64, old: f64, vt: f64, critical: f64) -> (f64, bool) {
+    if new <= critical || (new - old).abs() <= 2.0 * vt {
+        return (new, false);
+    }
+
+    let limited = if old > 0.0 {
+        let argument = 1.0 + (new - old) / vt;
+        match argument > 0.0 {
+            true => old + vt * argument.ln(),
+            false => critical,
+        }
+    } else {
+        vt * (new / vt).ln()
+    };
+
+    (limited, true)
+}
+
+/// The charge and capacitance of the depletion region of a junction at `voltage`.
+///
+/// * `capacitance` - Zero-bias capacitance `CJO` in Farads.
+/// * `potential` - Built-in potential `VJ` in Volts.
+/// * `grading` - Grading coefficient `M`.
+pub(crate) fn depletion(
+    voltage: f64,
+    capacitance: f64,
+    potential: f64,
+    grading: f64,
+) -> (f64, f64) {
+    if capacitance == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let threshold = FORWARD_BIAS_COEFFICIENT * potential;
+    if voltage < threshold {
+        let base = 1.0 - voltage / potential;
+        let charge = capacitance * potential * (1.0 - base.powf(1.0 - grading)) / (1.0 - grading);
+
+        return (charge, capacitance * base.powf(-grading));
+    }
+
+    // Above the threshold, the capacitance continues along its tangent
+    let f1 =
+        potential * (1.0 - (1.0 - FORWARD_BIAS_COEFFICIENT).powf(1.0 - grading)) / (1.0 - grading);
+    let f2 = (1.0 - FORWARD_BIAS_COEFFICIENT).powf(1.0 + grading);
+    let f3 = 1.0 - FORWARD_BIAS_COEFFICIENT * (1.0 + grading);
+    let charge = capacitance
+        * (f1
+            + (f3 * (voltage - threshold)
+                + grading / (2.0 * potential) * (voltage * voltage - threshold * threshold))
+                / f2);
+
+    (
+        charge,
+        capacitance * (f3 + grading * voltage / potential) / f2,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::{
+        critical_voltage, depletion, limit, saturation_current, thermal_voltage,
+        NOMINAL_TEMPERATURE, THERMAL_VOLTAGE,
+    };
+
+    /// Large forward steps are shrunk to a logarithmic step, small ones are kept.
+    #[test]
+    fn limiting() {
+        let vt = THERMAL_VOLTAGE;
+        let critical = critical_voltage(1e-14, vt);
+
+        assert_eq!(limit(0.65, 0.64, vt, critical), (0.65, false));
+        assert_eq!(limit(-5.0, 0.0, vt, critical), (-5.0, false));
+
+        let (voltage, limited) = limit(10.0, 0.7, vt, critical);
+        assert!(limited);
+        assert_relative_eq!(voltage, 0.7 + vt * (1.0 + 9.3 / vt).ln());
+    }
+
+    /// The charge is the integral of the capacitance, on both sides of the threshold.
+    #[test]
+    fn depletion_charge() {
+        for voltage in [-2.0, 0.0, 0.4, 0.6, 0.9] {
+            let h = 1e-6;
+            let (below, _) = depletion(voltage - h, 1e-12, 0.8, 0.4);
+            let (above, _) = depletion(voltage + h, 1e-12, 0.8, 0.4);
+            let (_, capacitance) = depletion(voltage, 1e-12, 0.8, 0.4);
+
+            assert_relative_eq!(
+                (above - below) / (2.0 * h),
+                capacitance,
+                max_relative = 1e-6
+            );
+        }
+        assert_eq!(depletion(0.0, 1e-12, 0.8, 0.4), (0.0, 1e-12));
+    }
+
+    /// The parameters hold at the nominal temperature, and the saturation current
+    /// roughly doubles with every 5°C or so above it.
+    #[test]
+    fn temperature() {
+        assert_relative_eq!(thermal_voltage(NOMINAL_TEMPERATURE), THERMAL_VOLTAGE);
+        assert_relative_eq!(
+            saturation_current(1e-14, NOMINAL_TEMPERATURE, 1.11, 3.0, 1.0),
+            1e-14
+        );
+
+        let warmer = saturation_current(1e-14, NOMINAL_TEMPERATURE + 5.0, 1.11, 3.0, 1.0);
+        assert!(1.8e-14 < warmer && warmer < 2.2e-14);
+    }
+}
@@ -1,16 +1,36 @@
-use std::{any::Any, fmt::Debug};
+use std::{any::Any, f64::consts::TAU, fmt::Debug};
 
 use dyn_clone::DynClone;
 use nalgebra::Complex;
+use thiserror::Error;
 
-use crate::NodeId;
+use crate::{
+    runners::{integration::Integration, operating_point::OperatingPoint},
+    BranchId, ElementId, NodeId,
+};
 
+use self::stamp::StampContext;
+
+pub mod ac_current_source;
 pub mod ac_volatage_source;
+pub mod bjt;
 pub mod capacitor;
+pub mod current_controlled_current_source;
+pub mod current_controlled_voltage_source;
 pub mod dc_current_source;
 pub mod dc_voltage_source;
+pub mod diode;
+pub mod ideal_transformer;
 pub mod inductor;
+mod junction;
+pub mod mosfet;
+pub mod mutual_inductance;
 pub mod resistor;
+mod source;
+pub mod stamp;
+pub mod voltage_controlled_current_source;
+pub mod voltage_controlled_voltage_source;
+pub mod waveform;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Polarity {
@@ -21,7 +41,7 @@ pub enum Polarity {
 }
 
 impl Polarity {
-    pub fn sign(&self) -> f32 {
+    pub fn sign(&self) -> f64 {
         match self {
             Self::Positive | Self::Neutral => 1.0,
             Self::Negative => -1.0,
@@ -52,7 +72,7 @@ impl Terminal {
         Self::new(node, Polarity::Negative)
     }
 
-    pub fn sign(&self) -> f32 {
+    pub fn sign(&self) -> f64 {
         self.polarity.sign()
     }
 }
@@ -61,33 +81,158 @@ pub trait Element: Any + DynClone + Debug {
     fn terminals(&self) -> &[Terminal];
 
     /// "Stamp" the circuit elements' influence onto the
-    /// `a_matrix` and `z_vector`.
+    /// `a_matrix` and `z_vector` through the `context`.
+    fn stamp(&self, context: &mut StampContext<f64>);
+
+    /// Does the current through the element depend nonlinearly on the voltages across it?
+    ///
+    /// If so, the circuit is solved by Newton-Raphson iteration,
+    /// stamping the element through [`Element::stamp_linearized`].
+    fn is_nonlinear(&self) -> bool {
+        false
+    }
+
+    /// The number of values the element keeps in its `state` between
+    /// Newton-Raphson iterations, such as the junction voltage it was last linearized at.
+    fn state_len(&self) -> usize {
+        0
+    }
+
+    /// Sets the `state` from a `solution` of the circuit that the Newton-Raphson iteration
+    /// starts from, such as the operating point a transient analysis starts at.
+    ///
+    /// Otherwise the state starts at zero.
+    fn initialize_state(&self, _solution: &OperatingPoint, _state: &mut [f64]) {}
+
+    /// "Stamp" the Jacobian and right-hand side of the element, linearized at
+    /// the `estimate` of the current Newton-Raphson iteration, through the `context`.
+    ///
+    /// Linear elements stamp themselves the same way as in [`Element::stamp`].
     ///
-    /// The `a_matrix` consists of 4 different matrices: G, B, C, D.
-    /// These are ordered in like so:
+    /// * `estimate` - Solution of the circuit at the previous iteration.
+    /// * `state` - The values kept by the element between iterations,
+    ///   [`Element::state_len`] long and zero at the first iteration.
+    fn stamp_linearized(
+        &self,
+        context: &mut StampContext<f64>,
+        _estimate: &OperatingPoint,
+        _state: &mut [f64],
+    ) {
+        self.stamp(context);
+    }
+
+    /// "Stamp" the companion model of the element for a single time step through
+    /// the `context`, linearized at the `estimate` of the current Newton-Raphson iteration.
     ///
-    /// `[G B]`\
-    /// `[C D]`
+    /// Elements without memory of the past stamp themselves the same way as in
+    /// [`Element::stamp_linearized`].
     ///
-    /// * `a_matrix` - Consists of all the passive elements in the circuit.
-    /// * `z_vector` - Consists of all the active elements in the circuit.
-    /// * `n` - Number of nodes in the circuit.
-    /// * `m` - Number of independent voltage sources.
-    fn stamp(&self, a_matrix: &mut Vec<f32>, z_vector: &mut Vec<f32>, n: usize, m: usize);
+    /// * `integration` - How the [`Element::charges`] of the element are integrated
+    ///   over the step, which gives their derivatives.
+    fn stamp_transient(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+        _integration: &Integration,
+    ) {
+        self.stamp_linearized(context, estimate, state);
+    }
+
+    /// "Stamp" the small-signal model of the element through the complex `context`
+    /// of an AC analysis.
+    ///
+    /// By default, the [`Element::admittance_at_omega`] is stamped between the first two terminals.
+    ///
+    /// * `operating_point` - DC solution of the circuit, which nonlinear elements
+    ///   are linearized at.
+    /// * `omega` - Angular frequency in radians per second.
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        omega: f64,
+    ) {
+        let terminals = self.terminals();
+        context.add_conductance(
+            terminals[0].node,
+            terminals[1].node,
+            self.admittance_at_omega(omega),
+        );
+    }
 
     /// Does this element stamp itself onto the B or C matrices?
+    ///
+    /// If so, [`Circuit::add_element`](crate::Circuit::add_element) assigns it a branch current
+    /// through [`Element::set_branch`].
     fn is_b_c_element(&self) -> bool {
         false
     }
 
-    fn dc_voltage(&self) -> f32;
-    fn ac_voltage(&self) -> Complex<f32>;
+    /// Sets the branch whose current is the unknown this element stamps onto the B and C matrices.
+    fn set_branch(&mut self, _branch: BranchId) {}
+
+    /// The number of nodes inside of the element, such as the node between
+    /// a diode junction and its series resistance.
+    ///
+    /// [`Circuit::add_element`](crate::Circuit::add_element) allocates them
+    /// through [`Element::set_internal_nodes`].
+    fn internal_node_count(&self) -> usize {
+        0
+    }
 
-    fn dc_current(&self) -> f32;
-    fn ac_current(&self) -> Complex<f32>;
+    /// Sets the nodes inside of the element, [`Element::internal_node_count`] long.
+    fn set_internal_nodes(&mut self, _nodes: &[NodeId]) {}
 
-    fn resistance(&self) -> f32;
-    fn conductance(&self) -> f32 {
+    /// The charges of the capacitances and the fluxes of the inductances of the element
+    /// at a `solution`, which a transient analysis integrates over time, and whose local
+    /// truncation error limits its time step.
+    ///
+    /// They are in the same order as in the [`Integration`] of [`Element::stamp_transient`].
+    fn charges(&self, _solution: &OperatingPoint) -> Vec<f64> {
+        Vec::new()
+    }
+
+    /// The charges the element starts a transient analysis with when it skips the operating
+    /// point, from its own initial conditions, such as the voltage across a capacitor,
+    /// or otherwise from the `solution` the analysis starts at.
+    fn initial_charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        self.charges(solution)
+    }
+
+    /// The times up to `stop` at which the element changes abruptly, such as the edges of
+    /// a pulse, which a transient analysis steps onto instead of over.
+    fn breakpoints(&self, _stop: f64) -> Vec<f64> {
+        Vec::new()
+    }
+
+    /// Updates the element after a parameter of the `element` with `id` was set through
+    /// [`Circuit::set_parameter`](crate::Circuit::set_parameter), for elements that depend on
+    /// the parameters of others, such as the inductances a coupling is made of.
+    fn element_changed(&mut self, _id: ElementId, _element: &dyn Element) {}
+
+    /// The value of the parameter called `name`, such as `r` of a resistor or `temp` of a diode,
+    /// if the element has it.
+    ///
+    /// Parameters are named in lowercase after their SPICE names.
+    fn parameter(&self, _name: &str) -> Option<f64> {
+        None
+    }
+
+    /// Sets the parameter called `name` to `value`, such as to step it through a family of
+    /// analyses.
+    fn set_parameter(&mut self, name: &str, _value: f64) -> Result<(), ParameterError> {
+        Err(ParameterError::Unknown(name.to_string()))
+    }
+
+    fn dc_voltage(&self) -> f64;
+    fn ac_voltage(&self) -> Complex<f64>;
+
+    fn dc_current(&self) -> f64;
+    fn ac_current(&self) -> Complex<f64>;
+
+    fn resistance(&self) -> f64;
+    fn conductance(&self) -> f64 {
         let resistance = self.resistance();
         if resistance == 0.0 {
             return 0.0;
@@ -96,15 +241,42 @@ pub trait Element: Any + DynClone + Debug {
         resistance.recip()
     }
 
-    fn impedance(&self, frequency: f32) -> Complex<f32>;
-    fn admittance(&self, frequency: f32) -> Complex<f32> {
-        let impedance = self.impedance(frequency);
+    /// The impedance at the angular frequency `omega` in radians per second.
+    fn impedance_at_omega(&self, omega: f64) -> Complex<f64>;
+    /// The admittance at the angular frequency `omega` in radians per second,
+    /// which is 0 where the impedance is 0.
+    fn admittance_at_omega(&self, omega: f64) -> Complex<f64> {
+        let impedance = self.impedance_at_omega(omega);
         if impedance == Complex::ZERO {
             return Complex::ZERO;
         }
 
         impedance.inv()
     }
+
+    /// The impedance at `frequency` in Hertz, which is an angular frequency of `ω = 2πf`.
+    fn impedance(&self, frequency: f64) -> Complex<f64> {
+        self.impedance_at_omega(TAU * frequency)
+    }
+    /// The admittance at `frequency` in Hertz, which is an angular frequency of `ω = 2πf`.
+    fn admittance(&self, frequency: f64) -> Complex<f64> {
+        self.admittance_at_omega(TAU * frequency)
+    }
 }
 
 dyn_clone::clone_trait_object!(Element);
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ParameterError {
+    #[error("the element has no parameter `{0}`")]
+    Unknown(String),
+    #[error("`{0}` decides the nodes of the element, so it cannot change once it is in a circuit")]
+    Fixed(String),
+    #[error("{value} is out of range for `{name}`")]
+    OutOfRange { name: String, value: f64 },
+}
+
+/// The control element of a current controlled source has no branch current to sense.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("element {0} has no branch current")]
+pub struct NoBranchCurrent(pub usize);
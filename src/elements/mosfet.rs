@@ -0,0 +1,969 @@
+use std::fmt;
+
+use nalgebra::Complex;
+
+use crate::{
+    runners::{integration::Integration, operating_point::OperatingPoint},
+    units, NodeId,
+};
+
+use super::{junction::GMIN, stamp::StampContext, Element, ParameterError, Terminal};
+
+/// The permittivity of silicon dioxide in Farads per meter.
+const OXIDE_PERMITTIVITY: f64 = 3.9 * 8.854_187_8e-12;
+
+/// Whether the channel of a [`Mosfet`] is n-type or p-type.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MosfetKind {
+    #[default]
+    Nmos,
+    Pmos,
+}
+
+impl MosfetKind {
+    /// `1` for NMOS and `-1` for PMOS, which flips every voltage and current.
+    pub fn sign(&self) -> f64 {
+        match self {
+            Self::Nmos => 1.0,
+            Self::Pmos => -1.0,
+        }
+    }
+}
+
+/// The parameters of a level 1 [`Mosfet`], named after their SPICE `.model` parameters.
+///
+/// The defaults are the same as in SPICE. As there, the threshold voltage of a
+/// PMOS transistor is negative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MosfetModel {
+    /// `VTO`, the zero-bias threshold voltage in Volts.
+    pub threshold_voltage: f64,
+    /// `KP`, the transconductance parameter in Amperes per square Volt.
+    pub transconductance: f64,
+    /// `GAMMA`, the body effect parameter in square root Volts.
+    pub body_effect: f64,
+    /// `PHI`, the surface potential in Volts.
+    pub surface_potential: f64,
+    /// `LAMBDA`, the channel length modulation in inverse Volts.
+    pub channel_length_modulation: f64,
+    /// `TOX`, the oxide thickness in meters, which gives the Meyer gate capacitances.
+    /// Zero leaves them out.
+    pub oxide_thickness: f64,
+    /// `CGSO`, the gate-source overlap capacitance in Farads per meter of width.
+    pub gate_source_overlap: f64,
+    /// `CGDO`, the gate-drain overlap capacitance in Farads per meter of width.
+    pub gate_drain_overlap: f64,
+    /// `CGBO`, the gate-bulk overlap capacitance in Farads per meter of length.
+    pub gate_bulk_overlap: f64,
+}
+
+impl Default for MosfetModel {
+    fn default() -> Self {
+        Self {
+            threshold_voltage: 0.0,
+            transconductance: 2e-5,
+            body_effect: 0.0,
+            surface_potential: 0.6,
+            channel_length_modulation: 0.0,
+            oxide_thickness: 0.0,
+            gate_source_overlap: 0.0,
+            gate_drain_overlap: 0.0,
+            gate_bulk_overlap: 0.0,
+        }
+    }
+}
+
+impl MosfetModel {
+    /// The value of the parameter with the lowercase SPICE `name`, such as `vto`.
+    pub fn parameter(&self, name: &str) -> Option<f64> {
+        let mut model = *self;
+        model.parameter_mut(name).copied()
+    }
+
+    /// The parameter with the lowercase SPICE `name`, such as `vto`, to change.
+    pub fn parameter_mut(&mut self, name: &str) -> Option<&mut f64> {
+        Some(match name {
+            "vto" | "vt0" => &mut self.threshold_voltage,
+            "kp" => &mut self.transconductance,
+            "gamma" => &mut self.body_effect,
+            "phi" => &mut self.surface_potential,
+            "lambda" => &mut self.channel_length_modulation,
+            "tox" => &mut self.oxide_thickness,
+            "cgso" => &mut self.gate_source_overlap,
+            "cgdo" => &mut self.gate_drain_overlap,
+            "cgbo" => &mut self.gate_bulk_overlap,
+            _ => return None,
+        })
+    }
+}
+
+/// The region of operation of a [`Mosfet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// The gate is below the threshold, so no current flows.
+    Cutoff,
+    /// The channel reaches from the source to the drain, and acts like a resistor.
+    Linear,
+    /// The channel is pinched off, and the current barely depends on the drain.
+    Saturation,
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Cutoff => "cutoff",
+            Self::Linear => "linear",
+            Self::Saturation => "saturation",
+        })
+    }
+}
+
+/// The state of a [`Mosfet`] at a DC operating point, as returned by
+/// [`Mosfet::operating_point`].
+///
+/// Voltages and the drain current have the signs of the circuit,
+/// so they are negative in a conducting PMOS transistor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MosfetOperatingPoint {
+    pub region: Region,
+    pub vgs: f64,
+    pub vds: f64,
+    pub vbs: f64,
+    /// The threshold voltage, including the body effect.
+    pub threshold_voltage: f64,
+    /// The drain-source voltage at which the channel pinches off.
+    pub saturation_voltage: f64,
+    /// The current flowing into the drain.
+    pub drain_current: f64,
+    /// `d(id) / d(vgs)`
+    pub gm: f64,
+    /// `d(id) / d(vds)`
+    pub gds: f64,
+    /// `d(id) / d(vbs)`
+    pub gmbs: f64,
+}
+
+/// Lists every quantity, one per line.
+impl fmt::Display for MosfetOperatingPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "region = {}", self.region)?;
+        for (name, value, unit) in [
+            ("id", self.drain_current, "A"),
+            ("vgs", self.vgs, "V"),
+            ("vds", self.vds, "V"),
+            ("vbs", self.vbs, "V"),
+            ("vth", self.threshold_voltage, "V"),
+            ("vdsat", self.saturation_voltage, "V"),
+            ("gm", self.gm, "S"),
+            ("gds", self.gds, "S"),
+            ("gmbs", self.gmbs, "S"),
+        ] {
+            writeln!(f, "{name} = {}", units::format(value, unit))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The drain current of a [`Mosfet`] and its derivatives, oriented as in an NMOS transistor
+/// whose drain is above its source.
+#[derive(Debug, Clone, Copy)]
+struct Channel {
+    region: Region,
+    threshold_voltage: f64,
+    saturation_voltage: f64,
+    current: f64,
+    gm: f64,
+    gds: f64,
+    gmbs: f64,
+}
+
+/// A metal-oxide-semiconductor field-effect transistor following the Shichman-Hodges
+/// (SPICE level 1) square law, with the body effect, channel length modulation and
+/// Meyer gate capacitances.
+///
+/// The drain and source are symmetric, so whichever is further from the gate in the
+/// direction of conduction acts as the drain. The bulk junctions are left out, apart from
+/// a tiny conductance from the drain and source to the bulk.
+#[derive(Debug, Clone, Copy)]
+pub struct Mosfet {
+    kind: MosfetKind,
+    model: MosfetModel,
+    /// The drain, gate, source and bulk.
+    terminals: [Terminal; 4],
+    /// `W` in meters.
+    width: f64,
+    /// `L` in meters.
+    length: f64,
+}
+
+impl Mosfet {
+    pub fn new(
+        kind: MosfetKind,
+        drain: NodeId,
+        gate: NodeId,
+        source: NodeId,
+        bulk: NodeId,
+    ) -> Self {
+        Self {
+            kind,
+            model: MosfetModel::default(),
+            terminals: [
+                Terminal::new_positive(drain),
+                Terminal::new_neutral(gate),
+                Terminal::new_negative(source),
+                Terminal::new_neutral(bulk),
+            ],
+            width: 1e-4,
+            length: 1e-4,
+        }
+    }
+
+    /// Sets the parameters of the transistor.
+    #[must_use]
+    pub fn with_model(mut self, model: MosfetModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Sets the channel `width` and `length` in meters, which are 100µm by default.
+    #[must_use]
+    pub fn with_dimensions(mut self, width: f64, length: f64) -> Self {
+        self.width = width;
+        self.length = length;
+        self
+    }
+
+    pub fn kind(&self) -> MosfetKind {
+        self.kind
+    }
+
+    pub fn model(&self) -> &MosfetModel {
+        &self.model
+    }
+
+    /// The region, currents and small-signal parameters of the transistor
+    /// at a DC operating point `solution`.
+    pub fn operating_point(&self, solution: &OperatingPoint) -> MosfetOperatingPoint {
+        let sign = self.kind.sign();
+        let (vgs, vds, vbs) = self.voltages(solution);
+        let (channel, reversed) = self.channel(vgs, vds, vbs);
+
+        // Conducting from the source to the drain turns the derivatives around,
+        // with the source voltage taking the place of the drain voltage
+        let (current, gds) = match reversed {
+            false => (channel.current, channel.gds),
+            true => (-channel.current, channel.gm + channel.gds + channel.gmbs),
+        };
+        let (gm, gmbs) = match reversed {
+            false => (channel.gm, channel.gmbs),
+            true => (-channel.gm, -channel.gmbs),
+        };
+
+        MosfetOperatingPoint {
+            region: channel.region,
+            vgs: sign * vgs,
+            vds: sign * vds,
+            vbs: sign * vbs,
+            threshold_voltage: sign * channel.threshold_voltage,
+            saturation_voltage: sign * channel.saturation_voltage,
+            drain_current: sign * current,
+            gm,
+            gds,
+            gmbs,
+        }
+    }
+
+    /// The gate-source, drain-source and bulk-source voltages,
+    /// oriented as in an NMOS transistor.
+    fn voltages(&self, solution: &OperatingPoint) -> (f64, f64, f64) {
+        let [drain, gate, source, bulk] = self.terminals.map(|terminal| terminal.node);
+        let sign = self.kind.sign();
+
+        (
+            sign * solution.voltage_between(gate, source),
+            sign * solution.voltage_between(drain, source),
+            sign * solution.voltage_between(bulk, source),
+        )
+    }
+
+    /// The oxide capacitance under the whole gate.
+    fn oxide_capacitance(&self) -> f64 {
+        match self.model.oxide_thickness > 0.0 {
+            true => OXIDE_PERMITTIVITY / self.model.oxide_thickness * self.width * self.length,
+            false => 0.0,
+        }
+    }
+
+    /// The channel at the NMOS oriented voltages, and whether the source acts as the drain
+    /// because `vds` is negative, in which case the channel is given from the drain's side.
+    fn channel(&self, vgs: f64, vds: f64, vbs: f64) -> (Channel, bool) {
+        match vds >= 0.0 {
+            true => (self.forward_channel(vgs, vds, vbs), false),
+            false => (self.forward_channel(vgs - vds, -vds, vbs - vds), true),
+        }
+    }
+
+    /// The square law, for a non-negative `vds`.
+    fn forward_channel(&self, vgs: f64, vds: f64, vbs: f64) -> Channel {
+        let model = &self.model;
+        let phi = model.surface_potential;
+        let gamma = model.body_effect;
+
+        // sqrt(PHI - vbs), continued linearly into forward bias as in SPICE
+        let root = match vbs <= 0.0 {
+            true => (phi - vbs).sqrt(),
+            false => (phi.sqrt() - vbs / (2.0 * phi.sqrt())).max(0.0),
+        };
+        let threshold_voltage =
+            self.kind.sign() * model.threshold_voltage + gamma * (root - phi.sqrt());
+        let overdrive = vgs - threshold_voltage;
+        let body = match root > 0.0 {
+            true => gamma / (2.0 * root),
+            false => 0.0,
+        };
+
+        let beta = model.transconductance * self.width / self.length;
+        let lambda = model.channel_length_modulation;
+        let modulated = beta * (1.0 + lambda * vds);
+        let (region, current, gm, gds) = if overdrive <= 0.0 {
+            (Region::Cutoff, 0.0, 0.0, 0.0)
+        } else if overdrive <= vds {
+            (
+                Region::Saturation,
+                modulated * overdrive * overdrive / 2.0,
+                modulated * overdrive,
+                lambda * beta * overdrive * overdrive / 2.0,
+            )
+        } else {
+            (
+                Region::Linear,
+                modulated * vds * (overdrive - vds / 2.0),
+                modulated * vds,
+                modulated * (overdrive - vds) + lambda * beta * vds * (overdrive - vds / 2.0),
+            )
+        };
+
+        Channel {
+            region,
+            threshold_voltage,
+            saturation_voltage: overdrive.max(0.0),
+            current,
+            gm,
+            gds,
+            gmbs: gm * body,
+        }
+    }
+
+    /// The Meyer gate-source, gate-drain and gate-bulk capacitances, including the overlaps,
+    /// where the source and drain are the ones of the `channel`.
+    fn capacitances(&self, vgs: f64, vds: f64, channel: &Channel) -> [f64; 3] {
+        let model = &self.model;
+        let oxide = self.oxide_capacitance();
+        let phi = model.surface_potential;
+        let overdrive = vgs - channel.threshold_voltage;
+
+        let (gate_source, gate_drain, gate_bulk) = if overdrive <= -phi {
+            (0.0, 0.0, oxide)
+        } else if overdrive <= -phi / 2.0 {
+            (0.0, 0.0, -overdrive * oxide / phi)
+        } else if overdrive <= 0.0 {
+            (
+                2.0 * oxide / 3.0 * (1.0 + 2.0 * overdrive / phi),
+                0.0,
+                -overdrive * oxide / phi,
+            )
+        } else if channel.saturation_voltage <= vds {
+            (2.0 * oxide / 3.0, 0.0, 0.0)
+        } else {
+            let saturation = channel.saturation_voltage;
+            let denominator = (2.0 * saturation - vds).powi(2);
+            (
+                2.0 * oxide / 3.0 * (1.0 - (saturation - vds).powi(2) / denominator),
+                2.0 * oxide / 3.0 * (1.0 - saturation * saturation / denominator),
+                0.0,
+            )
+        };
+
+        [
+            gate_source + model.gate_source_overlap * self.width,
+            gate_drain + model.gate_drain_overlap * self.width,
+            gate_bulk + model.gate_bulk_overlap * self.length,
+        ]
+    }
+
+    /// The Meyer capacitances from the gate to the source, drain and bulk at a `solution`,
+    /// each with its node and the voltage across it.
+    fn gate_capacitances(&self, solution: &OperatingPoint) -> [(NodeId, f64, f64); 3] {
+        let [drain, gate, source, bulk] = self.terminals.map(|terminal| terminal.node);
+        let (vgs, vds, vbs) = self.voltages(solution);
+        let (channel, reversed) = self.channel(vgs, vds, vbs);
+        let (vgs, vds) = match reversed {
+            false => (vgs, vds),
+            true => (vgs - vds, -vds),
+        };
+
+        let [gate_source, gate_drain, gate_bulk] = self.capacitances(vgs, vds, &channel);
+        let (source_capacitance, drain_capacitance) = match reversed {
+            false => (gate_source, gate_drain),
+            true => (gate_drain, gate_source),
+        };
+        [
+            (source, source_capacitance),
+            (drain, drain_capacitance),
+            (bulk, gate_bulk),
+        ]
+        .map(|(node, capacitance)| (node, solution.voltage_between(gate, node), capacitance))
+    }
+
+    /// The drain and source in the direction of conduction.
+    fn conducting_terminals(&self, reversed: bool) -> (NodeId, NodeId) {
+        let (drain, source) = (self.terminals[0].node, self.terminals[2].node);
+        match reversed {
+            false => (drain, source),
+            true => (source, drain),
+        }
+    }
+
+    /// Stamps the channel linearized at the NMOS oriented voltages, as a transconductance
+    /// for the gate and the bulk and a conductance from the drain to the source, along with
+    /// a current source that carries the drain current at those voltages.
+    fn stamp_channel(&self, context: &mut StampContext<f64>, vgs: f64, vds: f64, vbs: f64) {
+        let [_, gate, _, bulk] = self.terminals.map(|terminal| terminal.node);
+        let (channel, reversed) = self.channel(vgs, vds, vbs);
+        let (drain, source) = self.conducting_terminals(reversed);
+        let (vgs, vds, vbs) = match reversed {
+            false => (vgs, vds, vbs),
+            true => (vgs - vds, -vds, vbs - vds),
+        };
+
+        context.add_transconductance(drain, source, gate, source, channel.gm);
+        context.add_transconductance(drain, source, bulk, source, channel.gmbs);
+        context.add_conductance(drain, source, channel.gds);
+        let current = channel.current - channel.gm * vgs - channel.gds * vds - channel.gmbs * vbs;
+        context.add_current(source, drain, self.kind.sign() * current);
+
+        context.add_conductance(self.terminals[0].node, bulk, GMIN);
+        context.add_conductance(self.terminals[2].node, bulk, GMIN);
+    }
+}
+
+/// Limits a Newton-Raphson step of the gate voltage from `old` to `new` around
+/// the `threshold` voltage, like `fetlim` in SPICE, so the square law does not swing
+/// between cutoff and strong inversion.
+fn limit_gate(new: f64, old: f64, threshold: f64) -> f64 {
+    let high = (2.0 * (old - threshold)).abs() + 2.0;
+    let low = high / 2.0 + 2.0;
+    let strong = threshold + 3.5;
+    let step = new - old;
+
+    if old >= threshold {
+        if old >= strong {
+            if step <= 0.0 {
+                if new >= strong {
+                    return match -step > low {
+                        true => old - low,
+                        false => new,
+                    };
+                }
+                return new.max(threshold + 2.0);
+            }
+            return match step >= high {
+                true => old + high,
+                false => new,
+            };
+        }
+        return match step <= 0.0 {
+            true => new.max(threshold - 0.5),
+            false => new.min(threshold + 4.0),
+        };
+    }
+
+    if step <= 0.0 {
+        return match -step > high {
+            true => old - high,
+            false => new,
+        };
+    }
+    let turn_on = threshold + 0.5;
+    match new <= turn_on {
+        true => match step > low {
+            true => old + low,
+            false => new,
+        },
+        false => turn_on,
+    }
+}
+
+/// Limits a Newton-Raphson step of the drain-source voltage from `old` to `new`,
+/// like `limvds` in SPICE.
+fn limit_drain(new: f64, old: f64) -> f64 {
+    if old >= 3.5 {
+        match new > old {
+            true => new.min(3.0 * old + 2.0),
+            false if new < 3.5 => new.max(2.0),
+            false => new,
+        }
+    } else {
+        match new > old {
+            true => new.min(4.0),
+            false => new.max(-0.5),
+        }
+    }
+}
+
+impl Element for Mosfet {
+    fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    /// Stamps the transistor linearized with every terminal at 0V.
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        self.stamp_channel(context, 0.0, 0.0, 0.0);
+    }
+
+    fn is_nonlinear(&self) -> bool {
+        true
+    }
+
+    /// The gate-source and drain-source voltages the transistor was last linearized at.
+    fn state_len(&self) -> usize {
+        2
+    }
+
+    fn initialize_state(&self, solution: &OperatingPoint, state: &mut [f64]) {
+        let (vgs, vds, _) = self.voltages(solution);
+        state[0] = vgs;
+        state[1] = vds;
+    }
+
+    /// Limits the gate and drain voltages against the ones of the last iteration kept in
+    /// the `state`, from the side of whichever terminal acted as the source, and stamps
+    /// the channel linearized at them.
+    fn stamp_linearized(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+    ) {
+        let (vgs, vds, vbs) = self.voltages(estimate);
+        let (old_vgs, old_vds) = (state[0], state[1]);
+        let threshold = self.channel(old_vgs, old_vds, vbs).0.threshold_voltage;
+
+        let (limited_vgs, limited_vds, limited) = if old_vds >= 0.0 {
+            let vgd = vgs - vds;
+            let limited_vgs = limit_gate(vgs, old_vgs, threshold);
+            let unlimited_vds = limited_vgs - vgd;
+            let limited_vds = limit_drain(unlimited_vds, old_vds);
+            let limited = limited_vgs != vgs || limited_vds != unlimited_vds;
+            (limited_vgs, limited_vds, limited)
+        } else {
+            let vgd = vgs - vds;
+            let limited_vgd = limit_gate(vgd, old_vgs - old_vds, threshold);
+            let unlimited_vsd = limited_vgd - vgs;
+            let limited_vds = -limit_drain(unlimited_vsd, -old_vds);
+            let limited = limited_vgd != vgd || limited_vds != -unlimited_vsd;
+            (limited_vgd + limited_vds, limited_vds, limited)
+        };
+        if limited {
+            context.mark_limited();
+        }
+        state[0] = limited_vgs;
+        state[1] = limited_vds;
+
+        self.stamp_channel(context, limited_vgs, limited_vds, vbs);
+    }
+
+    /// Stamps the channel together with the Meyer gate capacitances, which are integrated
+    /// as the charges `C * v` at their capacitance at the estimate, as if they stayed constant.
+    fn stamp_transient(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        state: &mut [f64],
+        integration: &Integration,
+    ) {
+        self.stamp_linearized(context, estimate, state);
+
+        let gate = self.terminals[1].node;
+        for (index, (node, voltage, capacitance)) in
+            self.gate_capacitances(estimate).into_iter().enumerate()
+        {
+            let (conductance, current) =
+                integration.companion(index, voltage, (capacitance * voltage, capacitance));
+            context.add_conductance(gate, node, conductance);
+            context.add_current(node, gate, current);
+        }
+    }
+
+    /// The charges of the gate to the source, drain and bulk, as `C * v`.
+    fn charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        self.gate_capacitances(solution)
+            .into_iter()
+            .map(|(_, voltage, capacitance)| capacitance * voltage)
+            .collect()
+    }
+
+    /// Stamps the small-signal model at the operating point, with the Meyer gate
+    /// capacitances.
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        operating_point: &OperatingPoint,
+        omega: f64,
+    ) {
+        let [_, gate, _, bulk] = self.terminals.map(|terminal| terminal.node);
+        let (vgs, vds, vbs) = self.voltages(operating_point);
+        let (channel, reversed) = self.channel(vgs, vds, vbs);
+        let (drain, source) = self.conducting_terminals(reversed);
+        let (vgs, vds) = match reversed {
+            false => (vgs, vds),
+            true => (vgs - vds, -vds),
+        };
+
+        let real = |value: f64| Complex::new(value, 0.0);
+        context.add_transconductance(drain, source, gate, source, real(channel.gm));
+        context.add_transconductance(drain, source, bulk, source, real(channel.gmbs));
+        context.add_conductance(drain, source, real(channel.gds));
+        context.add_conductance(self.terminals[0].node, bulk, real(GMIN));
+        context.add_conductance(self.terminals[2].node, bulk, real(GMIN));
+
+        let capacitances = self.capacitances(vgs, vds, &channel);
+        for (node, capacitance) in [source, drain, bulk].into_iter().zip(capacitances) {
+            context.add_conductance(gate, node, Complex::new(0.0, omega * capacitance));
+        }
+    }
+
+    /// `w` and `l`, the channel width and length in meters, and the parameters of the
+    /// [`MosfetModel`].
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "w" => Some(self.width),
+            "l" => Some(self.length),
+            _ => self.model.parameter(name),
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        let parameter = match name {
+            "w" => &mut self.width,
+            "l" => &mut self.length,
+            _ => self
+                .model
+                .parameter_mut(name)
+                .ok_or(ParameterError::Unknown(name.to_string()))?,
+        };
+        *parameter = value;
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn resistance(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::new(f64::INFINITY, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource, dc_voltage_source::DCVoltageSource,
+            resistor::Resistor,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep, transient::transient},
+        Circuit, ElementId,
+    };
+
+    use super::{
+        limit_drain, limit_gate, Mosfet, MosfetKind, MosfetModel, MosfetOperatingPoint, Region,
+    };
+
+    /// `VTO = 1V` and `KP * W/L = 200µA/V²`.
+    fn model() -> MosfetModel {
+        MosfetModel {
+            threshold_voltage: 1.0,
+            transconductance: 2e-5,
+            ..MosfetModel::default()
+        }
+    }
+
+    /// An NMOS transistor with its gate, drain and bulk held by sources, and its source grounded.
+    fn biased(model: MosfetModel, vgs: f64, vds: f64, vbs: f64) -> (Circuit, ElementId) {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let gate = circuit.push_node();
+        let drain = circuit.push_node();
+        let bulk = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(vgs, gate, ground)));
+        circuit.add_element(Box::new(DCVoltageSource::new(vds, drain, ground)));
+        circuit.add_element(Box::new(DCVoltageSource::new(vbs, bulk, ground)));
+        let mosfet = circuit.add_element(Box::new(
+            Mosfet::new(MosfetKind::Nmos, drain, gate, ground, bulk)
+                .with_model(model)
+                .with_dimensions(10e-6, 1e-6),
+        ));
+
+        (circuit, mosfet)
+    }
+
+    fn operating_point(circuit: &Circuit, mosfet: ElementId) -> MosfetOperatingPoint {
+        let op = dc_op(circuit).unwrap();
+        circuit
+            .element::<Mosfet>(mosfet)
+            .unwrap()
+            .operating_point(&op)
+    }
+
+    #[test]
+    fn saturation() {
+        let model = MosfetModel {
+            channel_length_modulation: 0.02,
+            ..model()
+        };
+        let (circuit, mosfet) = biased(model, 3.0, 5.0, 0.0);
+
+        let op = operating_point(&circuit, mosfet);
+
+        assert_eq!(op.region, Region::Saturation);
+        assert_relative_eq!(op.drain_current, 1e-4 * 4.0 * 1.1, max_relative = 1e-9);
+        assert_relative_eq!(op.gm, 2e-4 * 2.0 * 1.1, max_relative = 1e-9);
+        assert_relative_eq!(op.gds, 0.02 * 1e-4 * 4.0, max_relative = 1e-9);
+        assert_relative_eq!(op.saturation_voltage, 2.0);
+    }
+
+    #[test]
+    fn linear() {
+        let (circuit, mosfet) = biased(model(), 3.0, 0.5, 0.0);
+
+        let op = operating_point(&circuit, mosfet);
+
+        assert_eq!(op.region, Region::Linear);
+        assert_relative_eq!(op.drain_current, 2e-4 * 0.5 * 1.75, max_relative = 1e-9);
+        assert_relative_eq!(op.gm, 2e-4 * 0.5, max_relative = 1e-9);
+        assert_relative_eq!(op.gds, 2e-4 * 1.5, max_relative = 1e-9);
+    }
+
+    #[test]
+    fn cutoff() {
+        let (circuit, mosfet) = biased(model(), 0.5, 5.0, 0.0);
+
+        let op = operating_point(&circuit, mosfet);
+
+        assert_eq!(op.region, Region::Cutoff);
+        assert_eq!((op.drain_current, op.gm, op.gds), (0.0, 0.0, 0.0));
+        assert!(op
+            .to_string()
+            .starts_with("region = cutoff\nid = 0 A\nvgs = 500 mV\nvds = 5 V\n"));
+    }
+
+    /// A reverse biased bulk raises the threshold by `GAMMA * (sqrt(PHI - vbs) - sqrt(PHI))`.
+    #[test]
+    fn body_effect() {
+        let model = MosfetModel {
+            body_effect: 0.5,
+            ..model()
+        };
+        let (circuit, mosfet) = biased(model, 3.0, 5.0, -2.0);
+
+        let op = operating_point(&circuit, mosfet);
+
+        let threshold = 1.0 + 0.5 * (2.6f64.sqrt() - 0.6f64.sqrt());
+        assert_relative_eq!(op.threshold_voltage, threshold, max_relative = 1e-9);
+        assert_relative_eq!(
+            op.drain_current,
+            1e-4 * (3.0 - threshold).powi(2),
+            max_relative = 1e-9
+        );
+        assert_relative_eq!(
+            op.gmbs,
+            op.gm * 0.5 / (2.0 * 2.6f64.sqrt()),
+            max_relative = 1e-9
+        );
+    }
+
+    /// With the drain below the source, the source acts as the drain and the current reverses.
+    #[test]
+    fn reversed() {
+        let (circuit, mosfet) = biased(model(), 3.0, -0.5, 0.0);
+
+        let op = operating_point(&circuit, mosfet);
+
+        // The gate is 3.5V above the drain, which acts as the source
+        assert_eq!(op.region, Region::Linear);
+        assert_relative_eq!(op.drain_current, -2e-4 * 0.5 * 2.25, max_relative = 1e-9);
+    }
+
+    /// A CMOS inverter swings from rail to rail.
+    #[test]
+    fn inverter() {
+        for (input, output) in [(0.0, 5.0), (5.0, 0.0)] {
+            let mut circuit = Circuit::default();
+            let ground = circuit.push_node();
+            let vdd = circuit.push_node();
+            let vin = circuit.push_node();
+            let vout = circuit.push_node();
+            circuit.add_element(Box::new(DCVoltageSource::new(5.0, vdd, ground)));
+            circuit.add_element(Box::new(DCVoltageSource::new(input, vin, ground)));
+            circuit.add_element(Box::new(
+                Mosfet::new(MosfetKind::Nmos, vout, vin, ground, ground).with_model(model()),
+            ));
+            let pmos = MosfetModel {
+                threshold_voltage: -1.0,
+                ..model()
+            };
+            circuit.add_element(Box::new(
+                Mosfet::new(MosfetKind::Pmos, vout, vin, vdd, vdd).with_model(pmos),
+            ));
+
+            let op = dc_op(&circuit).unwrap();
+
+            assert_relative_eq!(op.voltage(vout), output, epsilon = 1e-6);
+        }
+    }
+
+    /// The gain of a common source stage is `-gm * (RD || 1/gds)`.
+    #[test]
+    fn common_source_gain() {
+        let model = MosfetModel {
+            channel_length_modulation: 0.02,
+            ..model()
+        };
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vdd = circuit.push_node();
+        let bias = circuit.push_node();
+        let gate = circuit.push_node();
+        let drain = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(10.0, vdd, ground)));
+        circuit.add_element(Box::new(Resistor::new(10e3, vdd, drain)));
+        circuit.add_element(Box::new(DCVoltageSource::new(2.0, bias, ground)));
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            gate,
+            bias,
+        )));
+        let mosfet = circuit.add_element(Box::new(
+            Mosfet::new(MosfetKind::Nmos, drain, gate, ground, ground)
+                .with_model(model)
+                .with_dimensions(10e-6, 1e-6),
+        ));
+
+        let op = operating_point(&circuit, mosfet);
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 1.0,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_eq!(op.region, Region::Saturation);
+        let expected = -op.gm / (1e-4 + op.gds);
+        assert_relative_eq!(
+            result.points[0].voltage(drain).re,
+            expected,
+            max_relative = 1e-6
+        );
+    }
+
+    /// In saturation the gate sees two thirds of the oxide capacitance to the source,
+    /// along with the overlap capacitance to the drain.
+    #[test]
+    fn gate_capacitance() {
+        let model = MosfetModel {
+            oxide_thickness: 1e-8,
+            gate_drain_overlap: 1e-10,
+            ..model()
+        };
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let bias = circuit.push_node();
+        let gate = circuit.push_node();
+        let drain = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(3.0, bias, ground)));
+        let source = circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            gate,
+            bias,
+        )));
+        circuit.add_element(Box::new(DCVoltageSource::new(5.0, drain, ground)));
+        circuit.add_element(Box::new(
+            Mosfet::new(MosfetKind::Nmos, drain, gate, ground, ground)
+                .with_model(model)
+                .with_dimensions(10e-6, 1e-6),
+        ));
+
+        let frequency = 1e6;
+        let sweep = Sweep::Linear {
+            start: frequency,
+            stop: frequency,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        let oxide = 3.9 * 8.854_187_8e-12 / 1e-8 * 10e-6 * 1e-6;
+        let capacitance = 2.0 * oxide / 3.0 + 1e-10 * 10e-6;
+        let current = result.points[0].branch_current(source).unwrap();
+        assert_relative_eq!(current.re, 0.0, epsilon = 1e-15);
+        assert_relative_eq!(
+            -current.im,
+            TAU * frequency * capacitance,
+            max_relative = 1e-9
+        );
+    }
+
+    /// A transistor with gate capacitances stays at its operating point when nothing changes.
+    #[test]
+    fn transient_steady_state() {
+        let model = MosfetModel {
+            oxide_thickness: 1e-8,
+            ..model()
+        };
+        let (circuit, mosfet) = biased(model, 3.0, 5.0, 0.0);
+
+        let result = transient(&circuit, 1e-9, 1e-8).unwrap();
+
+        for (_, point) in result.iter() {
+            let op = circuit
+                .element::<Mosfet>(mosfet)
+                .unwrap()
+                .operating_point(point);
+            assert_relative_eq!(op.drain_current, 4e-4, max_relative = 1e-9);
+        }
+    }
+
+    /// Large gate steps are cut short around the threshold, as are large drain steps.
+    #[test]
+    fn limiting() {
+        assert_eq!(limit_gate(10.0, 0.0, 1.0), 1.5);
+        assert_eq!(limit_gate(1.2, 1.0, 1.0), 1.2);
+        assert_eq!(limit_gate(10.0, 2.0, 1.0), 5.0);
+        assert_eq!(limit_drain(10.0, 0.0), 4.0);
+        assert_eq!(limit_drain(-10.0, 1.0), -0.5);
+        assert_eq!(limit_drain(30.0, 5.0), 17.0);
+    }
+}
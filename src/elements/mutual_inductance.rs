@@ -0,0 +1,355 @@
+use nalgebra::Complex;
+use thiserror::Error;
+
+use crate::{
+    runners::{integration::Integration, operating_point::OperatingPoint},
+    BranchId, Circuit, ElementId,
+};
+
+use super::{
+    inductor::Inductor,
+    stamp::{StampContext, Unknown},
+    Element, ParameterError, Terminal,
+};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CouplingError {
+    #[error("the coupling coefficient {0} is not between -1 and 1")]
+    InvalidCoefficient(f64),
+    #[error("at least two inductors must be coupled")]
+    TooFewInductors,
+    #[error("element {0} is not an inductor")]
+    NotAnInductor(usize),
+    #[error("inductor {0} is coupled to itself")]
+    SelfCoupling(usize),
+}
+
+/// Magnetic coupling between two or more [`Inductor`]s, the `K` element of SPICE.
+///
+/// Every pair of the inductors shares a mutual inductance of `k * sqrt(L1 * L2)`,
+/// which adds `M * di2/dt` to the voltage across the first inductor, and vice versa.
+/// Currents flowing into the positive nodes of the inductors add up when `k` is positive.
+///
+/// The mutual inductances follow the inductances of the inductors as they are set
+/// through [`Circuit::set_parameter`], such as by a `.step` line.
+#[derive(Debug, Clone)]
+pub struct MutualInductance {
+    coefficient: f64,
+    /// The handle and inductance of every coupled inductor.
+    inductors: Vec<(ElementId, f64)>,
+}
+
+impl MutualInductance {
+    /// Couples the `inductors` of the `circuit` with the `coefficient` `k`.
+    ///
+    /// Errors if `|k| > 1`, if fewer than two inductors are given,
+    /// if any of them is not an [`Inductor`], or if any is given twice.
+    pub fn new(
+        circuit: &Circuit,
+        coefficient: f64,
+        inductors: &[ElementId],
+    ) -> Result<Self, CouplingError> {
+        if !(-1.0..=1.0).contains(&coefficient) {
+            return Err(CouplingError::InvalidCoefficient(coefficient));
+        }
+        if inductors.len() < 2 {
+            return Err(CouplingError::TooFewInductors);
+        }
+
+        let inductors = inductors
+            .iter()
+            .enumerate()
+            .map(|(position, &id)| {
+                let inductor = circuit
+                    .element::<Inductor>(id)
+                    .ok_or(CouplingError::NotAnInductor(id.index()))?;
+                if inductors[..position].contains(&id) {
+                    return Err(CouplingError::SelfCoupling(id.index()));
+                }
+
+                Ok((id, inductor.inductance()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            coefficient,
+            inductors,
+        })
+    }
+
+    /// The coupling coefficient `k`.
+    pub fn coefficient(&self) -> f64 {
+        self.coefficient
+    }
+
+    /// Every ordered pair of coupled branches with their mutual inductance
+    /// at the present inductances.
+    fn pairs(&self) -> impl Iterator<Item = (BranchId, BranchId, f64)> + '_ {
+        let branch = |id: ElementId| id.branch().expect("an inductor has a branch current");
+        self.inductors
+            .iter()
+            .enumerate()
+            .flat_map(move |(i, &(id_1, l1))| {
+                self.inductors
+                    .iter()
+                    .enumerate()
+                    .filter(move |&(j, _)| j != i)
+                    .map(move |(_, &(id_2, l2))| {
+                        (
+                            branch(id_1),
+                            branch(id_2),
+                            self.coefficient * (l1 * l2).sqrt(),
+                        )
+                    })
+            })
+    }
+}
+
+impl Element for MutualInductance {
+    fn terminals(&self) -> &[Terminal] {
+        &[]
+    }
+
+    /// The currents through the inductors are constant at DC, so they induce no voltage.
+    fn stamp(&self, _context: &mut StampContext<f64>) {}
+
+    /// Stamps the companion model of the flux `M * i2` that the current of the second inductor
+    /// of every pair induces in the first, as a resistance onto the D matrix
+    /// and a voltage source in the branch equation of the first inductor.
+    fn stamp_transient(
+        &self,
+        context: &mut StampContext<f64>,
+        estimate: &OperatingPoint,
+        _state: &mut [f64],
+        integration: &Integration,
+    ) {
+        for (index, (branch_1, branch_2, mutual)) in self.pairs().enumerate() {
+            let current = estimate
+                .branch(branch_2)
+                .expect("the inductor is part of the solved circuit");
+            let (resistance, voltage) =
+                integration.companion(index, current, (mutual * current, mutual));
+
+            context.add(
+                Unknown::Branch(branch_1),
+                Unknown::Branch(branch_2),
+                -resistance,
+            );
+            context.add_branch_voltage(branch_1, voltage);
+        }
+    }
+
+    /// Stamps `-jωM` onto the D matrix between every pair of inductors.
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        omega: f64,
+    ) {
+        for (branch_1, branch_2, mutual) in self.pairs() {
+            context.add(
+                Unknown::Branch(branch_1),
+                Unknown::Branch(branch_2),
+                Complex::new(0.0, -mutual * omega),
+            );
+        }
+    }
+
+    /// `M * i2` of every ordered pair of coupled inductors.
+    fn charges(&self, solution: &OperatingPoint) -> Vec<f64> {
+        self.pairs()
+            .map(|(_, branch_2, mutual)| {
+                mutual
+                    * solution
+                        .branch(branch_2)
+                        .expect("the inductor is part of the solved circuit")
+            })
+            .collect()
+    }
+
+    /// Takes the new inductance of a coupled inductor.
+    fn element_changed(&mut self, id: ElementId, element: &dyn Element) {
+        for (inductor, inductance) in &mut self.inductors {
+            if *inductor == id {
+                *inductance = element
+                    .parameter("l")
+                    .expect("a coupled inductor has an inductance");
+            }
+        }
+    }
+
+    /// `k`, the coupling coefficient, which stays within `[-1, 1]`.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "k" | "coefficient" => Some(self.coefficient),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "k" | "coefficient" if (-1.0..=1.0).contains(&value) => self.coefficient = value,
+            "k" | "coefficient" => {
+                return Err(ParameterError::OutOfRange {
+                    name: name.to_string(),
+                    value,
+                })
+            }
+            _ => return Err(ParameterError::Unknown(name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn resistance(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::new(f64::INFINITY, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::{Complex, DVector};
+
+    use crate::{
+        elements::{ac_volatage_source::ACVoltageSource, inductor::Inductor, resistor::Resistor},
+        runners::{
+            ac::ac,
+            node_labels,
+            operating_point::OperatingPoint,
+            sweep::Sweep,
+            transient::{integrate, TimeSpan},
+        },
+        Circuit,
+    };
+
+    use super::{CouplingError, MutualInductance};
+
+    /// An open secondary of 4 times the inductance, coupled by 0.5, sees the primary voltage,
+    /// and half of it once the primary has the same inductance.
+    #[test]
+    fn open_secondary() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vin = circuit.push_node();
+        let primary = circuit.push_node();
+        let secondary = circuit.push_node();
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            vin,
+            ground,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1.0, vin, primary)));
+        let l1 = circuit.add_element(Box::new(Inductor::new(1e-3, primary, ground)));
+        let l2 = circuit.add_element(Box::new(Inductor::new(4e-3, secondary, ground)));
+        let coupling = MutualInductance::new(&circuit, 0.5, &[l1, l2]).unwrap();
+        circuit.add_element(Box::new(coupling));
+
+        let sweep = Sweep::Linear {
+            start: 100.0,
+            stop: 1000.0,
+            points: 3,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        for point in &result.points {
+            let (v1, v2) = (point.voltage(primary), point.voltage(secondary));
+            assert_relative_eq!(v2.re, v1.re, max_relative = 1e-9);
+            assert_relative_eq!(v2.im, v1.im, max_relative = 1e-9);
+        }
+
+        // Stepping the primary to the inductance of the secondary keeps k, so M doubles
+        circuit.set_parameter(l1, "l", 4e-3).unwrap();
+        let result = ac(&circuit, Sweep::List(vec![100.0])).unwrap();
+
+        let (v1, v2) = (
+            result.points[0].voltage(primary),
+            result.points[0].voltage(secondary),
+        );
+        assert_relative_eq!(v2.re, 0.5 * v1.re, max_relative = 1e-9);
+        assert_relative_eq!(v2.im, 0.5 * v1.im, max_relative = 1e-9);
+    }
+
+    /// The primary of the same pair discharging through a resistor, as in an RL circuit,
+    /// induces `M/L1` of its voltage across the open secondary at every step.
+    #[test]
+    fn discharge() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let primary = circuit.push_node();
+        let secondary = circuit.push_node();
+        circuit.add_element(Box::new(Resistor::new(1.0, primary, ground)));
+        let l1 = circuit.add_element(Box::new(Inductor::new(1e-3, primary, ground)));
+        let l2 = circuit.add_element(Box::new(Inductor::new(4e-3, secondary, ground)));
+        let coupling = MutualInductance::new(&circuit, 0.5, &[l1, l2]).unwrap();
+        circuit.add_element(Box::new(coupling));
+
+        let initial = OperatingPoint::new(
+            node_labels(&circuit),
+            DVector::from_vec(vec![-1.0, 0.0, 1.0, 0.0]),
+        );
+        let result = integrate(&circuit, initial, &TimeSpan::new(1e-6, 1e-3)).unwrap();
+
+        let last = result.points.last().unwrap();
+        assert_relative_eq!(last.voltage(primary), -(-1.0f64).exp(), epsilon = 0.001);
+        for point in &result.points[1..] {
+            assert_relative_eq!(
+                point.voltage(secondary),
+                point.voltage(primary),
+                epsilon = 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_coupling() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let v1 = circuit.push_node();
+        let l1 = circuit.add_element(Box::new(Inductor::new(1e-3, v1, ground)));
+        let l2 = circuit.add_element(Box::new(Inductor::new(1e-3, v1, ground)));
+        let r1 = circuit.add_element(Box::new(Resistor::new(1.0, v1, ground)));
+
+        assert_eq!(
+            MutualInductance::new(&circuit, 1.5, &[l1, l2]).err(),
+            Some(CouplingError::InvalidCoefficient(1.5))
+        );
+        assert_eq!(
+            MutualInductance::new(&circuit, -1.01, &[l1, l2]).err(),
+            Some(CouplingError::InvalidCoefficient(-1.01))
+        );
+        assert_eq!(
+            MutualInductance::new(&circuit, 1.0, &[l1]).err(),
+            Some(CouplingError::TooFewInductors)
+        );
+        assert_eq!(
+            MutualInductance::new(&circuit, 1.0, &[l1, r1]).err(),
+            Some(CouplingError::NotAnInductor(2))
+        );
+        assert_eq!(
+            MutualInductance::new(&circuit, 0.5, &[l1, l1]).err(),
+            Some(CouplingError::SelfCoupling(0))
+        );
+        assert!(MutualInductance::new(&circuit, -1.0, &[l1, l2]).is_ok());
+    }
+}
@@ -2,16 +2,16 @@ use nalgebra::Complex;
 
 use crate::NodeId;
 
-use super::{Element, Terminal};
+use super::{stamp::StampContext, Element, ParameterError, Terminal};
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Resistor {
-    resistance: f32,
+    resistance: f64,
     terminals: [Terminal; 2],
 }
 
 impl Resistor {
-    pub fn new(resistance: f32, node1: NodeId, node2: NodeId) -> Self {
+    pub fn new(resistance: f64, node1: NodeId, node2: NodeId) -> Self {
         Self {
             resistance,
             terminals: [
@@ -28,55 +28,51 @@ impl Element for Resistor {
     }
 
     /// Stamps itself onto the G matrix, which is apart of the A matrix.
-    fn stamp(&self, a_matrix: &mut Vec<f32>, _z_vector: &mut Vec<f32>, n: usize, m: usize) {
-        let node_1 = self.terminals()[0].node.0;
-        let node_2 = self.terminals()[1].node.0;
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        let terminals = self.terminals();
+        context.add_conductance(terminals[0].node, terminals[1].node, self.conductance());
+    }
 
-        match (node_1 > 0, node_2 > 0) {
-            // Neither node is ground
-            (true, true) => {
-                a_matrix[(node_1 - 1) * (n + m)] += self.conductance();
-                a_matrix[(node_2 - 1) + (node_1 - 1) * (n - 1 + m)] -= self.conductance();
+    /// `r`, the resistance in Ohms.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "r" | "resistance" => Some(self.resistance),
+            _ => None,
+        }
+    }
 
-                a_matrix[(node_2 - 1) * (n + m)] += self.conductance();
-                a_matrix[(node_1 - 1) + (node_2 - 1) * (n - 1 + m)] -= self.conductance();
-            }
-            // Only node 2 is ground
-            (true, false) => {
-                a_matrix[(node_1 - 1) * (n + m)] += self.conductance();
-            }
-            // Only node 1 is ground
-            (false, true) => {
-                a_matrix[(node_2 - 1) * (n + m)] += self.conductance();
-            }
-            // Both nodes are ground
-            _ => (),
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "r" | "resistance" => self.resistance = value,
+            _ => return Err(ParameterError::Unknown(name.to_string())),
         }
+
+        Ok(())
     }
 
-    fn dc_voltage(&self) -> f32 {
+    fn dc_voltage(&self) -> f64 {
         0.0
     }
 
-    fn ac_voltage(&self) -> Complex<f32> {
+    fn ac_voltage(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn dc_current(&self) -> f32 {
+    fn dc_current(&self) -> f64 {
         0.0
     }
 
-    fn ac_current(&self) -> Complex<f32> {
+    fn ac_current(&self) -> Complex<f64> {
         Complex::ZERO
     }
 
-    fn resistance(&self) -> f32 {
+    fn resistance(&self) -> f64 {
         self.resistance
     }
 
     /// The rectangular impedence of a resistor is equal to `R + j0`,
     /// where `R` is the resistance in Ohms.
-    fn impedance(&self, _frequency: f32) -> Complex<f32> {
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
         Complex::new(self.resistance, 0.0)
     }
 }
@@ -0,0 +1,95 @@
+//! The values shared by the independent sources in each analysis.
+
+use nalgebra::{Complex, ComplexField};
+
+use super::{waveform::Waveform, ParameterError};
+
+/// The value of an independent source in every analysis, like the DC, AC and transient
+/// specifications of a SPICE source such as `V1 n1 n2 DC 1 AC 1 0 SIN(0 1 1k)`.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub(crate) struct Source {
+    /// The value in a DC operating point.
+    pub(crate) dc: f64,
+    /// The phasor in an AC analysis, which is built from `ac_polar` by [`Source::set_ac`].
+    pub(crate) ac: Complex<f64>,
+    /// The magnitude and phase in degrees of the phasor, kept apart so that the phase
+    /// survives a magnitude of 0.
+    pub(crate) ac_polar: (f64, f64),
+    /// The shape over time in a transient analysis, which is the DC value without one.
+    pub(crate) waveform: Option<Waveform>,
+}
+
+impl Source {
+    /// A source of the `phasor` in an AC analysis, and 0 otherwise.
+    pub(crate) fn from_phasor(phasor: Complex<f64>) -> Self {
+        Self {
+            ac: phasor,
+            ac_polar: (phasor.modulus(), phasor.argument().to_degrees()),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the phasor in an AC analysis to `magnitude` with a `phase` in degrees.
+    pub(crate) fn set_ac(&mut self, magnitude: f64, phase: f64) {
+        let (sin, cos) = phase.to_radians().sin_cos();
+        self.ac = Complex::new(magnitude * cos, magnitude * sin);
+        self.ac_polar = (magnitude, phase);
+    }
+
+    /// The value at the `time` of a transient analysis, or the DC value without one.
+    pub(crate) fn value(&self, time: Option<f64>) -> f64 {
+        match (&self.waveform, time) {
+            (Some(waveform), Some(time)) => waveform.value(time),
+            _ => self.dc,
+        }
+    }
+
+    /// `dc`, the DC value, and `acmag` and `acphase`, the magnitude and phase in degrees
+    /// of the AC phasor.
+    pub(crate) fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "dc" => Some(self.dc),
+            "acmag" => Some(self.ac_polar.0),
+            "acphase" => Some(self.ac_polar.1),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "dc" => self.dc = value,
+            "acmag" => self.set_ac(value, self.ac_polar.1),
+            "acphase" => self.set_ac(self.ac_polar.0, value),
+            _ => return Err(ParameterError::Unknown(name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn breakpoints(&self, stop: f64) -> Vec<f64> {
+        self.waveform
+            .as_ref()
+            .map_or_else(Vec::new, |waveform| waveform.breakpoints(stop))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Source;
+
+    /// The phase is kept at a magnitude of 0, so the order the two are set in does not matter.
+    #[test]
+    fn ac_parameters() {
+        let mut first = Source::default();
+        first.set_parameter("acphase", 90.0).unwrap();
+        first.set_parameter("acmag", 2.0).unwrap();
+        let mut second = Source::default();
+        second.set_parameter("acmag", 2.0).unwrap();
+        second.set_parameter("acphase", 90.0).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first.parameter("acphase"), Some(90.0));
+        assert!(first.ac.re.abs() < 1e-12);
+        assert_eq!(first.ac.im, 2.0);
+    }
+}
@@ -0,0 +1,253 @@
+use nalgebra::ComplexField;
+
+use crate::{sparse::TripletMatrix, BranchId, NodeId};
+
+/// An unknown of the MNA system, which is a row and a column of the `a_matrix`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unknown {
+    /// The voltage of a node, where ground is not an unknown and is left out.
+    Node(NodeId),
+    /// The current through a branch.
+    Branch(BranchId),
+}
+
+/// Collects the stamps of every element into the `a_matrix` and `z_vector`
+/// of `A * x = z`, without the elements knowing how they are laid out.
+///
+/// The `a_matrix` consists of 4 different matrices: G, B, C, D.
+/// These are ordered in like so:
+///
+/// `[G B]`\
+/// `[C D]`
+///
+/// Stamps on ground are dropped, as its voltage is fixed at 0V.
+/// `T` is `f64` for the DC and transient runners, and a complex phasor for the AC runner.
+///
+/// ```
+/// use spice_rs::{elements::stamp::StampContext, BranchId, NodeId};
+///
+/// let (ground, v1) = (NodeId(0), NodeId(1));
+/// let mut context = StampContext::new(2, 1);
+/// context.add_conductance(v1, ground, 0.5);
+/// context.add_branch(BranchId(0), v1, ground);
+/// context.add_branch_voltage(BranchId(0), 10.0);
+///
+/// let (a_matrix, z_vector) = context.into_system();
+/// assert_eq!(a_matrix.to_csc().get(0, 0), 0.5);
+/// assert_eq!(a_matrix.to_csc().get(1, 0), 1.0);
+/// assert_eq!(z_vector, vec![0.0, 10.0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StampContext<T: ComplexField> {
+    a_matrix: TripletMatrix<T>,
+    z_vector: Vec<T>,
+    node_count: usize,
+    limited: bool,
+    time: Option<f64>,
+}
+
+impl<T: ComplexField> StampContext<T> {
+    /// An empty system of a circuit with `node_count` nodes, including ground,
+    /// and `branch_count` branch currents.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no nodes, not even ground.
+    pub fn new(node_count: usize, branch_count: usize) -> Self {
+        assert!(node_count > 0, "a circuit has at least a ground node");
+
+        let size = node_count - 1 + branch_count;
+        Self {
+            a_matrix: TripletMatrix::new(size),
+            z_vector: vec![T::zero(); size],
+            node_count,
+            limited: false,
+            time: None,
+        }
+    }
+
+    /// Adds `value` to the `a_matrix` entry in the equation of `row` for the unknown `column`.
+    pub fn add(&mut self, row: Unknown, column: Unknown, value: T) {
+        if let (Some(row), Some(column)) = (self.index(row), self.index(column)) {
+            self.a_matrix.add(row, column, value);
+        }
+    }
+
+    /// Adds `value` to the `z_vector` entry in the equation of `row`.
+    pub fn add_rhs(&mut self, row: Unknown, value: T) {
+        if let Some(row) = self.index(row) {
+            self.z_vector[row] += value;
+        }
+    }
+
+    /// Stamps a `conductance` between nodes `a` and `b` onto the G matrix.
+    ///
+    /// In an AC analysis this is a complex admittance.
+    pub fn add_conductance(&mut self, a: NodeId, b: NodeId, conductance: T) {
+        let (a, b) = (Unknown::Node(a), Unknown::Node(b));
+        self.add(a, a, conductance.clone());
+        self.add(b, b, conductance.clone());
+        self.add(a, b, -conductance.clone());
+        self.add(b, a, -conductance);
+    }
+
+    /// Stamps a `current` flowing into node `a` and out of node `b` onto the `z_vector`.
+    pub fn add_current(&mut self, a: NodeId, b: NodeId, current: T) {
+        self.add_rhs(Unknown::Node(a), current.clone());
+        self.add_rhs(Unknown::Node(b), -current);
+    }
+
+    /// Stamps a current of `transconductance * (v(control_positive) - v(control_negative))`
+    /// flowing from node `a` through the element to node `b` onto the G matrix.
+    pub fn add_transconductance(
+        &mut self,
+        a: NodeId,
+        b: NodeId,
+        control_positive: NodeId,
+        control_negative: NodeId,
+        transconductance: T,
+    ) {
+        let (a, b) = (Unknown::Node(a), Unknown::Node(b));
+        let (positive, negative) = (
+            Unknown::Node(control_positive),
+            Unknown::Node(control_negative),
+        );
+        self.add(a, positive, transconductance.clone());
+        self.add(a, negative, -transconductance.clone());
+        self.add(b, positive, -transconductance.clone());
+        self.add(b, negative, transconductance);
+    }
+
+    /// Stamps the current of `branch` flowing from `positive` through the element to `negative`
+    /// onto the B matrix, and the voltage across it onto the C matrix.
+    ///
+    /// The equation of the branch is then `v(positive) - v(negative) = 0`,
+    /// which [`StampContext::add_branch_voltage`] and
+    /// [`StampContext::add_branch_impedance`] add to.
+    pub fn add_branch(&mut self, branch: BranchId, positive: NodeId, negative: NodeId) {
+        let branch = Unknown::Branch(branch);
+        for (node, sign) in [(positive, T::one()), (negative, -T::one())] {
+            let node = Unknown::Node(node);
+            self.add(node, branch, sign.clone());
+            self.add(branch, node, sign);
+        }
+    }
+
+    /// Adds `voltage` to the right-hand side of the equation of `branch`.
+    pub fn add_branch_voltage(&mut self, branch: BranchId, voltage: T) {
+        self.add_rhs(Unknown::Branch(branch), voltage);
+    }
+
+    /// Stamps an `impedance` in series with `branch` onto the D matrix, so that its equation
+    /// becomes `v(positive) - v(negative) - impedance * i(branch) = voltage`.
+    pub fn add_branch_impedance(&mut self, branch: BranchId, impedance: T) {
+        let branch = Unknown::Branch(branch);
+        self.add(branch, branch, -impedance);
+    }
+
+    /// Tells the Newton-Raphson solver that an element was linearized at a limited
+    /// estimate instead of the one it was given, so the solution has not converged yet.
+    pub fn mark_limited(&mut self) {
+        self.limited = true;
+    }
+
+    /// Whether any element was linearized at a limited estimate.
+    pub fn is_limited(&self) -> bool {
+        self.limited
+    }
+
+    /// Sets the `time` in seconds of the transient analysis being stamped, at which
+    /// independent sources take the value of their [`Waveform`](super::waveform::Waveform).
+    pub fn set_time(&mut self, time: f64) {
+        self.time = Some(time);
+    }
+
+    /// The time of the transient analysis being stamped, or `None` in a DC or AC analysis.
+    pub fn time(&self) -> Option<f64> {
+        self.time
+    }
+
+    /// The stamped `a_matrix` and `z_vector`.
+    pub fn into_system(self) -> (TripletMatrix<T>, Vec<T>) {
+        (self.a_matrix, self.z_vector)
+    }
+
+    /// The row and column of `unknown`, or `None` for ground.
+    fn index(&self, unknown: Unknown) -> Option<usize> {
+        match unknown {
+            Unknown::Node(NodeId(0)) => None,
+            Unknown::Node(NodeId(node)) => Some(node - 1),
+            Unknown::Branch(BranchId(branch)) => Some(self.node_count - 1 + branch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BranchId, NodeId};
+
+    use super::StampContext;
+
+    /// Only the diagonal of the node that is not ground is stamped.
+    #[test]
+    fn conductance_to_ground() {
+        let mut context = StampContext::new(3, 0);
+        context.add_conductance(NodeId(0), NodeId(2), 2.0);
+
+        let (a_matrix, _) = context.into_system();
+        let a_matrix = a_matrix.to_csc();
+
+        assert_eq!(a_matrix.entry_count(), 1);
+        assert_eq!(a_matrix.get(1, 1), 2.0);
+    }
+
+    #[test]
+    fn conductance_between_nodes() {
+        let mut context = StampContext::new(3, 0);
+        context.add_conductance(NodeId(1), NodeId(2), 2.0);
+        context.add_current(NodeId(1), NodeId(2), 3.0);
+
+        let (a_matrix, z_vector) = context.into_system();
+        let a_matrix = a_matrix.to_csc();
+
+        assert_eq!(a_matrix.get(0, 0), 2.0);
+        assert_eq!(a_matrix.get(0, 1), -2.0);
+        assert_eq!(a_matrix.get(1, 0), -2.0);
+        assert_eq!(a_matrix.get(1, 1), 2.0);
+        assert_eq!(z_vector, vec![3.0, -3.0]);
+    }
+
+    /// The current leaving `a` depends on the voltage between the control nodes.
+    #[test]
+    fn transconductance() {
+        let mut context = StampContext::new(4, 0);
+        context.add_transconductance(NodeId(1), NodeId(0), NodeId(2), NodeId(3), 2.0);
+
+        let (a_matrix, _) = context.into_system();
+        let a_matrix = a_matrix.to_csc();
+
+        assert_eq!(a_matrix.get(0, 1), 2.0);
+        assert_eq!(a_matrix.get(0, 2), -2.0);
+        assert_eq!(a_matrix.entry_count(), 2);
+    }
+
+    /// Branch currents come after the node voltages, excluding ground.
+    #[test]
+    fn branch() {
+        let mut context = StampContext::new(3, 2);
+        context.add_branch(BranchId(1), NodeId(2), NodeId(1));
+        context.add_branch_voltage(BranchId(1), 5.0);
+        context.add_branch_impedance(BranchId(1), 0.1);
+
+        let (a_matrix, z_vector) = context.into_system();
+        let a_matrix = a_matrix.to_csc();
+
+        assert_eq!(a_matrix.get(1, 3), 1.0);
+        assert_eq!(a_matrix.get(0, 3), -1.0);
+        assert_eq!(a_matrix.get(3, 1), 1.0);
+        assert_eq!(a_matrix.get(3, 0), -1.0);
+        assert_eq!(a_matrix.get(3, 3), -0.1);
+        assert_eq!(a_matrix.entry_count(), 5);
+        assert_eq!(z_vector, vec![0.0, 0.0, 0.0, 5.0]);
+    }
+}
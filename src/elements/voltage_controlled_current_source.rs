@@ -0,0 +1,159 @@
+use nalgebra::{Complex, ComplexField};
+
+use crate::{runners::operating_point::OperatingPoint, NodeId};
+
+use super::{stamp::StampContext, Element, ParameterError, Terminal};
+
+/// A current source of `transconductance` times the voltage between a pair of control nodes,
+/// the `G` element of SPICE.
+///
+/// The current flows from the positive node, through the source, to the negative node.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct VoltageControlledCurrentSource {
+    transconductance: f64,
+    /// The positive and negative output and control nodes.
+    terminals: [Terminal; 4],
+}
+
+impl VoltageControlledCurrentSource {
+    pub fn new(
+        transconductance: f64,
+        positive_node: NodeId,
+        negative_node: NodeId,
+        control_positive: NodeId,
+        control_negative: NodeId,
+    ) -> Self {
+        Self {
+            transconductance,
+            terminals: [
+                Terminal::new_positive(positive_node),
+                Terminal::new_negative(negative_node),
+                Terminal::new_positive(control_positive),
+                Terminal::new_negative(control_negative),
+            ],
+        }
+    }
+
+    fn stamp_transconductance<T: ComplexField<RealField = f64>>(
+        &self,
+        context: &mut StampContext<T>,
+    ) {
+        let [positive, negative, control_positive, control_negative] =
+            self.terminals.map(|terminal| terminal.node);
+        context.add_transconductance(
+            positive,
+            negative,
+            control_positive,
+            control_negative,
+            T::from_real(self.transconductance),
+        );
+    }
+}
+
+impl Element for VoltageControlledCurrentSource {
+    fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        self.stamp_transconductance(context);
+    }
+
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        _omega: f64,
+    ) {
+        self.stamp_transconductance(context);
+    }
+
+    /// `gain`, the transconductance in Siemens.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "gain" | "transconductance" => Some(self.transconductance),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "gain" | "transconductance" => self.transconductance = value,
+            _ => return Err(ParameterError::Unknown(name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn resistance(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::new(f64::INFINITY, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource, dc_voltage_source::DCVoltageSource,
+            resistor::Resistor,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep},
+        Circuit,
+    };
+
+    use super::VoltageControlledCurrentSource;
+
+    /// 1mA/V drawn out of a 1kΩ load inverts the control voltage.
+    #[test]
+    fn transconductance() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let bias = circuit.push_node();
+        let vin = circuit.push_node();
+        let vout = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(2.0, bias, ground)));
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            vin,
+            bias,
+        )));
+        circuit.add_element(Box::new(VoltageControlledCurrentSource::new(
+            1e-3, vout, ground, vin, ground,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, vout, ground)));
+
+        let op = dc_op(&circuit).unwrap();
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 1.0,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_relative_eq!(op.voltage(vout), -2.0);
+        assert_eq!(result.points[0].voltage(vout), Complex::new(-1.0, 0.0));
+    }
+}
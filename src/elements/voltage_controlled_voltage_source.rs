@@ -0,0 +1,179 @@
+use nalgebra::{Complex, ComplexField};
+
+use crate::{runners::operating_point::OperatingPoint, BranchId, NodeId};
+
+use super::{
+    stamp::{StampContext, Unknown},
+    Element, ParameterError, Terminal,
+};
+
+/// A voltage source of `gain` times the voltage between a pair of control nodes,
+/// the `E` element of SPICE.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct VoltageControlledVoltageSource {
+    gain: f64,
+    /// The positive and negative output and control nodes.
+    terminals: [Terminal; 4],
+    branch: BranchId,
+}
+
+impl VoltageControlledVoltageSource {
+    pub fn new(
+        gain: f64,
+        positive_node: NodeId,
+        negative_node: NodeId,
+        control_positive: NodeId,
+        control_negative: NodeId,
+    ) -> Self {
+        Self {
+            gain,
+            terminals: [
+                Terminal::new_positive(positive_node),
+                Terminal::new_negative(negative_node),
+                Terminal::new_positive(control_positive),
+                Terminal::new_negative(control_negative),
+            ],
+            branch: BranchId::default(),
+        }
+    }
+
+    /// Stamps the equation of the branch, `v(positive) - v(negative) - gain * v(control) = 0`.
+    fn stamp_gain<T: ComplexField<RealField = f64>>(&self, context: &mut StampContext<T>) {
+        let [positive, negative, control_positive, control_negative] =
+            self.terminals.map(|terminal| terminal.node);
+        let branch = Unknown::Branch(self.branch);
+
+        context.add_branch(self.branch, positive, negative);
+        context.add(
+            branch,
+            Unknown::Node(control_positive),
+            T::from_real(-self.gain),
+        );
+        context.add(
+            branch,
+            Unknown::Node(control_negative),
+            T::from_real(self.gain),
+        );
+    }
+}
+
+impl Element for VoltageControlledVoltageSource {
+    fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    fn stamp(&self, context: &mut StampContext<f64>) {
+        self.stamp_gain(context);
+    }
+
+    fn stamp_ac(
+        &self,
+        context: &mut StampContext<Complex<f64>>,
+        _operating_point: &OperatingPoint,
+        _omega: f64,
+    ) {
+        self.stamp_gain(context);
+    }
+
+    fn is_b_c_element(&self) -> bool {
+        true
+    }
+
+    fn set_branch(&mut self, branch: BranchId) {
+        self.branch = branch;
+    }
+
+    /// `gain`, the voltage gain.
+    fn parameter(&self, name: &str) -> Option<f64> {
+        match name {
+            "gain" => Some(self.gain),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        match name {
+            "gain" => self.gain = value,
+            _ => return Err(ParameterError::Unknown(name.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn dc_voltage(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_voltage(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn dc_current(&self) -> f64 {
+        0.0
+    }
+
+    fn ac_current(&self) -> Complex<f64> {
+        Complex::ZERO
+    }
+
+    fn resistance(&self) -> f64 {
+        0.0
+    }
+
+    fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+        Complex::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource, dc_voltage_source::DCVoltageSource,
+            resistor::Resistor,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep},
+        Circuit,
+    };
+
+    use super::VoltageControlledVoltageSource;
+
+    /// An amplifier with a gain of 10, driven from the middle of a divider
+    /// that it does not load.
+    #[test]
+    fn amplifier() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let bias = circuit.push_node();
+        let vin = circuit.push_node();
+        let middle = circuit.push_node();
+        let vout = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(2.0, bias, ground)));
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(0.0, 1.0),
+            vin,
+            bias,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, vin, middle)));
+        circuit.add_element(Box::new(Resistor::new(1e3, middle, ground)));
+        circuit.add_element(Box::new(VoltageControlledVoltageSource::new(
+            -10.0, vout, ground, middle, ground,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, vout, ground)));
+
+        let op = dc_op(&circuit).unwrap();
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 1.0,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_relative_eq!(op.voltage(vout), -10.0);
+        assert_relative_eq!(result.points[0].voltage(vout).re, 0.0);
+        assert_relative_eq!(result.points[0].voltage(vout).im, -5.0);
+    }
+}
@@ -0,0 +1,333 @@
+use std::f64::consts::TAU;
+
+/// The most periods of a pulse whose corners are breakpoints. The corners of any later periods
+/// are left to the time step, as a transient analysis could not step onto them all anyway.
+const MAX_PULSE_PERIODS: usize = 100_000;
+
+/// The shape of an independent source over time, evaluated at every time point
+/// of a transient analysis, as in the transient specification of a SPICE source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Waveform {
+    /// `PULSE(V1 V2 TD TR TF PW PER)`, a trapezoidal pulse from `initial` to `pulsed`,
+    /// repeating every `period`.
+    ///
+    /// Rise and fall times of 0 are instantaneous edges, and a `period` of infinity
+    /// is a single pulse.
+    Pulse {
+        initial: f64,
+        pulsed: f64,
+        delay: f64,
+        rise: f64,
+        fall: f64,
+        width: f64,
+        period: f64,
+    },
+    /// `SIN(VO VA FREQ TD THETA PHASE)`, a sine wave of `frequency` in Hertz that starts
+    /// after `delay` and decays by `exp(-damping * t)`.
+    ///
+    /// `phase` is in degrees, and before the `delay` the source stays at its starting value.
+    Sine {
+        offset: f64,
+        amplitude: f64,
+        frequency: f64,
+        delay: f64,
+        damping: f64,
+        phase: f64,
+    },
+    /// `EXP(V1 V2 TD1 TAU1 TD2 TAU2)`, an exponential rise from `initial` towards `pulsed`
+    /// after `rise_delay`, and an exponential fall back towards `initial` after `fall_delay`.
+    Exponential {
+        initial: f64,
+        pulsed: f64,
+        rise_delay: f64,
+        rise_time_constant: f64,
+        fall_delay: f64,
+        fall_time_constant: f64,
+    },
+    /// `PWL(T1 V1 T2 V2 ...)`, straight lines between `(time, value)` points in
+    /// increasing order of time.
+    ///
+    /// The source holds the first value before the first point, and the last one after the
+    /// last point.
+    PiecewiseLinear(Vec<(f64, f64)>),
+    /// `SFFM(VO VA FC MDI FS)`, a sine wave of `carrier` frequency, frequency modulated by
+    /// a sine of `signal` frequency with a `modulation_index`.
+    SingleFrequencyFm {
+        offset: f64,
+        amplitude: f64,
+        carrier: f64,
+        modulation_index: f64,
+        signal: f64,
+    },
+}
+
+impl Waveform {
+    /// The value of the source at `time` in seconds.
+    pub fn value(&self, time: f64) -> f64 {
+        match *self {
+            Self::Pulse {
+                initial,
+                pulsed,
+                delay,
+                rise,
+                fall,
+                width,
+                period,
+            } => {
+                let mut time = time - delay;
+                if time <= 0.0 {
+                    return initial;
+                }
+                if period.is_finite() && period > 0.0 {
+                    time %= period;
+                }
+
+                if time < rise {
+                    initial + (pulsed - initial) * time / rise
+                } else if time <= rise + width {
+                    pulsed
+                } else if time < rise + width + fall {
+                    pulsed + (initial - pulsed) * (time - rise - width) / fall
+                } else {
+                    initial
+                }
+            }
+            Self::Sine {
+                offset,
+                amplitude,
+                frequency,
+                delay,
+                damping,
+                phase,
+            } => {
+                let phase = phase.to_radians();
+                let time = time - delay;
+                if time <= 0.0 {
+                    return offset + amplitude * phase.sin();
+                }
+
+                offset
+                    + amplitude * (-damping * time).exp() * (TAU * frequency * time + phase).sin()
+            }
+            Self::Exponential {
+                initial,
+                pulsed,
+                rise_delay,
+                rise_time_constant,
+                fall_delay,
+                fall_time_constant,
+            } => {
+                let mut value = initial;
+                if time > rise_delay {
+                    value += (pulsed - initial)
+                        * (1.0 - (-(time - rise_delay) / rise_time_constant).exp());
+                }
+                if time > fall_delay {
+                    value += (initial - pulsed)
+                        * (1.0 - (-(time - fall_delay) / fall_time_constant).exp());
+                }
+
+                value
+            }
+            Self::PiecewiseLinear(ref points) => {
+                let Some(&(first_time, first_value)) = points.first() else {
+                    return 0.0;
+                };
+                if time <= first_time {
+                    return first_value;
+                }
+
+                points
+                    .windows(2)
+                    .find(|pair| time <= pair[1].0)
+                    .map(|pair| {
+                        let ((t1, v1), (t2, v2)) = (pair[0], pair[1]);
+                        v1 + (v2 - v1) * (time - t1) / (t2 - t1)
+                    })
+                    .unwrap_or(points[points.len() - 1].1)
+            }
+            Self::SingleFrequencyFm {
+                offset,
+                amplitude,
+                carrier,
+                modulation_index,
+                signal,
+            } => {
+                offset
+                    + amplitude
+                        * (TAU * carrier * time + modulation_index * (TAU * signal * time).sin())
+                            .sin()
+            }
+        }
+    }
+
+    /// The times up to `stop` at which the waveform has a corner, in increasing order,
+    /// which a transient analysis steps onto instead of over.
+    ///
+    /// A pulse that is cut short by its period only has the corners before the next period
+    /// starts, and only the corners of its first 100,000 periods are given.
+    pub fn breakpoints(&self, stop: f64) -> Vec<f64> {
+        let mut breakpoints = match *self {
+            Self::Pulse {
+                delay,
+                rise,
+                fall,
+                width,
+                period,
+                ..
+            } => {
+                let periodic = period.is_finite() && period > 0.0;
+                // As in `value`, a period shorter than the pulse cuts it short
+                let corners: Vec<f64> = [0.0, rise, rise + width, rise + width + fall]
+                    .into_iter()
+                    .filter(|&corner| !periodic || corner < period)
+                    .collect();
+                let periods = match periodic {
+                    true => MAX_PULSE_PERIODS,
+                    false => 1,
+                };
+                (0..periods)
+                    .map(|index| delay + index as f64 * period.min(f64::MAX))
+                    .take_while(|&start| start <= stop)
+                    .flat_map(|start| corners.iter().map(move |corner| start + corner))
+                    .collect()
+            }
+            Self::Sine { delay, .. } => vec![delay],
+            Self::Exponential {
+                rise_delay,
+                fall_delay,
+                ..
+            } => {
+                vec![rise_delay, fall_delay]
+            }
+            Self::PiecewiseLinear(ref points) => points.iter().map(|&(time, _)| time).collect(),
+            Self::SingleFrequencyFm { .. } => Vec::new(),
+        };
+        breakpoints.retain(|&time| time <= stop);
+        breakpoints.sort_by(f64::total_cmp);
+        breakpoints.dedup();
+
+        breakpoints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::Waveform;
+
+    #[test]
+    fn pulse() {
+        let pulse = Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 1.0,
+            delay: 1.0,
+            rise: 1.0,
+            fall: 2.0,
+            width: 1.0,
+            period: 10.0,
+        };
+
+        assert_eq!(pulse.value(0.0), 0.0);
+        assert_eq!(pulse.value(1.5), 0.5);
+        assert_eq!(pulse.value(2.5), 1.0);
+        assert_eq!(pulse.value(4.0), 0.5);
+        assert_eq!(pulse.value(6.0), 0.0);
+        assert_eq!(pulse.value(11.5), 0.5);
+        assert_eq!(
+            pulse.breakpoints(12.0),
+            vec![1.0, 2.0, 3.0, 5.0, 11.0, 12.0]
+        );
+    }
+
+    /// A pulse longer than its period is cut short at every period, and a period far shorter
+    /// than the stop time gives a bounded number of breakpoints.
+    #[test]
+    fn short_period() {
+        let pulse = |width, period| Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 1.0,
+            delay: 0.0,
+            rise: 1.0,
+            fall: 1.0,
+            width,
+            period,
+        };
+
+        assert_eq!(
+            pulse(2.0, 3.0).breakpoints(7.0),
+            vec![0.0, 1.0, 3.0, 4.0, 6.0, 7.0]
+        );
+        let breakpoints = pulse(1e-15, 1e-15).breakpoints(1e-3);
+        assert_eq!(breakpoints.len(), super::MAX_PULSE_PERIODS);
+        assert!(breakpoints.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn sine() {
+        let sine = Waveform::Sine {
+            offset: 1.0,
+            amplitude: 2.0,
+            frequency: 0.25,
+            delay: 1.0,
+            damping: 0.0,
+            phase: 90.0,
+        };
+
+        assert_relative_eq!(sine.value(0.0), 3.0);
+        assert_relative_eq!(sine.value(2.0), 1.0, epsilon = 1e-12);
+        assert_relative_eq!(sine.value(3.0), -1.0);
+        assert_eq!(sine.breakpoints(10.0), vec![1.0]);
+    }
+
+    #[test]
+    fn exponential() {
+        let exponential = Waveform::Exponential {
+            initial: 0.0,
+            pulsed: 1.0,
+            rise_delay: 1.0,
+            rise_time_constant: 1.0,
+            fall_delay: 3.0,
+            fall_time_constant: 2.0,
+        };
+
+        assert_eq!(exponential.value(0.5), 0.0);
+        assert_relative_eq!(exponential.value(2.0), 1.0 - (-1.0f64).exp());
+        assert_relative_eq!(
+            exponential.value(5.0),
+            (1.0 - (-4.0f64).exp()) - (1.0 - (-1.0f64).exp())
+        );
+        assert_eq!(exponential.breakpoints(2.0), vec![1.0]);
+    }
+
+    #[test]
+    fn piecewise_linear() {
+        let pwl = Waveform::PiecewiseLinear(vec![(1.0, 0.0), (2.0, 4.0), (4.0, 0.0)]);
+
+        assert_eq!(pwl.value(0.0), 0.0);
+        assert_eq!(pwl.value(1.5), 2.0);
+        assert_eq!(pwl.value(3.5), 1.0);
+        assert_eq!(pwl.value(5.0), 0.0);
+        assert_eq!(pwl.breakpoints(3.0), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn single_frequency_fm() {
+        let sffm = Waveform::SingleFrequencyFm {
+            offset: 0.5,
+            amplitude: 1.0,
+            carrier: 1.0,
+            modulation_index: 2.0,
+            signal: 0.25,
+        };
+
+        assert_eq!(sffm.value(0.0), 0.5);
+        // The carrier is half way through its cycle, and the signal an eighth of the way
+        assert_relative_eq!(
+            sffm.value(0.5),
+            0.5 + (std::f64::consts::PI + std::f64::consts::SQRT_2).sin()
+        );
+        assert!(sffm.breakpoints(1.0).is_empty());
+    }
+}
@@ -1,12 +1,31 @@
-use elements::Element;
+use std::{any::Any, collections::HashMap};
+
+use elements::{Element, ParameterError};
+use runners::options::Options;
 
 pub mod elements;
+pub mod netlist;
 pub mod runners;
+pub mod sparse;
+pub mod units;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Circuit {
     pub nodes: Vec<NodeId>,
-    pub elements: Vec<Box<dyn Element>>,
+    node_ids: HashMap<String, NodeId>,
+    node_names: HashMap<NodeId, String>,
+    elements: Vec<Box<dyn Element>>,
+    branch_count: usize,
+    /// The tolerances and iteration limits used to solve the circuit.
+    pub options: Options,
+    /// The voltages of nodes at the start of a transient analysis, as set by a SPICE `.ic` line.
+    ///
+    /// The operating point the analysis starts from holds the nodes at them, and an analysis
+    /// that skips the operating point starts at them.
+    pub initial_conditions: HashMap<NodeId, f64>,
+    /// The voltages of nodes that the Newton-Raphson iteration of an operating point starts
+    /// from, as set by a SPICE `.nodeset` line, such as to pick one state of a latch.
+    pub nodesets: HashMap<NodeId, f64>,
 }
 
 impl Circuit {
@@ -34,9 +53,114 @@ impl Circuit {
         next_node
     }
 
+    /// Gets the node called `name`, adding it to the node list if it does not exist yet.
+    ///
+    /// `0` and `gnd` (in any case) are ground, which is added first if the circuit has no nodes.
+    ///
+    /// ```
+    /// use spice_rs::{Circuit, NodeId};
+    ///
+    /// let mut circuit = Circuit::default();
+    ///
+    /// let vin = circuit.node("vin");
+    /// let vout = circuit.node("vout");
+    ///
+    /// assert_eq!(circuit.node("gnd"), NodeId(0));
+    /// assert_eq!(circuit.node("vin"), vin);
+    /// assert_eq!(vout, NodeId(2));
+    /// assert_eq!(circuit.node_name(vout), Some("vout"));
+    /// ```
+    pub fn node(&mut self, name: &str) -> NodeId {
+        if self.nodes.is_empty() {
+            self.push_node();
+        }
+        if let Some(node) = self.find_node(name) {
+            return node;
+        }
+
+        let node = self.push_node();
+        self.node_ids.insert(name.to_string(), node);
+        self.node_names.insert(node, name.to_string());
+
+        node
+    }
+
+    /// The node called `name`, if it exists.
+    pub fn find_node(&self, name: &str) -> Option<NodeId> {
+        if name == "0" || name.eq_ignore_ascii_case("gnd") {
+            return (!self.nodes.is_empty()).then_some(NodeId(0));
+        }
+
+        self.node_ids.get(name).copied()
+    }
+
+    /// The name of `node`, if it was added through [`Circuit::node`].
+    ///
+    /// Ground is always called `0`.
+    pub fn node_name(&self, node: NodeId) -> Option<&str> {
+        match node.0 {
+            0 => (!self.nodes.is_empty()).then_some("0"),
+            _ => self.node_names.get(&node).map(String::as_str),
+        }
+    }
+
+    /// The name of `node`, or its number if it has no name.
+    pub fn node_label(&self, node: NodeId) -> String {
+        self.node_name(node)
+            .map(str::to_string)
+            .unwrap_or_else(|| node.0.to_string())
+    }
+
     /// Adds a new element to the circuit.
-    pub fn add_element(&mut self, element: Box<dyn Element>) {
+    ///
+    /// Elements that stamp onto the B and C matrices are assigned the next free branch current,
+    /// and elements with nodes inside of them are assigned new unnamed nodes.
+    /// The returned [`ElementId`] is used to look up that branch current in a runner's result.
+    ///
+    /// ```
+    /// use spice_rs::{
+    ///     elements::{dc_voltage_source::DCVoltageSource, resistor::Resistor},
+    ///     BranchId, Circuit,
+    /// };
+    ///
+    /// let mut circuit = Circuit::default();
+    ///
+    /// let ground = circuit.push_node();
+    /// let v1 = circuit.push_node();
+    /// let v2 = circuit.push_node();
+    ///
+    /// let source_1 = circuit.add_element(Box::new(DCVoltageSource::new(5.0, v1, ground)));
+    /// let resistor = circuit.add_element(Box::new(Resistor::new(2.0, v1, v2)));
+    /// let source_2 = circuit.add_element(Box::new(DCVoltageSource::new(2.0, v2, ground)));
+    ///
+    /// assert_eq!(source_1.branch(), Some(BranchId(0)));
+    /// assert_eq!(resistor.branch(), None);
+    /// assert_eq!(source_2.branch(), Some(BranchId(1)));
+    /// assert_eq!(circuit.branch_count(), 2);
+    /// ```
+    pub fn add_element(&mut self, mut element: Box<dyn Element>) -> ElementId {
+        let internal_nodes: Vec<_> = (0..element.internal_node_count())
+            .map(|_| self.push_node())
+            .collect();
+        if !internal_nodes.is_empty() {
+            element.set_internal_nodes(&internal_nodes);
+        }
+
+        let branch = element.is_b_c_element().then(|| {
+            let branch = BranchId(self.branch_count);
+            self.branch_count += 1;
+            element.set_branch(branch);
+
+            branch
+        });
+
+        let id = ElementId {
+            index: self.elements.len(),
+            branch,
+        };
         self.elements.push(element);
+
+        id
     }
 
     /// The number of nodes in the circuit.
@@ -44,11 +168,106 @@ impl Circuit {
         self.nodes.iter().len()
     }
 
+    /// The number of branch currents, which is the number of elements
+    /// that stamp onto the B and C matrices.
+    pub fn branch_count(&self) -> usize {
+        self.branch_count
+    }
+
     /// All of the elements in the circuit.
     pub fn elements(&self) -> &[Box<dyn Element>] {
         &self.elements
     }
+
+    /// The element with `id`, if it is an `E`, such as a
+    /// [`Mosfet`](elements::mosfet::Mosfet) to look up its operating point.
+    pub fn element<E: Element>(&self, id: ElementId) -> Option<&E> {
+        let element: &dyn Any = self.elements.get(id.index)?.as_ref();
+        element.downcast_ref()
+    }
+
+    /// The element with `id` to change, if it is an `E`, such as a
+    /// [`DCVoltageSource`](elements::dc_voltage_source::DCVoltageSource) to sweep its voltage.
+    pub fn element_mut<E: Element>(&mut self, id: ElementId) -> Option<&mut E> {
+        let element: &mut dyn Any = self.elements.get_mut(id.index)?.as_mut();
+        element.downcast_mut()
+    }
+
+    /// The value of the parameter called `name` of the element with `id`,
+    /// as in [`Element::parameter`].
+    pub fn parameter(&self, id: ElementId, name: &str) -> Option<f64> {
+        self.elements.get(id.index)?.parameter(name)
+    }
+
+    /// Sets the parameter called `name` of the element with `id`, whatever type it is,
+    /// and updates the elements that depend on it, as in [`Element::element_changed`].
+    ///
+    /// ```
+    /// use spice_rs::{elements::resistor::Resistor, Circuit};
+    ///
+    /// let mut circuit = Circuit::default();
+    /// let ground = circuit.push_node();
+    /// let v1 = circuit.push_node();
+    /// let resistor = circuit.add_element(Box::new(Resistor::new(1e3, v1, ground)));
+    ///
+    /// circuit.set_parameter(resistor, "r", 2e3).unwrap();
+    /// assert_eq!(circuit.parameter(resistor, "r"), Some(2e3));
+    /// assert!(circuit.set_parameter(resistor, "c", 1e-9).is_err());
+    /// ```
+    pub fn set_parameter(
+        &mut self,
+        id: ElementId,
+        name: &str,
+        value: f64,
+    ) -> Result<(), ParameterError> {
+        self.elements
+            .get_mut(id.index)
+            .ok_or(ParameterError::Unknown(name.to_string()))?
+            .set_parameter(name, value)?;
+
+        let (before, rest) = self.elements.split_at_mut(id.index);
+        let (changed, after) = rest.split_first_mut().expect("the element exists");
+        for element in before.iter_mut().chain(after) {
+            element.element_changed(id, changed.as_ref());
+        }
+
+        Ok(())
+    }
+
+    /// Sets the temperature in °C of every element that has a `temp` parameter,
+    /// such as the diodes and bipolar transistors.
+    pub fn set_temperature(&mut self, temperature: f64) {
+        for element in &mut self.elements {
+            if element.parameter("temp").is_some() {
+                // Only elements with a temperature are changed, so this cannot fail
+                let _ = element.set_parameter("temp", temperature);
+            }
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct NodeId(pub usize);
+
+/// The index of a branch current in the circuit, which is an unknown alongside the node voltages.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BranchId(pub usize);
+
+/// A handle to an element of a [`Circuit`], returned by [`Circuit::add_element`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ElementId {
+    index: usize,
+    branch: Option<BranchId>,
+}
+
+impl ElementId {
+    /// The position of the element in [`Circuit::elements`].
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The branch current assigned to the element, if it stamps onto the B and C matrices.
+    pub fn branch(&self) -> Option<BranchId> {
+        self.branch
+    }
+}
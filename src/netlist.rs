@@ -0,0 +1,1885 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    elements::{
+        bjt::{Bjt, BjtKind, BjtModel},
+        capacitor::Capacitor,
+        current_controlled_current_source::CurrentControlledCurrentSource,
+        current_controlled_voltage_source::CurrentControlledVoltageSource,
+        dc_current_source::DCCurrentSource,
+        dc_voltage_source::DCVoltageSource,
+        diode::{Diode, DiodeModel},
+        inductor::Inductor,
+        mosfet::{Mosfet, MosfetKind, MosfetModel},
+        mutual_inductance::{CouplingError, MutualInductance},
+        resistor::Resistor,
+        voltage_controlled_current_source::VoltageControlledCurrentSource,
+        voltage_controlled_voltage_source::VoltageControlledVoltageSource,
+        waveform::Waveform,
+        Element,
+    },
+    runners::{
+        dc_sweep::SourceSweep,
+        integration::Method,
+        step::{ParameterStep, Stepped},
+        sweep::Sweep,
+        transient::TimeSpan,
+    },
+    units, Circuit, ElementId, NodeId,
+};
+
+/// A circuit and its analyses, parsed from a SPICE deck.
+pub struct Netlist {
+    /// The first line of the deck.
+    pub title: String,
+    pub circuit: Circuit,
+    /// The analyses requested by the control lines, in order.
+    pub analyses: Vec<Analysis>,
+    /// The parameter of a `.step` line, which every analysis is repeated across.
+    pub step: Option<ParameterStep>,
+    elements: HashMap<String, ElementId>,
+}
+
+impl Netlist {
+    /// The node with `name`, where `0` and `gnd` are ground.
+    ///
+    /// Names are case insensitive, like the rest of a SPICE deck,
+    /// so the nodes of the [`Netlist::circuit`] are named in lowercase.
+    pub fn node(&self, name: &str) -> Option<NodeId> {
+        self.circuit.find_node(&name.to_ascii_lowercase())
+    }
+
+    /// The element with `name`, such as `R1` or `Vdd`.
+    pub fn element(&self, name: &str) -> Option<ElementId> {
+        self.elements.get(&name.to_ascii_lowercase()).copied()
+    }
+}
+
+/// An analysis requested by a control line of a SPICE deck.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Analysis {
+    /// `.op`
+    Op,
+    /// `.tran tstep tstop [tstart [tmax]] [uic]`, where `uic` skips the operating point and
+    /// starts from the initial conditions, as in
+    /// [`transient_uic_over`](crate::runners::transient::transient_uic_over)
+    Tran { span: TimeSpan, uic: bool },
+    /// `.ac dec|oct|lin points fstart fstop`
+    Ac(Sweep),
+    /// `.dc source start stop increment [source2 start2 stop2 increment2]`, where a sweep
+    /// may also be `dec|oct|lin points start stop` or `list value ...` after its source
+    Dc {
+        sweep: SourceSweep,
+        nested: Option<SourceSweep>,
+    },
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("line {line}, column {column}: {kind}")]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    #[error("the deck is empty")]
+    Empty,
+    #[error("unknown element type `{0}`")]
+    UnknownElement(char),
+    #[error("unknown control line `{0}`")]
+    UnknownControl(String),
+    #[error("expected {0}")]
+    Missing(&'static str),
+    #[error("unexpected `{0}`")]
+    Unexpected(String),
+    #[error("`{0}` is not a valid number")]
+    InvalidValue(String),
+    #[error("unknown option `{0}`")]
+    UnknownOption(String),
+    #[error("unknown integration method `{0}`")]
+    UnknownMethod(String),
+    #[error("unknown model `{0}`")]
+    UnknownModel(String),
+    #[error("unknown model type `{0}`")]
+    UnknownModelType(String),
+    #[error("unknown parameter `{0}`")]
+    UnknownParameter(String),
+    #[error("model `{0}` does not fit this element")]
+    WrongModelType(String),
+    #[error("model level `{0}` is not supported")]
+    UnsupportedLevel(String),
+    #[error("unknown controlling element `{0}`")]
+    UnknownControllingElement(String),
+    #[error("element `{0}` has no branch current")]
+    NoBranchCurrent(String),
+    #[error("`{0}` is not a DC voltage or current source")]
+    NotASource(String),
+    #[error("`{0}` is not an inductor")]
+    NotAnInductor(String),
+    #[error(transparent)]
+    Coupling(CouplingError),
+    #[error("wrong number of arguments for `{0}`")]
+    WaveformArguments(String),
+    #[error("element `{0}` is defined more than once")]
+    DuplicateElement(String),
+    #[error("element `{0}` is not defined")]
+    UndefinedElement(String),
+    #[error("node `{0}` is not defined")]
+    UndefinedNode(String),
+    #[error("only one `.step` line is supported")]
+    MultipleSteps,
+    #[error("a continuation line must follow a card")]
+    DanglingContinuation,
+}
+
+/// Parses a SPICE deck into a [`Netlist`].
+///
+/// The first line is the title. Lines starting with `*` are comments, `;` starts a comment
+/// until the end of the line, and lines starting with `+` continue the previous card.
+/// Everything after `.end` is ignored.
+///
+/// Supported cards are resistors (`R`), capacitors (`C`), inductors (`L`), DC voltage (`V`)
+/// and current (`I`) sources, voltage and current controlled voltage (`E`, `H`) and current
+/// (`G`, `F`) sources, diodes (`D`), bipolar transistors (`Q`) and MOSFETs (`M`), along
+/// with the `.op`, `.dc`, `.tran`, `.ac`, `.step`, `.model`, `.options`, `.ic`, `.nodeset`
+/// and `.end` control lines. Diodes and transistors name a `.model` of type `D`, `NPN`, `PNP`,
+/// `NMOS` or `PMOS`, which may be defined anywhere in the deck, as in
+/// `.model dmod D(IS=1e-14 RS=10)`. MOSFETs are level 1, and take their size as in
+/// `M1 d g s b nmod W=10u L=1u`. Current controlled sources name the voltage source or inductor
+/// whose current controls them, as in `F1 out 0 Vsense 100`, and mutual inductances (`K`)
+/// name the inductors they couple, as in `K1 L1 L2 0.99`. `.options` sets the `RELTOL`,
+/// `VNTOL`, `ABSTOL`, `CHGTOL`, `TRTOL`, `ITL1`, `ITL4`, `METHOD` (`trap`, `euler` or `gear`)
+/// and `MAXORD` [`Options`](crate::runners::options::Options) of the circuit, as in
+/// `.options reltol=1e-4 method=gear maxord=3`, and `TEMP` sets the temperature of every
+/// diode and bipolar transistor.
+/// Independent sources take a DC value, an AC magnitude and phase, and a transient
+/// [`Waveform`] of `PULSE`, `SIN`, `EXP`, `PWL` or `SFFM`, each used by its own analysis,
+/// as in `V1 in 0 DC 0 AC 1 0 PULSE(0 5 1u 1n 1n 5u 10u)`. A `.step` line repeats the
+/// analyses across the values of an element parameter, named as in
+/// [`Element::parameter`], or of the temperature, as in `.step R1 r list 1k 2k`,
+/// `.step V1 0 5 1` or `.step temp 0 100 25`.
+/// `.ic v(out)=1` and `.nodeset v(out)=1` set the
+/// [`initial_conditions`](crate::Circuit::initial_conditions) and
+/// [`nodesets`](crate::Circuit::nodesets) of nodes, and capacitors and inductors take their own
+/// initial voltage or current as in `C1 out 0 1u IC=1`, which `.tran 1u 1m uic` starts from.
+/// Values are written in engineering notation, as read by [`units::parse`].
+///
+/// ```
+/// use spice_rs::{netlist, runners::dc_op::dc_op};
+///
+/// let netlist = netlist::parse(
+///     "voltage divider
+///     V1 in 0 DC 10
+///     R1 in out 1k
+///     R2 out gnd 1000
+///     .op
+///     .end",
+/// )
+/// .unwrap();
+///
+/// let op = dc_op(&netlist.circuit).unwrap();
+/// assert_eq!(op.voltage(netlist.node("out").unwrap()), 5.0);
+/// ```
+pub fn parse(source: &str) -> Result<Netlist, ParseError> {
+    let mut lines = source.lines().enumerate();
+    let title = lines
+        .next()
+        .map(|(_, line)| line.trim().to_string())
+        .ok_or(ParseError {
+            line: 1,
+            column: 1,
+            kind: ParseErrorKind::Empty,
+        })?;
+
+    let mut parser = Parser::default();
+    parser.circuit.node("0");
+
+    let mut cards: Vec<Vec<Token>> = Vec::new();
+    for (index, line) in lines {
+        let tokens = tokenize(line, index + 1);
+        let Some(first) = tokens.first() else {
+            continue;
+        };
+
+        if first.text.starts_with('+') {
+            let card = cards
+                .last_mut()
+                .ok_or(first.error(ParseErrorKind::DanglingContinuation))?;
+            card.extend(continuation(tokens));
+            continue;
+        }
+
+        if first.text.eq_ignore_ascii_case(".end") {
+            break;
+        }
+        cards.push(tokens);
+    }
+
+    // Models can be defined after the elements that use them, and current controlled
+    // sources, couplings and analyses before the elements they refer to
+    cards.sort_by_key(|card| match card[0].text.chars().next() {
+        _ if card[0].text.eq_ignore_ascii_case(".model") => 0,
+        Some('F' | 'f' | 'H' | 'h' | 'K' | 'k') => 2,
+        Some('.') => 3,
+        _ => 1,
+    });
+    for card in cards {
+        parser.card(card)?;
+    }
+
+    Ok(Netlist {
+        title,
+        circuit: parser.circuit,
+        analyses: parser.analyses,
+        step: parser.step,
+        elements: parser.elements,
+    })
+}
+
+/// A whitespace separated word of a card, with its 1-based position in the deck.
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl Token<'_> {
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+
+    fn value(&self) -> Result<f64, ParseError> {
+        units::parse(self.text)
+            .map_err(|_| self.error(ParseErrorKind::InvalidValue(self.text.to_string())))
+    }
+}
+
+/// Splits a line into tokens, dropping comments.
+/// Commas, parentheses and equals signs count as whitespace.
+fn tokenize(line: &str, line_number: usize) -> Vec<Token<'_>> {
+    if line.trim_start().starts_with('*') {
+        return Vec::new();
+    }
+    let line = line.split(';').next().unwrap_or_default();
+
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        let separator = c.is_whitespace() || matches!(c, ',' | '(' | ')' | '=');
+        match (start, separator) {
+            (None, false) => start = Some(index),
+            (Some(begin), true) => {
+                tokens.push(Token {
+                    text: &line[begin..index],
+                    line: line_number,
+                    column: line[..begin].chars().count() + 1,
+                });
+                start = None;
+            }
+            _ => (),
+        }
+    }
+
+    tokens
+}
+
+/// The tokens of a continuation line without its leading `+`.
+fn continuation(tokens: Vec<Token<'_>>) -> impl Iterator<Item = Token<'_>> {
+    tokens.into_iter().filter_map(|token| match token.text {
+        "+" => None,
+        text => Some(match text.strip_prefix('+') {
+            Some(text) => Token {
+                text,
+                column: token.column + 1,
+                ..token
+            },
+            None => token,
+        }),
+    })
+}
+
+/// The parameters of a `.model` card.
+#[derive(Debug, Clone, Copy)]
+enum Model {
+    Diode(DiodeModel),
+    Bjt(BjtKind, BjtModel),
+    Mosfet(MosfetKind, MosfetModel),
+}
+
+#[derive(Default)]
+struct Parser {
+    circuit: Circuit,
+    analyses: Vec<Analysis>,
+    step: Option<ParameterStep>,
+    elements: HashMap<String, ElementId>,
+    models: HashMap<String, Model>,
+}
+
+impl Parser {
+    /// Parses a single card.
+    fn card(&mut self, tokens: Vec<Token<'_>>) -> Result<(), ParseError> {
+        let mut tokens = tokens.into_iter();
+        let Some(name) = tokens.next() else {
+            return Ok(());
+        };
+        let mut fields = Fields {
+            tokens: tokens.peekable(),
+            last: name,
+        };
+
+        if let Some(control) = name.text.strip_prefix('.') {
+            return self.control(name, control, fields);
+        }
+
+        let kind = name.text.chars().next().unwrap_or_default();
+        let element: Box<dyn Element> = match kind.to_ascii_uppercase() {
+            'R' => {
+                let (node_1, node_2) = self.node_pair(&mut fields)?;
+                let resistance = fields.value("a resistance")?;
+                Box::new(Resistor::new(resistance, node_1, node_2))
+            }
+            'C' => {
+                let (positive, negative) = self.node_pair(&mut fields)?;
+                let capacitance = fields.value("a capacitance")?;
+                let capacitor = Capacitor::new(capacitance, positive, negative);
+                Box::new(match fields.initial_condition()? {
+                    Some(voltage) => capacitor.with_initial_voltage(voltage),
+                    None => capacitor,
+                })
+            }
+            'L' => {
+                let (positive, negative) = self.node_pair(&mut fields)?;
+                let inductance = fields.value("an inductance")?;
+                let inductor = Inductor::new(inductance, positive, negative);
+                Box::new(match fields.initial_condition()? {
+                    Some(current) => inductor.with_initial_current(current),
+                    None => inductor,
+                })
+            }
+            'V' => {
+                let (positive, negative) = self.node_pair(&mut fields)?;
+                let spec = fields.source("a voltage")?;
+                let mut source = DCVoltageSource::new(spec.dc, positive, negative);
+                if let Some((magnitude, phase)) = spec.ac {
+                    source = source.with_ac(magnitude, phase);
+                }
+                if let Some(waveform) = spec.waveform {
+                    source = source.with_waveform(waveform);
+                }
+                Box::new(source)
+            }
+            'I' => {
+                let (positive, negative) = self.node_pair(&mut fields)?;
+                let spec = fields.source("a current")?;
+                // SPICE current flows from the positive node, through the source,
+                // into the negative node.
+                let mut source = DCCurrentSource::new(spec.dc, negative, positive);
+                if let Some((magnitude, phase)) = spec.ac {
+                    source = source.with_ac(magnitude, phase);
+                }
+                if let Some(waveform) = spec.waveform {
+                    source = source.with_waveform(waveform);
+                }
+                Box::new(source)
+            }
+            'E' => {
+                let (positive, negative) = self.node_pair(&mut fields)?;
+                let (control_positive, control_negative) = self.node_pair(&mut fields)?;
+                let gain = fields.value("a gain")?;
+                Box::new(VoltageControlledVoltageSource::new(
+                    gain,
+                    positive,
+                    negative,
+                    control_positive,
+                    control_negative,
+                ))
+            }
+            'G' => {
+                let (positive, negative) = self.node_pair(&mut fields)?;
+                let (control_positive, control_negative) = self.node_pair(&mut fields)?;
+                let transconductance = fields.value("a transconductance")?;
+                Box::new(VoltageControlledCurrentSource::new(
+                    transconductance,
+                    positive,
+                    negative,
+                    control_positive,
+                    control_negative,
+                ))
+            }
+            'H' => {
+                let (positive, negative) = self.node_pair(&mut fields)?;
+                let (name, control) = self.control_element(&mut fields)?;
+                let transresistance = fields.value("a transresistance")?;
+                let source = CurrentControlledVoltageSource::new(
+                    transresistance,
+                    positive,
+                    negative,
+                    control,
+                )
+                .map_err(|_| name.error(ParseErrorKind::NoBranchCurrent(name.text.to_string())))?;
+                Box::new(source)
+            }
+            'F' => {
+                let (positive, negative) = self.node_pair(&mut fields)?;
+                let (name, control) = self.control_element(&mut fields)?;
+                let gain = fields.value("a gain")?;
+                let source = CurrentControlledCurrentSource::new(gain, positive, negative, control)
+                    .map_err(|_| {
+                        name.error(ParseErrorKind::NoBranchCurrent(name.text.to_string()))
+                    })?;
+                Box::new(source)
+            }
+            'K' => {
+                let mut inductors = vec![fields.next("an inductor")?, fields.next("an inductor")?];
+                // Any number of inductors can be coupled, so only the last field is the coefficient
+                let mut coefficient = fields.next("a coupling coefficient")?;
+                while let Some(next) = fields.optional() {
+                    inductors.push(coefficient);
+                    coefficient = next;
+                }
+                let inductors = inductors
+                    .into_iter()
+                    .map(|name| self.inductor(name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let coupling =
+                    MutualInductance::new(&self.circuit, coefficient.value()?, &inductors)
+                        .map_err(|error| coefficient.error(ParseErrorKind::Coupling(error)))?;
+                Box::new(coupling)
+            }
+            'D' => {
+                let (anode, cathode) = self.node_pair(&mut fields)?;
+                let name = fields.next("a model name")?;
+                let Model::Diode(mut model) = self.model(name)? else {
+                    return Err(name.error(ParseErrorKind::WrongModelType(name.text.to_string())));
+                };
+                if let Some(area) = fields.optional() {
+                    let area = area.value()?;
+                    model.saturation_current *= area;
+                    model.junction_capacitance *= area;
+                    model.series_resistance /= area;
+                }
+                Box::new(Diode::new(anode, cathode).with_model(model))
+            }
+            'Q' => {
+                let (collector, base) = self.node_pair(&mut fields)?;
+                let emitter = self
+                    .circuit
+                    .node(&fields.next("a node")?.text.to_ascii_lowercase());
+                // The substrate is optional, so the next field is either it or the model
+                let mut name = fields.next("a model name")?;
+                let mut substrate = None;
+                if !self.models.contains_key(&name.text.to_ascii_lowercase()) {
+                    substrate = Some(self.circuit.node(&name.text.to_ascii_lowercase()));
+                    name = fields.next("a model name")?;
+                }
+                let Model::Bjt(kind, mut model) = self.model(name)? else {
+                    return Err(name.error(ParseErrorKind::WrongModelType(name.text.to_string())));
+                };
+                if let Some(area) = fields.optional() {
+                    let area = area.value()?;
+                    model.saturation_current *= area;
+                    model.emitter_capacitance *= area;
+                    model.collector_capacitance *= area;
+                    model.substrate_capacitance *= area;
+                    model.base_resistance /= area;
+                    model.collector_resistance /= area;
+                    model.emitter_resistance /= area;
+                }
+                let bjt = Bjt::new(kind, collector, base, emitter).with_model(model);
+                Box::new(match substrate {
+                    Some(substrate) => bjt.with_substrate(substrate),
+                    None => bjt,
+                })
+            }
+            'M' => {
+                let (drain, gate) = self.node_pair(&mut fields)?;
+                let (source, bulk) = self.node_pair(&mut fields)?;
+                let name = fields.next("a model name")?;
+                let Model::Mosfet(kind, model) = self.model(name)? else {
+                    return Err(name.error(ParseErrorKind::WrongModelType(name.text.to_string())));
+                };
+                let (mut width, mut length) = (1e-4, 1e-4);
+                while let Some(parameter) = fields.optional() {
+                    let value = fields.value("a parameter value")?;
+                    match parameter.text.to_ascii_lowercase().as_str() {
+                        "w" => width = value,
+                        "l" => length = value,
+                        _ => {
+                            return Err(parameter.error(ParseErrorKind::UnknownParameter(
+                                parameter.text.to_string(),
+                            )))
+                        }
+                    }
+                }
+                Box::new(
+                    Mosfet::new(kind, drain, gate, source, bulk)
+                        .with_model(model)
+                        .with_dimensions(width, length),
+                )
+            }
+            _ => return Err(name.error(ParseErrorKind::UnknownElement(kind))),
+        };
+        fields.end()?;
+
+        let key = name.text.to_ascii_lowercase();
+        if self.elements.contains_key(&key) {
+            return Err(name.error(ParseErrorKind::DuplicateElement(name.text.to_string())));
+        }
+        let id = self.circuit.add_element(element);
+        self.elements.insert(key, id);
+
+        Ok(())
+    }
+
+    fn control(
+        &mut self,
+        name: Token<'_>,
+        control: &str,
+        mut fields: Fields<'_>,
+    ) -> Result<(), ParseError> {
+        let analysis = match control.to_ascii_lowercase().as_str() {
+            "options" | "option" | "opt" => return self.options(fields),
+            "model" => return self.define_model(fields),
+            "step" => return self.step(name, fields),
+            "op" => Analysis::Op,
+            "tran" => {
+                let mut span =
+                    TimeSpan::new(fields.value("a time step")?, fields.value("a stop time")?);
+                if let Some(tstart) = fields.optional_value()? {
+                    span = span.with_start(tstart);
+                }
+                if let Some(tmax) = fields.optional_value()? {
+                    span = span.with_max_step(tmax);
+                }
+                let uic = fields
+                    .tokens
+                    .next_if(|token| token.text.eq_ignore_ascii_case("uic"))
+                    .is_some();
+                Analysis::Tran { span, uic }
+            }
+            "ic" => {
+                let voltages = self.node_voltages(fields)?;
+                self.circuit.initial_conditions.extend(voltages);
+                return Ok(());
+            }
+            "nodeset" => {
+                let voltages = self.node_voltages(fields)?;
+                self.circuit.nodesets.extend(voltages);
+                return Ok(());
+            }
+            "ac" => {
+                let variation = fields.next("dec, oct or lin")?;
+                let points = fields.count("a number of points")?;
+                let start = fields.value("a start frequency")?;
+                let stop = fields.value("a stop frequency")?;
+
+                Analysis::Ac(match variation.text.to_ascii_lowercase().as_str() {
+                    "dec" => Sweep::Decade {
+                        start,
+                        stop,
+                        points,
+                    },
+                    "oct" => Sweep::Octave {
+                        start,
+                        stop,
+                        points,
+                    },
+                    "lin" => Sweep::Linear {
+                        start,
+                        stop,
+                        points,
+                    },
+                    _ => {
+                        return Err(
+                            variation.error(ParseErrorKind::Unexpected(variation.text.to_string()))
+                        )
+                    }
+                })
+            }
+            "dc" => {
+                let source = fields.next("a source")?;
+                let sweep = self.source_sweep(source, &mut fields)?;
+                let nested = match fields.optional() {
+                    Some(source) => Some(self.source_sweep(source, &mut fields)?),
+                    None => None,
+                };
+                Analysis::Dc { sweep, nested }
+            }
+            _ => return Err(name.error(ParseErrorKind::UnknownControl(name.text.to_string()))),
+        };
+        fields.end()?;
+        self.analyses.push(analysis);
+
+        Ok(())
+    }
+
+    /// Sets the parameter of a `.step temp sweep` or `.step element [parameter] sweep` line,
+    /// where the parameter of a source may be left out for its DC value.
+    fn step(&mut self, name: Token<'_>, mut fields: Fields<'_>) -> Result<(), ParseError> {
+        if self.step.is_some() {
+            return Err(name.error(ParseErrorKind::MultipleSteps));
+        }
+
+        let target = fields.next("an element or temp")?;
+        let stepped = match target.text.to_ascii_lowercase().as_str() {
+            "temp" => Stepped::Temperature,
+            element_name => {
+                let element = self.elements.get(element_name).copied().ok_or(
+                    target.error(ParseErrorKind::UndefinedElement(target.text.to_string())),
+                )?;
+                // Anything but a number or the start of a sweep names the parameter
+                let parameter = fields.tokens.next_if(|token| {
+                    units::parse(token.text).is_err()
+                        && !matches!(
+                            token.text.to_ascii_lowercase().as_str(),
+                            "dec" | "oct" | "lin" | "list"
+                        )
+                });
+                let name = match parameter {
+                    Some(parameter) => {
+                        fields.last = parameter;
+                        let name = parameter.text.to_ascii_lowercase();
+                        if self.circuit.parameter(element, &name).is_none() {
+                            return Err(parameter.error(ParseErrorKind::UnknownParameter(
+                                parameter.text.to_string(),
+                            )));
+                        }
+                        name
+                    }
+                    None if self.circuit.parameter(element, "dc").is_some() => "dc".to_string(),
+                    None => return Err(fields.missing("a parameter")),
+                };
+                Stepped::Parameter { element, name }
+            }
+        };
+        let sweep = fields.sweep()?;
+        fields.end()?;
+        self.step = Some(ParameterStep { stepped, sweep });
+
+        Ok(())
+    }
+
+    /// Defines the model of a `.model name type(parameter=value ...)` line.
+    fn define_model(&mut self, mut fields: Fields<'_>) -> Result<(), ParseError> {
+        let name = fields.next("a model name")?;
+        let kind = fields.next("a model type")?;
+        let model = match kind.text.to_ascii_lowercase().as_str() {
+            "d" => {
+                let mut model = DiodeModel::default();
+                while let Some(parameter) = fields.optional() {
+                    let value = fields.value("a parameter value")?;
+                    *model
+                        .parameter_mut(&parameter.text.to_ascii_lowercase())
+                        .ok_or(parameter.error(ParseErrorKind::UnknownParameter(
+                            parameter.text.to_string(),
+                        )))? = value;
+                }
+                Model::Diode(model)
+            }
+            kind @ ("npn" | "pnp") => {
+                let mut model = BjtModel::default();
+                while let Some(parameter) = fields.optional() {
+                    let value = fields.value("a parameter value")?;
+                    *model
+                        .parameter_mut(&parameter.text.to_ascii_lowercase())
+                        .ok_or(parameter.error(ParseErrorKind::UnknownParameter(
+                            parameter.text.to_string(),
+                        )))? = value;
+                }
+                let kind = match kind {
+                    "npn" => BjtKind::Npn,
+                    _ => BjtKind::Pnp,
+                };
+                Model::Bjt(kind, model)
+            }
+            kind @ ("nmos" | "pmos") => {
+                let mut model = MosfetModel::default();
+                while let Some(parameter) = fields.optional() {
+                    let value = fields.next("a parameter value")?;
+                    let field = match parameter.text.to_ascii_lowercase().as_str() {
+                        "level" if value.text != "1" => {
+                            return Err(value
+                                .error(ParseErrorKind::UnsupportedLevel(value.text.to_string())))
+                        }
+                        "level" => continue,
+                        name => {
+                            model.parameter_mut(name).ok_or(parameter.error(
+                                ParseErrorKind::UnknownParameter(parameter.text.to_string()),
+                            ))?
+                        }
+                    };
+                    *field = value.value()?;
+                }
+                let kind = match kind {
+                    "nmos" => MosfetKind::Nmos,
+                    _ => MosfetKind::Pmos,
+                };
+                Model::Mosfet(kind, model)
+            }
+            _ => return Err(kind.error(ParseErrorKind::UnknownModelType(kind.text.to_string()))),
+        };
+        self.models.insert(name.text.to_ascii_lowercase(), model);
+
+        Ok(())
+    }
+
+    /// The model named by `name`.
+    fn model(&self, name: Token<'_>) -> Result<Model, ParseError> {
+        self.models
+            .get(&name.text.to_ascii_lowercase())
+            .copied()
+            .ok_or(name.error(ParseErrorKind::UnknownModel(name.text.to_string())))
+    }
+
+    /// The `v(node)=value` pairs of an `.ic` or `.nodeset` line.
+    fn node_voltages(&mut self, mut fields: Fields<'_>) -> Result<Vec<(NodeId, f64)>, ParseError> {
+        let mut voltages = Vec::new();
+        let mut next = Some(fields.next("a node voltage")?);
+        while let Some(voltage) = next {
+            if !voltage.text.eq_ignore_ascii_case("v") {
+                return Err(voltage.error(ParseErrorKind::Unexpected(voltage.text.to_string())));
+            }
+            let name = fields.next("a node")?;
+            let node = self
+                .circuit
+                .find_node(&name.text.to_ascii_lowercase())
+                .ok_or(name.error(ParseErrorKind::UndefinedNode(name.text.to_string())))?;
+            voltages.push((node, fields.value("a voltage")?));
+            next = fields.optional();
+        }
+
+        Ok(voltages)
+    }
+
+    /// Sets every `name=value` pair of an `.options` line.
+    fn options(&mut self, mut fields: Fields<'_>) -> Result<(), ParseError> {
+        while let Some(name) = fields.optional() {
+            let options = &mut self.circuit.options;
+            match name.text.to_ascii_lowercase().as_str() {
+                "method" => {
+                    let method = fields.next("an integration method")?;
+                    options.method = match method.text.to_ascii_lowercase().as_str() {
+                        "euler" => Method::BackwardEuler,
+                        "trap" | "trapezoidal" => Method::Trapezoidal,
+                        "gear" => Method::Gear,
+                        _ => {
+                            return Err(method
+                                .error(ParseErrorKind::UnknownMethod(method.text.to_string())))
+                        }
+                    };
+                }
+                "maxord" => {
+                    let order = fields.next("an order")?;
+                    options.max_order = match order.text.parse() {
+                        Ok(order @ 1..=6) => order,
+                        _ => {
+                            return Err(order.error(ParseErrorKind::InvalidValue(order.text.into())))
+                        }
+                    };
+                }
+                "reltol" => options.reltol = fields.value("an option value")?,
+                "vntol" => options.vntol = fields.value("an option value")?,
+                "abstol" => options.abstol = fields.value("an option value")?,
+                "chgtol" => options.chgtol = fields.value("an option value")?,
+                "trtol" => options.trtol = fields.value("an option value")?,
+                "itl1" => options.dc_iterations = fields.value("an option value")? as usize,
+                "itl4" => options.transient_iterations = fields.value("an option value")? as usize,
+                "temp" => {
+                    let temperature = fields.value("an option value")?;
+                    self.circuit.set_temperature(temperature);
+                }
+                _ => return Err(name.error(ParseErrorKind::UnknownOption(name.text.to_string()))),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The element named by the next field, whose branch current controls a source.
+    fn control_element<'a>(
+        &self,
+        fields: &mut Fields<'a>,
+    ) -> Result<(Token<'a>, ElementId), ParseError> {
+        let name = fields.next("a controlling element")?;
+        let control = self
+            .elements
+            .get(&name.text.to_ascii_lowercase())
+            .copied()
+            .ok_or(name.error(ParseErrorKind::UnknownControllingElement(
+                name.text.to_string(),
+            )))?;
+
+        Ok((name, control))
+    }
+
+    /// The DC source named by `name` and the values of a `.dc` line to sweep it through.
+    fn source_sweep(
+        &self,
+        name: Token<'_>,
+        fields: &mut Fields<'_>,
+    ) -> Result<SourceSweep, ParseError> {
+        let source = self
+            .elements
+            .get(&name.text.to_ascii_lowercase())
+            .copied()
+            .filter(|&id| {
+                self.circuit.element::<DCVoltageSource>(id).is_some()
+                    || self.circuit.element::<DCCurrentSource>(id).is_some()
+            })
+            .ok_or(name.error(ParseErrorKind::NotASource(name.text.to_string())))?;
+
+        let sweep = fields.sweep()?;
+
+        Ok(SourceSweep { source, sweep })
+    }
+
+    /// The inductor named by `name`.
+    fn inductor(&self, name: Token<'_>) -> Result<ElementId, ParseError> {
+        self.elements
+            .get(&name.text.to_ascii_lowercase())
+            .copied()
+            .filter(|&id| self.circuit.element::<Inductor>(id).is_some())
+            .ok_or(name.error(ParseErrorKind::NotAnInductor(name.text.to_string())))
+    }
+
+    fn node_pair(&mut self, fields: &mut Fields<'_>) -> Result<(NodeId, NodeId), ParseError> {
+        let node_1 = fields.next("a node")?;
+        let node_2 = fields.next("a node")?;
+
+        Ok((
+            self.circuit.node(&node_1.text.to_ascii_lowercase()),
+            self.circuit.node(&node_2.text.to_ascii_lowercase()),
+        ))
+    }
+}
+
+/// The specifications of an independent source in each analysis.
+struct SourceSpec {
+    dc: f64,
+    /// The magnitude and phase in degrees.
+    ac: Option<(f64, f64)>,
+    waveform: Option<Waveform>,
+}
+
+/// The fields of a card after its name.
+struct Fields<'a> {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token<'a>>>,
+    /// The most recently read token, for positioning errors about missing fields.
+    last: Token<'a>,
+}
+
+impl<'a> Fields<'a> {
+    fn next(&mut self, expected: &'static str) -> Result<Token<'a>, ParseError> {
+        let token = self.tokens.next().ok_or(self.missing(expected))?;
+        self.last = token;
+
+        Ok(token)
+    }
+
+    /// The error of a card that ends where `expected` should follow.
+    fn missing(&self, expected: &'static str) -> ParseError {
+        ParseError {
+            line: self.last.line,
+            column: self.last.column + self.last.text.chars().count(),
+            kind: ParseErrorKind::Missing(expected),
+        }
+    }
+
+    /// The next field, if there are any left.
+    fn optional(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.next()?;
+        self.last = token;
+
+        Some(token)
+    }
+
+    fn value(&mut self, expected: &'static str) -> Result<f64, ParseError> {
+        self.next(expected)?.value()
+    }
+
+    /// The next field as a whole number, such as the number of points of a sweep.
+    fn count(&mut self, expected: &'static str) -> Result<usize, ParseError> {
+        let token = self.next(expected)?;
+        token
+            .text
+            .parse()
+            .map_err(|_| token.error(ParseErrorKind::InvalidValue(token.text.into())))
+    }
+
+    /// The initial condition of a capacitor or inductor, as in `IC=1`, if it is given.
+    fn initial_condition(&mut self) -> Result<Option<f64>, ParseError> {
+        let Some(name) = self.optional() else {
+            return Ok(None);
+        };
+        if !name.text.eq_ignore_ascii_case("ic") {
+            return Err(name.error(ParseErrorKind::UnknownParameter(name.text.to_string())));
+        }
+
+        self.value("an initial condition").map(Some)
+    }
+
+    /// The DC value, AC magnitude and phase, and transient waveform of an independent source,
+    /// as in `DC 1 AC 1 90 PULSE(0 1 1u)`, where `DC` may be left out.
+    ///
+    /// Without a DC value, the source takes the value of its waveform at `t = 0`, or 0.
+    /// `AC` without a magnitude is 1, and without a phase is 0 degrees.
+    fn source(&mut self, expected: &'static str) -> Result<SourceSpec, ParseError> {
+        let (mut dc, mut ac, mut waveform) = (None, None, None);
+        while let Some(token) = self.optional() {
+            match token.text.to_ascii_lowercase().as_str() {
+                "dc" => dc = Some(self.value(expected)?),
+                "ac" => {
+                    let magnitude = self.optional_value()?.unwrap_or(1.0);
+                    let phase = self.optional_value()?.unwrap_or(0.0);
+                    ac = Some((magnitude, phase));
+                }
+                kind @ ("pulse" | "sin" | "exp" | "pwl" | "sffm") => {
+                    waveform = Some(self.waveform(token, kind)?);
+                }
+                _ if dc.is_none() => dc = Some(token.value()?),
+                _ => return Err(token.error(ParseErrorKind::Unexpected(token.text.to_string()))),
+            }
+        }
+
+        let dc = match (dc, &waveform) {
+            (Some(dc), _) => dc,
+            (None, Some(waveform)) => waveform.value(0.0),
+            (None, None) if ac.is_some() => 0.0,
+            (None, None) => return Err(self.missing(expected)),
+        };
+
+        Ok(SourceSpec { dc, ac, waveform })
+    }
+
+    /// The values of a `.dc` or `.step` sweep, given as `start stop increment`,
+    /// `dec|oct|lin points start stop` or `list value ...`.
+    fn sweep(&mut self) -> Result<Sweep, ParseError> {
+        let first = self.next("a start value")?;
+        let sweep = match first.text.to_ascii_lowercase().as_str() {
+            variation @ ("dec" | "oct" | "lin") => {
+                let points = self.count("a number of points")?;
+                let start = self.value("a start value")?;
+                let stop = self.value("a stop value")?;
+                match variation {
+                    "dec" => Sweep::Decade {
+                        start,
+                        stop,
+                        points,
+                    },
+                    "oct" => Sweep::Octave {
+                        start,
+                        stop,
+                        points,
+                    },
+                    _ => Sweep::Linear {
+                        start,
+                        stop,
+                        points,
+                    },
+                }
+            }
+            "list" => {
+                let mut values = vec![self.value("a value")?];
+                while let Some(value) = self.optional_value()? {
+                    values.push(value);
+                }
+                Sweep::List(values)
+            }
+            _ => {
+                let start = first.value()?;
+                let stop = self.value("a stop value")?;
+                let token = self.next("an increment")?;
+                let increment = token.value()?;
+                let steps = (stop - start) / increment;
+                if !(steps.is_finite() && steps >= 0.0) {
+                    return Err(token.error(ParseErrorKind::InvalidValue(token.text.to_string())));
+                }
+                // An increment that does not divide the range stops short of the stop value
+                let points = steps.round() as usize + 1;
+                match (steps - steps.round()).abs() < 1e-6 {
+                    true => Sweep::Linear {
+                        start,
+                        stop,
+                        points,
+                    },
+                    false => Sweep::List(
+                        (0..=steps.floor() as usize)
+                            .map(|step| start + increment * step as f64)
+                            .collect(),
+                    ),
+                }
+            }
+        };
+
+        Ok(sweep)
+    }
+
+    /// The next field if it is a number, which optional arguments are.
+    fn optional_value(&mut self) -> Result<Option<f64>, ParseError> {
+        match self
+            .tokens
+            .next_if(|token| units::parse(token.text).is_ok())
+        {
+            Some(token) => {
+                self.last = token;
+                token.value().map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The arguments of the waveform `kind` named by `name`, which are every number after it.
+    fn waveform(&mut self, name: Token<'a>, kind: &str) -> Result<Waveform, ParseError> {
+        let mut arguments = Vec::new();
+        while let Some(argument) = self.optional_value()? {
+            arguments.push(argument);
+        }
+        // Optional arguments default to 0, or to infinity where 0 makes no sense
+        let argument =
+            |index: usize, default: f64| arguments.get(index).copied().unwrap_or(default);
+        let count = |min: usize, max: usize| match (min..=max).contains(&arguments.len()) {
+            true => Ok(()),
+            false => Err(name.error(ParseErrorKind::WaveformArguments(name.text.to_string()))),
+        };
+
+        Ok(match kind {
+            "pulse" => {
+                count(2, 7)?;
+                Waveform::Pulse {
+                    initial: arguments[0],
+                    pulsed: arguments[1],
+                    delay: argument(2, 0.0),
+                    rise: argument(3, 0.0),
+                    fall: argument(4, 0.0),
+                    width: argument(5, f64::INFINITY),
+                    period: argument(6, f64::INFINITY),
+                }
+            }
+            "sin" => {
+                count(3, 6)?;
+                Waveform::Sine {
+                    offset: arguments[0],
+                    amplitude: arguments[1],
+                    frequency: arguments[2],
+                    delay: argument(3, 0.0),
+                    damping: argument(4, 0.0),
+                    phase: argument(5, 0.0),
+                }
+            }
+            "exp" => {
+                count(4, 6)?;
+                Waveform::Exponential {
+                    initial: arguments[0],
+                    pulsed: arguments[1],
+                    rise_delay: arguments[2],
+                    rise_time_constant: arguments[3],
+                    fall_delay: argument(4, f64::INFINITY),
+                    fall_time_constant: argument(5, arguments[3]),
+                }
+            }
+            "pwl" => {
+                if arguments.is_empty() || arguments.len() % 2 != 0 {
+                    return Err(
+                        name.error(ParseErrorKind::WaveformArguments(name.text.to_string()))
+                    );
+                }
+                Waveform::PiecewiseLinear(
+                    arguments
+                        .chunks(2)
+                        .map(|point| (point[0], point[1]))
+                        .collect(),
+                )
+            }
+            _ => {
+                count(3, 5)?;
+                Waveform::SingleFrequencyFm {
+                    offset: arguments[0],
+                    amplitude: arguments[1],
+                    carrier: arguments[2],
+                    modulation_index: argument(3, 0.0),
+                    signal: argument(4, 0.0),
+                }
+            }
+        })
+    }
+
+    /// Errors if there are any fields left.
+    fn end(mut self) -> Result<(), ParseError> {
+        match self.tokens.next() {
+            Some(token) => Err(token.error(ParseErrorKind::Unexpected(token.text.to_string()))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use crate::{
+        elements::{
+            mosfet::{Mosfet, Region},
+            mutual_inductance::{CouplingError, MutualInductance},
+        },
+        runners::{
+            ac::ac,
+            dc_op::dc_op,
+            dc_sweep::{dc_sweep, SourceSweep},
+            integration::Method,
+            step::{step, ParameterStep, Stepped},
+            sweep::Sweep,
+            transient::{transient, transient_uic, TimeSpan},
+        },
+        NodeId,
+    };
+
+    use super::{parse, Analysis, ParseError, ParseErrorKind};
+
+    #[test]
+    fn elements_and_analyses() {
+        let netlist = parse(
+            "* mixed sources
+V1 n1 n2 10 ; the only voltage source
+R1 n1 0 2
+r2 N1 n2
++ 4
+R3 n2 gnd 2
+I1 0 n1 DC 3
+C1 n1 n2 1uF
+L1 n2 0 1e-3
+
+.op
+.tran 1u 1m
+.tran 1u 1m 0 1u
+.tran 1u 1m 0.5m 0.1u UIC
+.AC dec 10 1 1meg
+.end
+R4 n1 0 this is ignored",
+        )
+        .unwrap();
+
+        assert_eq!(netlist.title, "* mixed sources");
+        assert_eq!(netlist.node("0"), Some(NodeId(0)));
+        assert_eq!(netlist.node("GND"), Some(NodeId(0)));
+        assert_eq!(netlist.node("n1"), Some(NodeId(1)));
+        assert_eq!(netlist.node("n2"), Some(NodeId(2)));
+        assert_eq!(netlist.circuit.node_name(NodeId(1)), Some("n1"));
+        assert_eq!(netlist.circuit.elements().len(), 7);
+        assert_eq!(netlist.element("r4"), None);
+        assert_eq!(
+            netlist.analyses,
+            vec![
+                Analysis::Op,
+                Analysis::Tran {
+                    span: TimeSpan::new(1e-6, 1e-3),
+                    uic: false
+                },
+                Analysis::Tran {
+                    span: TimeSpan::new(1e-6, 1e-3).with_max_step(1e-6),
+                    uic: false
+                },
+                Analysis::Tran {
+                    span: TimeSpan::new(1e-6, 1e-3)
+                        .with_start(5e-4)
+                        .with_max_step(1e-7),
+                    uic: true
+                },
+                Analysis::Ac(Sweep::Decade {
+                    start: 1.0,
+                    stop: 1e6,
+                    points: 10
+                })
+            ]
+        );
+    }
+
+    /// The same circuit as the `mixed_sources` DC operating point test.
+    #[test]
+    fn mixed_sources() {
+        let netlist = parse(
+            "mixed sources
+V1 n1 n2 10
+R1 n1 0 2
+R2 n1 n2 4
+R3 n2 0 2
+I1 0 n1 3
+.end",
+        )
+        .unwrap();
+
+        let op = dc_op(&netlist.circuit).unwrap();
+
+        assert_relative_eq!(op.voltage(netlist.node("n1").unwrap()), 8.0, epsilon = 0.01);
+        assert_relative_eq!(
+            op.voltage(netlist.node("n2").unwrap()),
+            -2.0,
+            epsilon = 0.01
+        );
+        assert_relative_eq!(
+            op.branch_current(netlist.element("v1").unwrap()).unwrap(),
+            -3.5,
+            epsilon = 0.01
+        );
+    }
+
+    #[test]
+    fn options() {
+        let netlist = parse(
+            "options
+.options reltol=1e-4 ITL1 = 50
++ vntol=1u trtol=1 method=Gear maxord=4
+.end",
+        )
+        .unwrap();
+
+        let options = netlist.circuit.options;
+        assert_relative_eq!(options.reltol, 1e-4);
+        assert_relative_eq!(options.vntol, 1e-6);
+        assert_eq!(options.trtol, 1.0);
+        assert_eq!(options.method, Method::Gear);
+        assert_eq!(options.max_order, 4);
+        assert_eq!(options.dc_iterations, 50);
+        assert_eq!(options.transient_iterations, 10);
+    }
+
+    /// A diode whose model is defined after it, with its area scaling the model.
+    #[test]
+    fn diode() {
+        let netlist = parse(
+            "diode
+V1 in 0 5
+R1 in out 1k
+D1 out 0 dmod 2
+.model DMOD d(is=1e-14 n=1
++ rs=10 cjo=2p bv=5.1)
+.end",
+        )
+        .unwrap();
+
+        // The series resistance adds an internal node
+        assert_eq!(netlist.circuit.node_count(), 4);
+        let op = dc_op(&netlist.circuit).unwrap();
+        let voltage = op.voltage(netlist.node("out").unwrap());
+        assert!(voltage > 0.6 && voltage < 0.8);
+    }
+
+    /// Transistors with and without a substrate node, one of them named in uppercase.
+    #[test]
+    fn bjt() {
+        let netlist = parse(
+            "common emitter
+VCC vcc 0 10
+RC vcc c1 1k
+IB 0 b 10u
+Q1 c1 b 0 QN
+Q2 c2 b 0 sub qn 2
+RC2 vcc c2 1k
+RSUB sub 0 1meg
+.model qn NPN(bf=100 is=1e-16 cjs=1p)
+.end",
+        )
+        .unwrap();
+
+        assert!(netlist.node("sub").is_some());
+        let op = dc_op(&netlist.circuit).unwrap();
+        // Both transistors share the base current, the second one with twice the area
+        assert_relative_eq!(
+            op.voltage(netlist.node("c1").unwrap()),
+            10.0 - 1e3 * 100.0 * 10e-6 / 3.0,
+            max_relative = 1e-3
+        );
+        assert_relative_eq!(
+            op.voltage(netlist.node("c2").unwrap()),
+            10.0 - 1e3 * 200.0 * 10e-6 / 3.0,
+            max_relative = 1e-3
+        );
+    }
+
+    /// A CMOS inverter with its input at the switching point of symmetric transistors.
+    #[test]
+    fn mosfet() {
+        let netlist = parse(
+            "cmos inverter
+VDD vdd 0 5
+VIN in 0 2.5
+MN out in 0 0 nch W=10u L=1u
+MP out in vdd vdd pch w=10u l=1u
+.model nch NMOS(level=1 vto=1 kp=20u lambda=0.02)
+.model pch PMOS(vto=-1 kp=20u lambda=0.02)
+.end",
+        )
+        .unwrap();
+
+        let op = dc_op(&netlist.circuit).unwrap();
+
+        assert_relative_eq!(
+            op.voltage(netlist.node("out").unwrap()),
+            2.5,
+            epsilon = 1e-6
+        );
+        let mn = netlist.element("mn").unwrap();
+        let mn = netlist
+            .circuit
+            .element::<Mosfet>(mn)
+            .unwrap()
+            .operating_point(&op);
+        assert_eq!(mn.region, Region::Saturation);
+        assert_relative_eq!(
+            mn.drain_current,
+            1e-4 * 1.5f64.powi(2) * 1.05,
+            max_relative = 1e-6
+        );
+    }
+
+    #[test]
+    fn controlled_sources() {
+        let netlist = parse(
+            "dependent sources
+V1 in 0 1
+E1 e 0 in 0 3
+RE e 0 1k
+G1 0 g in 0 2m
+RG g 0 1k
+F1 f 0 vsense 2
+RF f 0 1k
+H1 h 0 VSENSE 500
+R1 in sense 1k
+Vsense sense 0 0
+.end",
+        )
+        .unwrap();
+
+        let op = dc_op(&netlist.circuit).unwrap();
+        let voltage = |name| op.voltage(netlist.node(name).unwrap());
+
+        assert_relative_eq!(voltage("e"), 3.0);
+        assert_relative_eq!(voltage("g"), 2.0);
+        assert_relative_eq!(voltage("f"), -2.0);
+        assert_relative_eq!(voltage("h"), 0.5);
+    }
+
+    #[test]
+    fn waveforms() {
+        let netlist = parse(
+            "waveforms
+V1 a 0 PULSE(0 5 1m 0 0 2m)
+R1 a 0 1k
+V2 b 0 DC 1 PWL(0 0 1m 2 2m 2)
+R2 b 0 1k
+I1 0 c sin(1m 1m 250)
+R3 c 0 1k
+.end",
+        )
+        .unwrap();
+        let (a, b, c) = (
+            netlist.node("a").unwrap(),
+            netlist.node("b").unwrap(),
+            netlist.node("c").unwrap(),
+        );
+
+        let op = dc_op(&netlist.circuit).unwrap();
+        let result = transient(&netlist.circuit, 5e-4, 2e-3).unwrap();
+
+        // Without a DC value, a source takes the value of its waveform at t = 0
+        assert_eq!(op.voltage(a), 0.0);
+        assert_eq!(op.voltage(b), 1.0);
+        assert_relative_eq!(op.voltage(c), 1.0);
+        assert_eq!(result.points[0].voltage(b), 0.0);
+        let last = result.at(1.5e-3).unwrap();
+        assert_eq!(last.voltage(a), 5.0);
+        assert_eq!(last.voltage(b), 2.0);
+        assert_relative_eq!(last.voltage(c), 1.0 + 0.5f64.sqrt(), epsilon = 1e-9);
+    }
+
+    /// An LC tank started by the initial current of its inductor rings around the
+    /// `.ic` voltage of the capacitor, while the operating point holds that voltage.
+    #[test]
+    fn initial_conditions() {
+        let netlist = parse(
+            "initial conditions
+C1 out 0 1u
+L1 out mid 1m IC=10m
+R1 mid 0 1
+.ic v(out)=1
+.nodeset V(mid) = 0.5
+.tran 1u 1m UIC
+.end",
+        )
+        .unwrap();
+        let (out, mid) = (netlist.node("out").unwrap(), netlist.node("mid").unwrap());
+        let inductor = netlist.element("l1").unwrap();
+
+        assert_eq!(
+            netlist.analyses,
+            vec![Analysis::Tran {
+                span: TimeSpan::new(1e-6, 1e-3),
+                uic: true
+            }]
+        );
+        assert_eq!(netlist.circuit.initial_conditions[&out], 1.0);
+        assert_eq!(netlist.circuit.nodesets[&mid], 0.5);
+        assert_eq!(netlist.circuit.parameter(inductor, "ic"), Some(1e-2));
+
+        let uic = transient_uic(&netlist.circuit, 1e-6, 1e-4).unwrap();
+        assert_eq!(uic.points[0].voltage(out), 1.0);
+        // The inductor carries its current away from the capacitor at first
+        let first = &uic.points[1];
+        assert!(first.voltage(out) < 1.0);
+        assert!(first.branch_current(inductor).unwrap() > 1e-2);
+
+        let tran = transient(&netlist.circuit, 1e-6, 1e-4).unwrap();
+        assert_relative_eq!(tran.points[0].voltage(out), 1.0, epsilon = 1e-6);
+        assert_relative_eq!(
+            tran.points[0].branch_current(inductor).unwrap(),
+            1.0,
+            epsilon = 1e-6
+        );
+    }
+
+    /// Each analysis uses its own part of the sources.
+    #[test]
+    fn source_specifications() {
+        let netlist = parse(
+            "source specifications
+V1 in 0 DC 2 AC 1 90 SIN(0 1 1k)
+R1 in out 1k
+I1 0 out AC 2m
+R2 out 0 1k
+.end",
+        )
+        .unwrap();
+        let out = netlist.node("out").unwrap();
+
+        let op = dc_op(&netlist.circuit).unwrap();
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 1.0,
+            points: 1,
+        };
+        let result = ac(&netlist.circuit, sweep).unwrap();
+        let tran = transient(&netlist.circuit, 2.5e-4, 1e-3).unwrap();
+
+        assert_relative_eq!(op.voltage(out), 1.0);
+        assert_relative_eq!(result.points[0].voltage(out).re, 1.0);
+        assert_relative_eq!(result.points[0].voltage(out).im, 0.5);
+        assert_eq!(tran.points[0].voltage(out), 0.0);
+        assert_relative_eq!(tran.at(2.5e-4).unwrap().voltage(out), 0.5);
+    }
+
+    #[test]
+    fn dc_sweeps() {
+        let netlist = parse(
+            "transfer curves
+.dc V1 0 1 0.25 I1 list 0 1m
+V1 in 0 0
+R1 in out 1k
+R2 out 0 1k
+I1 0 out 0
+.dc v1 dec 1 1 100
+.dc I1 0 1 0.3
+.dc V1 1 0 -0.25
+.end",
+        )
+        .unwrap();
+        let (v1, i1) = (
+            netlist.element("v1").unwrap(),
+            netlist.element("i1").unwrap(),
+        );
+
+        let Analysis::Dc {
+            sweep,
+            nested: Some(nested),
+        } = &netlist.analyses[0]
+        else {
+            panic!("expected a nested DC sweep");
+        };
+        assert_eq!(sweep.source, v1);
+        assert_eq!(
+            sweep.sweep,
+            Sweep::Linear {
+                start: 0.0,
+                stop: 1.0,
+                points: 5
+            }
+        );
+        assert_eq!(nested.source, i1);
+        assert_eq!(nested.sweep, Sweep::List(vec![0.0, 1e-3]));
+        assert_eq!(
+            netlist.analyses[1],
+            Analysis::Dc {
+                sweep: SourceSweep {
+                    source: v1,
+                    sweep: Sweep::Decade {
+                        start: 1.0,
+                        stop: 100.0,
+                        points: 1
+                    }
+                },
+                nested: None,
+            }
+        );
+        let Analysis::Dc { sweep: uneven, .. } = &netlist.analyses[2] else {
+            panic!("expected a DC sweep");
+        };
+        assert_eq!(uneven.sweep.values().unwrap().len(), 4);
+        let Analysis::Dc {
+            sweep: descending, ..
+        } = &netlist.analyses[3]
+        else {
+            panic!("expected a DC sweep");
+        };
+        let falling = dc_sweep(&netlist.circuit, descending, None).unwrap();
+        assert_eq!(falling.values, vec![1.0, 0.75, 0.5, 0.25, 0.0]);
+
+        let result = dc_sweep(&netlist.circuit, sweep, Some(nested)).unwrap();
+        let out = netlist.node("out").unwrap();
+        assert_eq!(result.len(), 10);
+        assert_relative_eq!(result.points[4].voltage(out), 0.5);
+        assert_relative_eq!(result.points[9].voltage(out), 1.0);
+    }
+
+    /// A resistance stepped across the operating point, and the DC value of a source
+    /// and the temperature, which need no parameter name.
+    #[test]
+    fn steps() {
+        let netlist = parse(
+            "stepped divider
+.step R2 R list 1k 3k
+V1 in 0 1
+R1 in out 1k
+R2 out 0 1k
+D1 out 0 dmod
+.model dmod D
+.options temp=50
+.op
+.end",
+        )
+        .unwrap();
+        let r2 = netlist.element("r2").unwrap();
+        let d1 = netlist.element("d1").unwrap();
+
+        let parameter = netlist.step.as_ref().unwrap();
+        assert_eq!(
+            parameter.stepped,
+            Stepped::Parameter {
+                element: r2,
+                name: "r".to_string()
+            }
+        );
+        assert_eq!(netlist.circuit.parameter(d1, "temp"), Some(50.0));
+        let result = step(&netlist.circuit, parameter, dc_op).unwrap();
+        let out = netlist.node("out").unwrap();
+        assert!(result.results[0].voltage(out) < result.results[1].voltage(out));
+
+        let source = parse("source\nV1 1 0 1\n.step V1 0 1 0.5\n.end").unwrap();
+        assert_eq!(
+            source.step,
+            Some(ParameterStep {
+                stepped: Stepped::Parameter {
+                    element: source.element("v1").unwrap(),
+                    name: "dc".to_string()
+                },
+                sweep: Sweep::Linear {
+                    start: 0.0,
+                    stop: 1.0,
+                    points: 3
+                },
+            })
+        );
+        let temperature = parse("temperature\n.step temp dec 1 1 100\n.end").unwrap();
+        assert_eq!(temperature.step.unwrap().stepped, Stepped::Temperature);
+    }
+
+    #[test]
+    fn mutual_inductance() {
+        let netlist = parse(
+            "coupled inductors
+K1 L1 L2 0.5
+Kall L1 L2 L3 -1
+L1 p 0 1m
+L2 s 0 4m
+L3 t 0 4m
+.end",
+        )
+        .unwrap();
+
+        let coupling = |name| {
+            let id = netlist.element(name).unwrap();
+            netlist.circuit.element::<MutualInductance>(id).unwrap()
+        };
+
+        assert_eq!(coupling("k1").coefficient(), 0.5);
+        assert_eq!(coupling("kall").coefficient(), -1.0);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| parse(source).err().unwrap();
+
+        assert_eq!(
+            error("title\nR1 1 0 2\n  J1 1 0 0"),
+            ParseError {
+                line: 3,
+                column: 3,
+                kind: ParseErrorKind::UnknownElement('J'),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 1 0 2k!"),
+            ParseError {
+                line: 2,
+                column: 8,
+                kind: ParseErrorKind::InvalidValue("2k!".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 1 0"),
+            ParseError {
+                line: 2,
+                column: 7,
+                kind: ParseErrorKind::Missing("a resistance"),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 1 0 2 3"),
+            ParseError {
+                line: 2,
+                column: 10,
+                kind: ParseErrorKind::Unexpected("3".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 1 0 2\nr1 1 0 2"),
+            ParseError {
+                line: 3,
+                column: 1,
+                kind: ParseErrorKind::DuplicateElement("r1".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.ac dec 10 1"),
+            ParseError {
+                line: 2,
+                column: 13,
+                kind: ParseErrorKind::Missing("a stop frequency"),
+            }
+        );
+        assert_eq!(
+            error("title\n.dc V1 0 1 0.1"),
+            ParseError {
+                line: 2,
+                column: 5,
+                kind: ParseErrorKind::NotASource("V1".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nV1 1 0 1\n.dc V1 1 0 0.1"),
+            ParseError {
+                line: 3,
+                column: 12,
+                kind: ParseErrorKind::InvalidValue("0.1".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.sens v(1)"),
+            ParseError {
+                line: 2,
+                column: 1,
+                kind: ParseErrorKind::UnknownControl(".sens".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.options gmin=1p"),
+            ParseError {
+                line: 2,
+                column: 10,
+                kind: ParseErrorKind::UnknownOption("gmin".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nC1 1 0 1u V=1"),
+            ParseError {
+                line: 2,
+                column: 11,
+                kind: ParseErrorKind::UnknownParameter("V".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 1 0 1\n.ic v(1)=1 i(1)=0"),
+            ParseError {
+                line: 3,
+                column: 12,
+                kind: ParseErrorKind::Unexpected("i".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 out 0 1k\n.ic v(otu)=1"),
+            ParseError {
+                line: 3,
+                column: 7,
+                kind: ParseErrorKind::UndefinedNode("otu".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.options method=rk4"),
+            ParseError {
+                line: 2,
+                column: 17,
+                kind: ParseErrorKind::UnknownMethod("rk4".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.options maxord=7"),
+            ParseError {
+                line: 2,
+                column: 17,
+                kind: ParseErrorKind::InvalidValue("7".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nF1 1 0 V1 2"),
+            ParseError {
+                line: 2,
+                column: 8,
+                kind: ParseErrorKind::UnknownControllingElement("V1".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 1 0 2\nH1 2 0 r1 2"),
+            ParseError {
+                line: 3,
+                column: 8,
+                kind: ParseErrorKind::NoBranchCurrent("r1".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nL1 1 0 1m\nR2 1 0 1\nK1 L1 R2 1"),
+            ParseError {
+                line: 4,
+                column: 7,
+                kind: ParseErrorKind::NotAnInductor("R2".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nL1 1 0 1m\nL2 1 0 1m\nK1 L1 L2 1.1"),
+            ParseError {
+                line: 4,
+                column: 10,
+                kind: ParseErrorKind::Coupling(CouplingError::InvalidCoefficient(1.1)),
+            }
+        );
+        assert_eq!(
+            error("title\nL1 1 0 1m\nK1 L1 l1 0.5"),
+            ParseError {
+                line: 3,
+                column: 10,
+                kind: ParseErrorKind::Coupling(CouplingError::SelfCoupling(0)),
+            }
+        );
+        assert_eq!(
+            error("title\nV1 1 0 PULSE(0)"),
+            ParseError {
+                line: 2,
+                column: 8,
+                kind: ParseErrorKind::WaveformArguments("PULSE".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nV1 1 0 1 2"),
+            ParseError {
+                line: 2,
+                column: 10,
+                kind: ParseErrorKind::Unexpected("2".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nD1 1 0 dmod"),
+            ParseError {
+                line: 2,
+                column: 8,
+                kind: ParseErrorKind::UnknownModel("dmod".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.model dmod d(isat=1)"),
+            ParseError {
+                line: 2,
+                column: 15,
+                kind: ParseErrorKind::UnknownParameter("isat".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.model jmod njf"),
+            ParseError {
+                line: 2,
+                column: 13,
+                kind: ParseErrorKind::UnknownModelType("njf".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nD1 1 0 qmod\n.model qmod npn"),
+            ParseError {
+                line: 2,
+                column: 8,
+                kind: ParseErrorKind::WrongModelType("qmod".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.model nch nmos(level=49)"),
+            ParseError {
+                line: 2,
+                column: 23,
+                kind: ParseErrorKind::UnsupportedLevel("49".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 1 0 1\n.step R1 1 2 1"),
+            ParseError {
+                line: 3,
+                column: 9,
+                kind: ParseErrorKind::Missing("a parameter"),
+            }
+        );
+        assert_eq!(
+            error("title\nR1 1 0 1\n.step R1 c 1 2 1"),
+            ParseError {
+                line: 3,
+                column: 10,
+                kind: ParseErrorKind::UnknownParameter("c".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.step X1 r 1 2 1"),
+            ParseError {
+                line: 2,
+                column: 7,
+                kind: ParseErrorKind::UndefinedElement("X1".to_string()),
+            }
+        );
+        assert_eq!(
+            error("title\n.step temp 0 1 1\n.step temp 1 2 1"),
+            ParseError {
+                line: 3,
+                column: 1,
+                kind: ParseErrorKind::MultipleSteps,
+            }
+        );
+        assert_eq!(
+            error("title\n+ 1"),
+            ParseError {
+                line: 2,
+                column: 1,
+                kind: ParseErrorKind::DanglingContinuation,
+            }
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::f64::consts::TAU;
+
+use nalgebra::{Complex, ComplexField, DVector};
+
+use crate::{elements::stamp::StampContext, Circuit};
+
+use super::{
+    dc_op::dc_op, factor, node_labels, operating_point::OperatingPoint, sweep::Sweep, RunnerError,
+};
+
+/// The small-signal solution of a circuit, as complex phasors,
+/// at every frequency of an AC analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcResult {
+    pub frequencies: Vec<f64>,
+    pub points: Vec<OperatingPoint<Complex<f64>>>,
+}
+
+impl AcResult {
+    /// The number of frequency points.
+    pub fn len(&self) -> usize {
+        self.frequencies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frequencies.is_empty()
+    }
+
+    /// Every frequency paired with the solution at that frequency.
+    pub fn iter(&self) -> impl Iterator<Item = (f64, &OperatingPoint<Complex<f64>>)> {
+        self.frequencies.iter().copied().zip(self.points.iter())
+    }
+
+    /// Exports the result as CSV, with a `frequency` column followed by a magnitude and
+    /// phase column for every unknown, such as `|v(out)|` and `phase(v(out))` in degrees.
+    pub fn to_csv(&self) -> String {
+        let Some(first) = self.points.first() else {
+            return "frequency\n".to_string();
+        };
+
+        let mut csv = std::iter::once("frequency".to_string())
+            .chain(
+                first
+                    .unknown_labels()
+                    .flat_map(|label| [format!("|{label}|"), format!("phase({label})")]),
+            )
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push('\n');
+
+        for (frequency, point) in self.iter() {
+            let row = std::iter::once(frequency)
+                .chain(
+                    point
+                        .solution()
+                        .iter()
+                        .flat_map(|value| [value.modulus(), value.argument().to_degrees()]),
+                )
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            csv.push_str(&row);
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+/// AC small-signal analysis to calculate the frequency response of a circuit.
+///
+/// `sweep` gives the frequencies in Hertz to solve the circuit at.
+/// The DC operating point is solved first, to linearize nonlinear elements at.
+pub fn ac(circuit: &Circuit, sweep: Sweep) -> Result<AcResult, RunnerError> {
+    let n = circuit.node_count();
+    if n == 0 {
+        return Err(RunnerError::ZeroNode);
+    }
+
+    let m = circuit.branch_count();
+
+    let labels = node_labels(circuit);
+    let frequencies = sweep.values()?;
+    // Nonlinear elements are linearized at the DC operating point
+    let operating_point = dc_op(circuit)?;
+    // Every frequency stamps the same pattern, so the column order is only found once
+    let mut ordering: Option<Vec<usize>> = None;
+    let mut points = Vec::with_capacity(frequencies.len());
+    for frequency in &frequencies {
+        let mut context = StampContext::new(n, m);
+        for element in circuit.elements().iter() {
+            element.stamp_ac(&mut context, &operating_point, TAU * frequency);
+        }
+        let (a_matrix, z_vector) = context.into_system();
+
+        let lu = factor(&a_matrix.to_csc(), ordering.as_deref(), &labels)?;
+        let solution = lu.solve(&DVector::from_vec(z_vector));
+        ordering.get_or_insert_with(|| lu.ordering().to_vec());
+        points.push(OperatingPoint::new(labels.clone(), solution));
+    }
+
+    Ok(AcResult {
+        frequencies,
+        points,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_4;
+
+    use approx::assert_relative_eq;
+    use nalgebra::{Complex, ComplexField};
+
+    use crate::{
+        elements::{
+            ac_volatage_source::ACVoltageSource, capacitor::Capacitor,
+            dc_voltage_source::DCVoltageSource, inductor::Inductor, resistor::Resistor,
+        },
+        runners::{sweep::Sweep, RunnerError},
+        Circuit,
+    };
+
+    use super::ac;
+
+    /// An RC low-pass filter with a cutoff frequency of 1/(2π * 1kΩ * 1µF) ≈ 159.15Hz.
+    #[test]
+    fn rc_low_pass() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            v1,
+            v0,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, v1, v2)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, v2, v0)));
+
+        let sweep = Sweep::Linear {
+            start: 159.154_94,
+            stop: 159.154_94,
+            points: 1,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_eq!(result.len(), 1);
+        let v2 = result.points[0].voltage(v2);
+        assert_relative_eq!(v2.modulus(), 0.5f64.sqrt(), epsilon = 0.001);
+        assert_relative_eq!(v2.argument(), -FRAC_PI_4, epsilon = 0.001);
+    }
+
+    /// At 0Hz the capacitor of a low-pass filter is an open circuit, so the output
+    /// follows the input.
+    #[test]
+    fn rc_at_dc() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(1.0, 0.0),
+            v1,
+            v0,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, v1, v2)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, v2, v0)));
+
+        let result = ac(&circuit, Sweep::List(vec![0.0, 159.154_94])).unwrap();
+
+        assert_eq!(result.points[0].voltage(v2), Complex::new(1.0, 0.0));
+        assert_relative_eq!(
+            result.points[1].voltage(v2).modulus(),
+            0.5f64.sqrt(),
+            epsilon = 0.001
+        );
+    }
+
+    /// The CSV export labels every column by the node names.
+    #[test]
+    fn csv() {
+        let mut circuit = Circuit::default();
+        let vin = circuit.node("in");
+        let ground = circuit.node("gnd");
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(0.0, 1.0),
+            vin,
+            ground,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1.0, vin, ground)));
+
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 2.0,
+            points: 2,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_eq!(
+            result.to_csv(),
+            "frequency,|v(in)|,phase(v(in)),|i(0)|,phase(i(0))\n1,1,90,1,-90\n2,1,90,1,-90\n"
+        );
+    }
+
+    /// An RL high-pass filter, where the DC source does not contribute to the response.
+    #[test]
+    fn rl_high_pass() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        let v3 = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(5.0, v1, v0)));
+        circuit.add_element(Box::new(ACVoltageSource::new(
+            Complex::new(2.0, 0.0),
+            v2,
+            v1,
+        )));
+        circuit.add_element(Box::new(Resistor::new(1e3, v2, v3)));
+        circuit.add_element(Box::new(Inductor::new(1.0, v3, v0)));
+
+        let sweep = Sweep::Decade {
+            start: 1.0,
+            stop: 1e6,
+            points: 10,
+        };
+        let result = ac(&circuit, sweep).unwrap();
+
+        assert_eq!(result.len(), 61);
+        assert_relative_eq!(result.points[0].voltage(v3).modulus(), 0.0, epsilon = 0.02);
+        assert_relative_eq!(
+            result.points[60].voltage(v3).modulus(),
+            2.0,
+            epsilon = 0.001
+        );
+    }
+
+    #[test]
+    fn zero_node_error() {
+        let circuit = Circuit::default();
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 1.0,
+            points: 1,
+        };
+
+        assert_eq!(ac(&circuit, sweep), Err(RunnerError::ZeroNode));
+    }
+}
@@ -1,38 +1,55 @@
-use nalgebra::{DMatrix, DVector, Dyn, VecStorage, Vector, U1};
-
 use crate::Circuit;
 
-use super::RunnerError;
+use super::{
+    newton::Newton, node_labels, operating_point::OperatingPoint, starting_point, RunnerError,
+};
 
 /// DC Operating Point to calculate the steady state of a circuit.
-pub fn dc_op(circuit: &Circuit) -> Result<Vector<f32, Dyn, VecStorage<f32, Dyn, U1>>, RunnerError> {
-    let n = circuit.node_count();
-    if n == 0 {
-        return Err(RunnerError::ZeroNode);
-    }
-
-    let m = circuit
-        .elements()
-        .iter()
-        .filter(|x| x.is_b_c_element())
-        .count();
-    let z_size = n - 1 + m;
-    let a_size = z_size * z_size;
+///
+/// Circuits with nonlinear elements are solved by Newton-Raphson iteration from the
+/// [`nodesets`](crate::Circuit::nodesets) of the circuit and 0V elsewhere, for at most
+/// [`Options::dc_iterations`](super::options::Options::dc_iterations).
+pub fn dc_op(circuit: &Circuit) -> Result<OperatingPoint, RunnerError> {
+    solve(circuit, None)
+}
 
-    let mut a_matrix: Vec<f32> = vec![0.0; a_size];
-    let mut z_vector: Vec<f32> = vec![0.0; z_size];
+/// The operating point a transient analysis starts from, where independent sources
+/// take the value of their waveform at `t = 0` and the nodes are held at their
+/// [`initial_conditions`](crate::Circuit::initial_conditions).
+pub(crate) fn initial_op(circuit: &Circuit) -> Result<OperatingPoint, RunnerError> {
+    solve(circuit, Some(0.0))
+}
 
-    for element in circuit.elements().iter() {
-        element.stamp(&mut a_matrix, &mut z_vector, n, m);
+fn solve(circuit: &Circuit, time: Option<f64>) -> Result<OperatingPoint, RunnerError> {
+    if circuit.node_count() == 0 {
+        return Err(RunnerError::ZeroNode);
     }
 
-    let a_matrix = DMatrix::from_vec(z_size, z_size, a_matrix);
-    let z_vector = DVector::from_vec(z_vector);
+    let mut newton = Newton::new(circuit, node_labels(circuit));
+    let initial = match time {
+        Some(time) => {
+            let initial = starting_point(
+                circuit,
+                circuit.nodesets.iter().chain(&circuit.initial_conditions),
+            )?;
+            newton.set_time(time);
+            newton.force(
+                circuit
+                    .initial_conditions
+                    .iter()
+                    .map(|(&node, &voltage)| (node, voltage)),
+            );
+            initial
+        }
+        None => starting_point(circuit, &circuit.nodesets)?,
+    };
+    newton.initialize(&initial);
 
-    a_matrix
-        .try_inverse()
-        .map(|a| a * z_vector)
-        .ok_or(RunnerError::MalformedCircuit)
+    newton.solve(
+        initial,
+        circuit.options.dc_iterations,
+        |_, element, context, estimate, state| element.stamp_linearized(context, estimate, state),
+    )
 }
 
 #[cfg(test)]
@@ -41,11 +58,15 @@ mod tests {
 
     use crate::{
         elements::{
-            capacitor::Capacitor, dc_current_source::DCCurrentSource,
-            dc_voltage_source::DCVoltageSource, inductor::Inductor, resistor::Resistor,
+            capacitor::Capacitor,
+            dc_current_source::DCCurrentSource,
+            dc_voltage_source::DCVoltageSource,
+            inductor::Inductor,
+            mosfet::{Mosfet, MosfetKind, MosfetModel},
+            resistor::Resistor,
         },
-        runners::{dc_op::dc_op, RunnerError},
-        Circuit,
+        runners::{dc_op::dc_op, transient::transient, RunnerError},
+        Circuit, NodeId,
     };
 
     /// One node circuit with a 10V voltage source connected to a 2Ω resistor.
@@ -55,13 +76,13 @@ mod tests {
         let v0 = circuit.push_node();
         let v1 = circuit.push_node();
         circuit.add_element(Box::new(Resistor::new(2.0, v1, v0)));
-        circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v0, 0)));
+        let source = circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v0)));
 
-        let matrix = dc_op(&circuit).unwrap();
+        let op = dc_op(&circuit).unwrap();
 
-        assert_eq!(matrix.len(), 2);
-        assert_relative_eq!(matrix[0], 10.0, epsilon = 0.01); // v1
-        assert_relative_eq!(matrix[1], -5.0, epsilon = 0.01); // i_v_source
+        assert_eq!(op.solution().len(), 2);
+        assert_relative_eq!(op.voltage(v1), 10.0, epsilon = 0.01);
+        assert_relative_eq!(op.branch_current(source).unwrap(), -5.0, epsilon = 0.01);
     }
 
     /// One node circuit with a 10A current source connected to a 2Ω resistor.
@@ -73,10 +94,11 @@ mod tests {
         circuit.add_element(Box::new(Resistor::new(2.0, v1, v0)));
         circuit.add_element(Box::new(DCCurrentSource::new(10.0, v0, v1)));
 
-        let matrix = dc_op(&circuit).unwrap();
+        let op = dc_op(&circuit).unwrap();
 
-        assert_eq!(matrix.len(), 1);
-        assert_relative_eq!(matrix[0], -20.0, epsilon = 0.01); // v1
+        assert_eq!(op.solution().len(), 1);
+        assert_relative_eq!(op.voltage(v0), 0.0);
+        assert_relative_eq!(op.voltage(v1), -20.0, epsilon = 0.01);
     }
 
     /// Both current and voltage sources in a 2 node circuit.
@@ -86,18 +108,19 @@ mod tests {
         let v0 = circuit.push_node();
         let v1 = circuit.push_node();
         let v2 = circuit.push_node();
-        circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v2, 0)));
+        let source = circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v2)));
         circuit.add_element(Box::new(Resistor::new(2.0, v1, v0)));
         circuit.add_element(Box::new(Resistor::new(4.0, v1, v2)));
         circuit.add_element(Box::new(Resistor::new(2.0, v2, v0)));
         circuit.add_element(Box::new(DCCurrentSource::new(3.0, v1, v0)));
 
-        let matrix = dc_op(&circuit).unwrap();
+        let op = dc_op(&circuit).unwrap();
 
-        assert_eq!(matrix.len(), 3);
-        assert_relative_eq!(matrix[0], 8.0, epsilon = 0.01); // v1
-        assert_relative_eq!(matrix[1], -2.0, epsilon = 0.01); // v2
-        assert_relative_eq!(matrix[2], -3.5, epsilon = 0.01); // i_v_source
+        assert_eq!(op.solution().len(), 3);
+        assert_relative_eq!(op.voltage(v1), 8.0, epsilon = 0.01);
+        assert_relative_eq!(op.voltage(v2), -2.0, epsilon = 0.01);
+        assert_relative_eq!(op.voltage_between(v1, v2), 10.0, epsilon = 0.01);
+        assert_relative_eq!(op.branch_current(source).unwrap(), -3.5, epsilon = 0.01);
     }
 
     /// A voltage source, resistor, and capacitor connected in series.
@@ -107,16 +130,17 @@ mod tests {
         let v0 = circuit.push_node();
         let v1 = circuit.push_node();
         let v2 = circuit.push_node();
-        circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v0, 0)));
-        circuit.add_element(Box::new(Resistor::new(10.0, v1, v2)));
+        let source = circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v0)));
+        let resistor = circuit.add_element(Box::new(Resistor::new(10.0, v1, v2)));
         circuit.add_element(Box::new(Capacitor::new(1.0, v2, v0)));
 
-        let matrix = dc_op(&circuit).unwrap();
+        let op = dc_op(&circuit).unwrap();
 
-        assert_eq!(matrix.len(), 3);
-        assert_relative_eq!(matrix[0], 10.0, epsilon = 0.01); // v1
-        assert_relative_eq!(matrix[1], 10.0, epsilon = 0.01); // v2
-        assert_relative_eq!(matrix[2], 0.0, epsilon = 0.01); // i_v_source
+        assert_eq!(op.solution().len(), 3);
+        assert_relative_eq!(op.voltage(v1), 10.0, epsilon = 0.01);
+        assert_relative_eq!(op.voltage(v2), 10.0, epsilon = 0.01);
+        assert_relative_eq!(op.branch_current(source).unwrap(), 0.0, epsilon = 0.01);
+        assert_eq!(op.branch_current(resistor), None);
     }
 
     /// A voltage source, resistor, and inductor connected in series.
@@ -126,17 +150,137 @@ mod tests {
         let v0 = circuit.push_node();
         let v1 = circuit.push_node();
         let v2 = circuit.push_node();
-        circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v0, 0)));
+        let source = circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v0)));
+        circuit.add_element(Box::new(Resistor::new(10.0, v1, v2)));
+        let inductor = circuit.add_element(Box::new(Inductor::new(1.0, v2, v0)));
+
+        let op = dc_op(&circuit).unwrap();
+
+        assert_eq!(op.solution().len(), 4);
+        assert_relative_eq!(op.voltage(v1), 10.0, epsilon = 0.01);
+        assert_relative_eq!(op.voltage(v2), 0.0, epsilon = 0.01);
+        assert_relative_eq!(op.branch_current(source).unwrap(), -1.0, epsilon = 0.01);
+        assert_relative_eq!(op.branch_current(inductor).unwrap(), 1.0, epsilon = 0.01);
+    }
+
+    /// The same circuit as [`inductor`], but with the inductor added before the source.
+    #[test]
+    fn branch_handles() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        let inductor = circuit.add_element(Box::new(Inductor::new(1.0, v2, v0)));
         circuit.add_element(Box::new(Resistor::new(10.0, v1, v2)));
-        circuit.add_element(Box::new(Inductor::new(1.0, v2, v0, 1)));
+        let source = circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v0)));
+
+        let op = dc_op(&circuit).unwrap();
+
+        assert_relative_eq!(op.voltage(v1), 10.0, epsilon = 0.01);
+        assert_relative_eq!(op.voltage(v2), 0.0, epsilon = 0.01);
+        assert_relative_eq!(op.branch_current(source).unwrap(), -1.0, epsilon = 0.01);
+        assert_relative_eq!(op.branch_current(inductor).unwrap(), 1.0, epsilon = 0.01);
+    }
+
+    /// A divider of a 1mΩ and a 1TΩ resistor, which needs more than 32-bit precision.
+    #[test]
+    fn high_dynamic_range() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(1.0, v1, v0)));
+        circuit.add_element(Box::new(Resistor::new(1e12, v1, v2)));
+        circuit.add_element(Box::new(Resistor::new(1e-3, v2, v0)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, v2, v0)));
 
-        let matrix = dc_op(&circuit).unwrap();
+        let op = dc_op(&circuit).unwrap();
 
-        assert_eq!(matrix.len(), 4);
-        assert_relative_eq!(matrix[0], 10.0, epsilon = 0.01); // v1
-        assert_relative_eq!(matrix[1], 0.0, epsilon = 0.01); // v2
-        assert_relative_eq!(matrix[2], -1.0, epsilon = 0.01); // i_v_source
-        assert_relative_eq!(matrix[3], 1.0, epsilon = 0.01); // i_inductor
+        assert_relative_eq!(op.voltage(v2), 1e-15, max_relative = 1e-9);
+    }
+
+    /// A 50x50 mesh of 1Ω resistors, with every node on the left edge connected to the
+    /// source and every node on the right edge to ground through another 1Ω resistor.
+    ///
+    /// Every row of the mesh is then a chain of 51 equal resistors carrying the same current,
+    /// which is far too large to solve as a dense matrix.
+    #[test]
+    fn resistor_mesh() {
+        const SIZE: usize = 50;
+
+        let mut circuit = Circuit::default();
+        let ground = circuit.node("0");
+        let vin = circuit.node("in");
+        let mesh: Vec<Vec<_>> = (0..SIZE)
+            .map(|row| {
+                (0..SIZE)
+                    .map(|column| circuit.node(&format!("{row}_{column}")))
+                    .collect()
+            })
+            .collect();
+        circuit.add_element(Box::new(DCVoltageSource::new(51.0, vin, ground)));
+        for row in 0..SIZE {
+            circuit.add_element(Box::new(Resistor::new(1.0, vin, mesh[row][0])));
+            circuit.add_element(Box::new(Resistor::new(1.0, mesh[row][SIZE - 1], ground)));
+            for column in 0..SIZE {
+                if column + 1 < SIZE {
+                    circuit.add_element(Box::new(Resistor::new(
+                        1.0,
+                        mesh[row][column],
+                        mesh[row][column + 1],
+                    )));
+                }
+                if row + 1 < SIZE {
+                    circuit.add_element(Box::new(Resistor::new(
+                        1.0,
+                        mesh[row][column],
+                        mesh[row + 1][column],
+                    )));
+                }
+            }
+        }
+
+        let op = dc_op(&circuit).unwrap();
+
+        for row in [0, 17, SIZE - 1] {
+            for column in [0, 25, SIZE - 1] {
+                let expected = (SIZE - column) as f64;
+                assert_relative_eq!(op.voltage(mesh[row][column]), expected, epsilon = 1e-9);
+            }
+        }
+    }
+
+    /// A latch of two cross-coupled NMOS inverters settles in whichever of its two states
+    /// the nodesets start it nearest to.
+    #[test]
+    fn nodesets() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vdd = circuit.push_node();
+        let q = circuit.push_node();
+        let q_bar = circuit.push_node();
+        let model = MosfetModel {
+            threshold_voltage: 1.0,
+            transconductance: 1e-4,
+            ..Default::default()
+        };
+        circuit.add_element(Box::new(DCVoltageSource::new(5.0, vdd, ground)));
+        circuit.add_element(Box::new(Resistor::new(1e5, vdd, q)));
+        circuit.add_element(Box::new(Resistor::new(1e5, vdd, q_bar)));
+        circuit.add_element(Box::new(
+            Mosfet::new(MosfetKind::Nmos, q, q_bar, ground, ground).with_model(model),
+        ));
+        circuit.add_element(Box::new(
+            Mosfet::new(MosfetKind::Nmos, q_bar, q, ground, ground).with_model(model),
+        ));
+
+        for (high, low) in [(q, q_bar), (q_bar, q)] {
+            circuit.nodesets = [(high, 5.0), (low, 0.0)].into();
+            let op = dc_op(&circuit).unwrap();
+
+            assert!(op.voltage(high) > 4.5);
+            assert!(op.voltage(low) < 0.5);
+        }
     }
 
     #[test]
@@ -146,12 +290,51 @@ mod tests {
         assert_eq!(dc_op(&circuit), Err(RunnerError::ZeroNode));
     }
 
+    /// A nodeset or initial condition of a node outside the circuit.
     #[test]
-    fn malformed_circuit_error() {
+    fn unknown_node_error() {
         let mut circuit = Circuit::default();
         let v0 = circuit.push_node();
-        circuit.add_element(Box::new(DCVoltageSource::new(10.0, v0, v0, 0)));
+        let v1 = circuit.push_node();
+        circuit.add_element(Box::new(Resistor::new(1.0, v1, v0)));
+        circuit.nodesets.insert(NodeId(2), 1.0);
+
+        assert_eq!(dc_op(&circuit), Err(RunnerError::UnknownNode(2)));
+
+        circuit.nodesets.clear();
+        circuit.initial_conditions.insert(NodeId(3), 1.0);
+
+        assert_eq!(
+            transient(&circuit, 1e-3, 1e-2),
+            Err(RunnerError::UnknownNode(3))
+        );
+    }
+
+    #[test]
+    fn singular_matrix_error() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(10.0, v0, v0)));
+
+        assert_eq!(
+            dc_op(&circuit),
+            Err(RunnerError::SingularMatrix("i(0)".to_string()))
+        );
+    }
+
+    /// A node only connected through a capacitor floats at DC.
+    #[test]
+    fn floating_node_error() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.node("0");
+        let vin = circuit.node("in");
+        let vout = circuit.node("out");
+        circuit.add_element(Box::new(DCVoltageSource::new(10.0, vin, ground)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, vin, vout)));
 
-        assert_eq!(dc_op(&circuit), Err(RunnerError::MalformedCircuit));
+        assert_eq!(
+            dc_op(&circuit),
+            Err(RunnerError::SingularMatrix("v(out)".to_string()))
+        );
     }
 }
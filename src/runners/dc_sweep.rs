@@ -0,0 +1,263 @@
+use crate::{
+    elements::{dc_current_source::DCCurrentSource, dc_voltage_source::DCVoltageSource},
+    Circuit, ElementId,
+};
+
+use super::{
+    newton::Newton, node_labels, operating_point::OperatingPoint, starting_point, sweep::Sweep,
+    RunnerError,
+};
+
+/// A [`DCVoltageSource`] or [`DCCurrentSource`] and the values to sweep its DC value through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceSweep {
+    pub source: ElementId,
+    pub sweep: Sweep,
+}
+
+/// The operating point of a circuit at every step of a DC sweep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcSweepResult {
+    /// The value of the swept source at every step.
+    pub values: Vec<f64>,
+    /// The value of the nested source at every step, which is empty without one.
+    pub nested_values: Vec<f64>,
+    pub points: Vec<OperatingPoint>,
+}
+
+impl DcSweepResult {
+    /// The number of steps.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Every value of the swept source paired with the solution at that value.
+    pub fn iter(&self) -> impl Iterator<Item = (f64, &OperatingPoint)> {
+        self.values.iter().copied().zip(self.points.iter())
+    }
+
+    /// Exports the result as CSV, with a `sweep` column, a `nested` column if there is a
+    /// nested sweep, and a column for every node voltage and branch current.
+    pub fn to_csv(&self) -> String {
+        let Some(first) = self.points.first() else {
+            return "sweep\n".to_string();
+        };
+        let nested = !self.nested_values.is_empty();
+
+        let mut csv = std::iter::once("sweep".to_string())
+            .chain(nested.then(|| "nested".to_string()))
+            .chain(first.unknown_labels())
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push('\n');
+
+        for (index, (value, point)) in self.iter().enumerate() {
+            let row = std::iter::once(value)
+                .chain(nested.then(|| self.nested_values[index]))
+                .chain(point.solution().iter().copied())
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            csv.push_str(&row);
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+/// DC sweep analysis, such as to trace the transfer curve of a circuit.
+///
+/// Solves the operating point of the circuit at every value of the swept source.
+/// With a `nested` sweep, the first source is swept through all of its values for every
+/// value of the nested source. Every step starts its Newton-Raphson iteration from the
+/// solution of the step before it, and the first from the
+/// [`nodesets`](crate::Circuit::nodesets) of the circuit.
+pub fn dc_sweep(
+    circuit: &Circuit,
+    sweep: &SourceSweep,
+    nested: Option<&SourceSweep>,
+) -> Result<DcSweepResult, RunnerError> {
+    let n = circuit.node_count();
+    if n == 0 {
+        return Err(RunnerError::ZeroNode);
+    }
+
+    let values = sweep.sweep.values()?;
+    let nested_values = match nested {
+        Some(nested) => nested.sweep.values()?,
+        None => Vec::new(),
+    };
+
+    // The sources are changed on a copy, so the circuit itself stays as it was
+    let mut circuit = circuit.clone();
+    let labels = node_labels(&circuit);
+    // The first step starts from the nodesets, and every other from the step before it
+    let mut previous = starting_point(&circuit, &circuit.nodesets)?;
+    let mut result = DcSweepResult {
+        values: Vec::new(),
+        nested_values: Vec::new(),
+        points: Vec::new(),
+    };
+
+    // Without a nested sweep, the outer loop runs once and sets nothing
+    let outer: Vec<Option<f64>> = match nested_values.is_empty() {
+        true => vec![None],
+        false => nested_values.into_iter().map(Some).collect(),
+    };
+    for nested_value in outer {
+        if let (Some(nested), Some(value)) = (nested, nested_value) {
+            set_dc(&mut circuit, nested.source, value)?;
+        }
+
+        for &value in &values {
+            set_dc(&mut circuit, sweep.source, value)?;
+
+            let mut newton = Newton::new(&circuit, labels.clone());
+            newton.initialize(&previous);
+            let point = newton.solve(
+                previous,
+                circuit.options.dc_iterations,
+                |_, element, context, estimate, state| {
+                    element.stamp_linearized(context, estimate, state)
+                },
+            )?;
+
+            result.values.push(value);
+            result.nested_values.extend(nested_value);
+            result.points.push(point.clone());
+            previous = point;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Sets the DC value of the `source` of the `circuit`.
+fn set_dc(circuit: &mut Circuit, source: ElementId, value: f64) -> Result<(), RunnerError> {
+    if let Some(voltage_source) = circuit.element_mut::<DCVoltageSource>(source) {
+        voltage_source.set_dc(value);
+        return Ok(());
+    }
+    let current_source = circuit
+        .element_mut::<DCCurrentSource>(source)
+        .ok_or(RunnerError::NotASource(source.index()))?;
+    current_source.set_dc(value);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use crate::{
+        elements::{
+            dc_current_source::DCCurrentSource, dc_voltage_source::DCVoltageSource, diode::Diode,
+            resistor::Resistor, Element,
+        },
+        runners::{sweep::Sweep, RunnerError},
+        Circuit,
+    };
+
+    use super::{dc_sweep, SourceSweep};
+
+    /// The output of a divider follows the swept input, without changing the circuit itself.
+    #[test]
+    fn divider() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vin = circuit.push_node();
+        let vout = circuit.push_node();
+        let source = circuit.add_element(Box::new(DCVoltageSource::new(1.0, vin, ground)));
+        circuit.add_element(Box::new(Resistor::new(1e3, vin, vout)));
+        circuit.add_element(Box::new(Resistor::new(1e3, vout, ground)));
+
+        let sweep = SourceSweep {
+            source,
+            sweep: Sweep::Linear {
+                start: 0.0,
+                stop: 2.0,
+                points: 5,
+            },
+        };
+        let result = dc_sweep(&circuit, &sweep, None).unwrap();
+
+        assert_eq!(result.len(), 5);
+        assert!(result.nested_values.is_empty());
+        for (value, point) in result.iter() {
+            assert_relative_eq!(point.voltage(vout), value / 2.0);
+        }
+        assert!(result
+            .to_csv()
+            .starts_with("sweep,v(1),v(2),i(0)\n0,0,0,0\n0.5,0.5,0.25,"));
+        let unchanged = circuit.element::<DCVoltageSource>(source).unwrap();
+        assert_eq!(unchanged.dc_voltage(), 1.0);
+    }
+
+    /// The voltage of a diode fed from a swept source, for every value of a current source
+    /// in parallel with it, which is a family of curves.
+    #[test]
+    fn nested() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vin = circuit.push_node();
+        let anode = circuit.push_node();
+        let source = circuit.add_element(Box::new(DCVoltageSource::new(0.0, vin, ground)));
+        circuit.add_element(Box::new(Resistor::new(1e3, vin, anode)));
+        circuit.add_element(Box::new(Diode::new(anode, ground)));
+        let bias = circuit.add_element(Box::new(DCCurrentSource::new(0.0, anode, ground)));
+
+        let sweep = SourceSweep {
+            source,
+            sweep: Sweep::List(vec![-1.0, 0.0, 5.0]),
+        };
+        let nested = SourceSweep {
+            source: bias,
+            sweep: Sweep::Decade {
+                start: 1e-3,
+                stop: 1e-2,
+                points: 1,
+            },
+        };
+        let result = dc_sweep(&circuit, &sweep, Some(&nested)).unwrap();
+
+        assert_eq!(result.values, vec![-1.0, 0.0, 5.0, -1.0, 0.0, 5.0]);
+        assert_relative_eq!(result.nested_values[2], 1e-3);
+        assert_relative_eq!(result.nested_values[3], 1e-2);
+        assert!(result
+            .to_csv()
+            .starts_with("sweep,nested,v(1),v(2),i(0)\n-1,0.001,-1,"));
+        // More current from either source raises the diode voltage
+        let voltages: Vec<f64> = result
+            .points
+            .iter()
+            .map(|point| point.voltage(anode))
+            .collect();
+        assert!(voltages[0] < voltages[1] && voltages[1] < voltages[2]);
+        assert!(voltages[2] < voltages[5]);
+        assert!(voltages[1] > 0.5);
+    }
+
+    #[test]
+    fn not_a_source_error() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let v1 = circuit.push_node();
+        let resistor = circuit.add_element(Box::new(Resistor::new(1.0, v1, ground)));
+
+        let sweep = SourceSweep {
+            source: resistor,
+            sweep: Sweep::List(vec![1.0]),
+        };
+
+        assert_eq!(
+            dc_sweep(&circuit, &sweep, None),
+            Err(RunnerError::NotASource(0))
+        );
+    }
+}
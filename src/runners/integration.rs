@@ -0,0 +1,255 @@
+use nalgebra::{DMatrix, DVector};
+
+/// How a transient analysis integrates the charges and fluxes of a circuit over time,
+/// as set by `.options method=`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// First order, which damps oscillations but never rings.
+    BackwardEuler,
+    /// Second order, which keeps oscillations undamped but may ring on abrupt changes.
+    #[default]
+    Trapezoidal,
+    /// Gear's backward differentiation formulas, up to the order of `MAXORD`.
+    Gear,
+}
+
+impl Method {
+    /// The integrator of this method at the highest `order` the past time points allow.
+    pub fn integrator(&self, order: usize) -> Box<dyn Integrator> {
+        match (self, order) {
+            (Self::BackwardEuler, _) | (_, 0 | 1) => Box::new(BackwardEuler),
+            (Self::Trapezoidal, _) => Box::new(Trapezoidal),
+            (Self::Gear, order) => Box::new(Gear::new(order)),
+        }
+    }
+}
+
+/// The derivative of a charge at the time point being solved, written as a sum over its
+/// value there and at the past time points, and over its derivative at the previous one:
+///
+/// `dq/dt(n) = Σ a[i] * q(n - i) + b * dq/dt(n - 1)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coefficients {
+    /// `a`, from the time point being solved back into the past.
+    pub charges: Vec<f64>,
+    /// `b`.
+    pub derivative: f64,
+}
+
+/// A linear multistep method that integrates charges and fluxes over time.
+pub trait Integrator {
+    /// The order of the method, which is one less than the power of the time step its
+    /// local truncation error grows with.
+    fn order(&self) -> usize;
+
+    /// The coefficients of the derivative given the latest `steps` between time points,
+    /// starting with the one being taken, at least [`Integrator::order`] long.
+    fn coefficients(&self, steps: &[f64]) -> Coefficients;
+
+    /// The local truncation error of a step in units of the divided difference of the charge
+    /// of one order higher than the method, times the step to the power of that order.
+    fn error_constant(&self) -> f64;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BackwardEuler;
+
+impl Integrator for BackwardEuler {
+    fn order(&self) -> usize {
+        1
+    }
+
+    /// `dq/dt(n) = (q(n) - q(n - 1)) / h`
+    fn coefficients(&self, steps: &[f64]) -> Coefficients {
+        Coefficients {
+            charges: vec![steps[0].recip(), -steps[0].recip()],
+            derivative: 0.0,
+        }
+    }
+
+    fn error_constant(&self) -> f64 {
+        0.5
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Trapezoidal;
+
+impl Integrator for Trapezoidal {
+    fn order(&self) -> usize {
+        2
+    }
+
+    /// `dq/dt(n) = 2 * (q(n) - q(n - 1)) / h - dq/dt(n - 1)`
+    fn coefficients(&self, steps: &[f64]) -> Coefficients {
+        let coefficient = 2.0 / steps[0];
+        Coefficients {
+            charges: vec![coefficient, -coefficient],
+            derivative: -1.0,
+        }
+    }
+
+    fn error_constant(&self) -> f64 {
+        1.0 / 12.0
+    }
+}
+
+/// The backward differentiation formula of an order from 1 to 6, which is the derivative of
+/// the polynomial through the charge at the time point being solved and `order` past ones.
+#[derive(Debug, Clone, Copy)]
+pub struct Gear {
+    order: usize,
+}
+
+impl Gear {
+    /// The error constants of every order at a constant step, as in SPICE.
+    const ERROR_CONSTANTS: [f64; 6] = [
+        0.5,
+        0.222_222_222_2,
+        0.136_363_636_4,
+        0.096,
+        0.072_992_700_73,
+        0.058_309_037_90,
+    ];
+
+    /// # Panics
+    ///
+    /// If the `order` is not from 1 to 6, beyond which the formulas are unstable.
+    pub fn new(order: usize) -> Self {
+        assert!(
+            (1..=6).contains(&order),
+            "Gear integration is stable up to the 6th order"
+        );
+        Self { order }
+    }
+}
+
+impl Integrator for Gear {
+    fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Solves for the coefficients that differentiate every polynomial up to the order
+    /// exactly, at the times of the past time points relative to the step being taken.
+    fn coefficients(&self, steps: &[f64]) -> Coefficients {
+        let step = steps[0];
+        // The time points relative to the one being solved, in units of the step
+        let mut times = vec![0.0];
+        for past in &steps[..self.order] {
+            times.push(times[times.len() - 1] - past / step);
+        }
+
+        let size = self.order + 1;
+        let vandermonde =
+            DMatrix::from_fn(size, size, |power, point| times[point].powi(power as i32));
+        let mut derivative = DVector::zeros(size);
+        derivative[1] = 1.0;
+        let charges = vandermonde
+            .lu()
+            .solve(&derivative)
+            .expect("the time points are distinct");
+
+        Coefficients {
+            charges: charges.iter().map(|a| a / step).collect(),
+            derivative: 0.0,
+        }
+    }
+
+    fn error_constant(&self) -> f64 {
+        Self::ERROR_CONSTANTS[self.order - 1]
+    }
+}
+
+/// The integration of the charges of a single element over the step being solved,
+/// which its companion model is stamped from.
+#[derive(Debug, Clone, Copy)]
+pub struct Integration<'a> {
+    coefficient: f64,
+    history: &'a [f64],
+}
+
+impl<'a> Integration<'a> {
+    /// * `coefficient` - The first coefficient of the [`Coefficients::charges`].
+    /// * `history` - The part of the derivative of every charge of the element
+    ///   that comes from the past.
+    pub(crate) fn new(coefficient: f64, history: &'a [f64]) -> Self {
+        Self {
+            coefficient,
+            history,
+        }
+    }
+
+    /// How much the derivative of a charge changes with its value, which is `1/h`
+    /// for backward Euler.
+    pub fn coefficient(&self) -> f64 {
+        self.coefficient
+    }
+
+    /// The derivative of the charge at `index`, as in
+    /// [`Element::charges`](crate::elements::Element::charges), given its `charge`
+    /// at the time point being solved.
+    pub fn derivative(&self, index: usize, charge: f64) -> f64 {
+        self.coefficient * charge + self.history[index]
+    }
+
+    /// The companion model of the charge at `index`, given as the charge and its capacitance
+    /// at `voltage`, which is a conductance in parallel with a current source, so that
+    /// together they carry the derivative of the charge.
+    ///
+    /// Returns the conductance and the current of the source. For a flux, given with its
+    /// inductance at a current, they are a resistance and a voltage source in series.
+    pub fn companion(
+        &self,
+        index: usize,
+        voltage: f64,
+        (charge, capacitance): (f64, f64),
+    ) -> (f64, f64) {
+        let conductance = self.coefficient * capacitance;
+
+        (
+            conductance,
+            self.derivative(index, charge) - conductance * voltage,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::{Gear, Integrator, Method, Trapezoidal};
+
+    /// The second order formula at a constant step is `(3q(n) - 4q(n - 1) + q(n - 2)) / 2h`.
+    #[test]
+    fn gear_coefficients() {
+        let coefficients = Gear::new(2).coefficients(&[0.5, 0.5]);
+
+        for (a, expected) in coefficients.charges.iter().zip([3.0, -4.0, 1.0]) {
+            assert_relative_eq!(*a, expected, epsilon = 1e-12);
+        }
+        // Every order differentiates a polynomial of its order exactly, at any steps
+        let steps = [1e-3, 2e-3, 5e-4, 1e-3, 3e-3, 1e-3];
+        for order in 1..=6 {
+            let coefficients = Gear::new(order).coefficients(&steps);
+            let mut time = 0.0;
+            let mut derivative = 0.0;
+            for (i, a) in coefficients.charges.iter().enumerate() {
+                if i > 0 {
+                    time -= steps[i - 1];
+                }
+                derivative += a * (1.0 + time).powi(order as i32);
+            }
+            assert_relative_eq!(derivative, order as f64, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn method_orders() {
+        assert_eq!(Method::Gear.integrator(4).order(), 4);
+        assert_eq!(Method::Gear.integrator(1).order(), 1);
+        assert_eq!(Method::Trapezoidal.integrator(1).order(), 1);
+        assert_eq!(Method::Trapezoidal.integrator(6).order(), 2);
+        assert_eq!(Method::BackwardEuler.integrator(6).order(), 1);
+        assert_eq!(Trapezoidal.coefficients(&[0.5]).derivative, -1.0);
+    }
+}
@@ -0,0 +1,483 @@
+use std::ops::Range;
+
+use nalgebra::{convert, one, zero, ComplexField, DVector, RealField};
+use thiserror::Error;
+
+use crate::sparse::{minimum_degree, CscMatrix};
+
+/// A pivot on the diagonal is kept if it is at least this fraction of the largest candidate,
+/// which trades a little stability for keeping the fill-reducing order.
+const PIVOT_THRESHOLD: f64 = 1e-3;
+
+/// The matrix has no usable pivot for `unknown`, so it cannot be solved.
+#[derive(Error, Debug, PartialEq)]
+#[error("the matrix is singular at unknown {unknown}")]
+pub struct SingularMatrix {
+    /// The index of the unknown, as a column of the matrix.
+    pub unknown: usize,
+}
+
+/// A sparse LU factorization `P * A * Q = L * U`, with a fill-reducing column order `Q`
+/// and threshold partial pivoting for the row order `P`.
+///
+/// Factoring is the expensive part of solving `A * x = b`, so a factorization
+/// is kept and reused for every right-hand side `b` that shares the same `A`.
+/// Matrices with the same pattern, such as those of every time step, can also
+/// reuse the column order through [`Factorization::with_ordering`].
+///
+/// ```
+/// use nalgebra::{DMatrix, DVector};
+/// use spice_rs::{runners::lu::Factorization, sparse::CscMatrix};
+///
+/// let matrix = DMatrix::<f64>::from_row_slice(2, 2, &[4.0, 1.0, 2.0, 3.0]);
+/// let lu = Factorization::new(&CscMatrix::from(&matrix)).unwrap();
+///
+/// assert_eq!(lu.solve(&DVector::from_vec(vec![5.0, 5.0])), DVector::from_vec(vec![1.0, 1.0]));
+/// assert_eq!(lu.solve(&DVector::from_vec(vec![4.0, 2.0])), DVector::from_vec(vec![1.0, 0.0]));
+/// assert!((lu.condition_number() - 3.0).abs() < 1e-9);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Factorization<T: ComplexField> {
+    /// Every column starts with its unit diagonal.
+    lower: Triangle<T>,
+    /// Every column ends with its diagonal.
+    upper: Triangle<T>,
+    /// The pivot step of every row of `A`.
+    pivots: Vec<usize>,
+    /// The column of `A` eliminated at every step.
+    ordering: Vec<usize>,
+    /// The 1-norm of the factored matrix.
+    norm: T::RealField,
+}
+
+impl<T: ComplexField> Factorization<T> {
+    /// Factors `matrix`, eliminating its columns in [`minimum_degree`] order.
+    ///
+    /// See [`Factorization::with_ordering`] for when the matrix is singular.
+    pub fn new(matrix: &CscMatrix<T>) -> Result<Self, SingularMatrix> {
+        Self::with_ordering(matrix, minimum_degree(matrix))
+    }
+
+    /// Factors `matrix`, eliminating its columns in the given `ordering`.
+    ///
+    /// Fails on the first column whose pivot is lost to rounding
+    /// relative to the largest entry of that column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ordering` is not a permutation of the columns of `matrix`.
+    pub fn with_ordering(
+        matrix: &CscMatrix<T>,
+        ordering: Vec<usize>,
+    ) -> Result<Self, SingularMatrix> {
+        let size = matrix.size();
+        assert_eq!(
+            ordering.len(),
+            size,
+            "the ordering must have one entry per unknown"
+        );
+
+        let threshold: T::RealField = convert(PIVOT_THRESHOLD);
+        let epsilon: T::RealField = convert(f64::EPSILON);
+
+        let mut lower: Triangle<T> = Triangle::new(size);
+        let mut upper: Triangle<T> = Triangle::new(size);
+        let mut pivots: Vec<Option<usize>> = vec![None; size];
+        let mut x = vec![T::zero(); size];
+        let mut marked = vec![false; size];
+        let mut reach = Vec::with_capacity(size);
+        let mut stack = Vec::new();
+
+        for (step, &column) in ordering.iter().enumerate() {
+            let (rows, values) = matrix.column(column);
+
+            // The rows of L⁻¹ * A(:, column) that can be non-zero, in topological order
+            reach.clear();
+            for &row in rows {
+                if !marked[row] {
+                    lower.depth_first(row, &pivots, &mut marked, &mut stack, &mut reach);
+                }
+            }
+            reach.reverse();
+            for &row in &reach {
+                marked[row] = false;
+            }
+
+            for (&row, value) in rows.iter().zip(values) {
+                x[row] = value.clone();
+            }
+            for &row in &reach {
+                if let Some(pivot_step) = pivots[row] {
+                    let value = x[row].clone();
+                    for (&other, l) in lower.below_diagonal(pivot_step) {
+                        x[other] -= l.clone() * value.clone();
+                    }
+                }
+            }
+
+            // The rows already pivoted on form U, the largest of the others is the pivot
+            let mut candidate: Option<(usize, T::RealField)> = None;
+            for &row in &reach {
+                let modulus = x[row].clone().modulus();
+                match pivots[row] {
+                    Some(pivot_step) => upper.push(pivot_step, x[row].clone()),
+                    None if candidate
+                        .as_ref()
+                        .is_none_or(|(_, largest)| modulus > *largest) =>
+                    {
+                        candidate = Some((row, modulus));
+                    }
+                    None => (),
+                }
+            }
+
+            let tolerance = values.iter().fold(zero::<T::RealField>(), |a, value| {
+                a.max(value.clone().modulus())
+            }) * epsilon.clone();
+            let pivot_row = match candidate {
+                Some((row, largest)) if largest > tolerance => {
+                    let diagonal = x[column].clone().modulus();
+                    // The diagonal keeps the fill of the ordering, as long as it is not too small
+                    match pivots[column].is_none()
+                        && diagonal > tolerance
+                        && diagonal >= largest * threshold.clone()
+                    {
+                        true => column,
+                        false => row,
+                    }
+                }
+                _ => return Err(SingularMatrix { unknown: column }),
+            };
+
+            let pivot = x[pivot_row].clone();
+            pivots[pivot_row] = Some(step);
+            upper.push(step, pivot.clone());
+            upper.end_column();
+
+            lower.push(pivot_row, T::one());
+            for &row in &reach {
+                if pivots[row].is_none() {
+                    lower.push(row, x[row].clone() / pivot.clone());
+                }
+                x[row] = T::zero();
+            }
+            lower.end_column();
+        }
+
+        // Number the rows of L by pivot step, like the rows of U
+        let pivots: Vec<usize> = pivots
+            .into_iter()
+            .map(|step| step.expect("every row was pivoted on"))
+            .collect();
+        for row in &mut lower.rows {
+            *row = pivots[*row];
+        }
+
+        Ok(Self {
+            lower,
+            upper,
+            pivots,
+            ordering,
+            norm: matrix.one_norm(),
+        })
+    }
+
+    /// The number of unknowns.
+    pub fn size(&self) -> usize {
+        self.pivots.len()
+    }
+
+    /// The order in which the columns were eliminated.
+    pub fn ordering(&self) -> &[usize] {
+        &self.ordering
+    }
+
+    /// The number of stored entries of `L` and `U`,
+    /// which grows with the fill-in of the factored matrix.
+    pub fn entry_count(&self) -> usize {
+        self.lower.rows.len() + self.upper.rows.len()
+    }
+
+    /// Solves `A * x = rhs` for `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` does not have one entry per unknown.
+    pub fn solve(&self, rhs: &DVector<T>) -> DVector<T> {
+        let mut x = rhs.clone();
+        self.solve_mut(&mut x);
+        x
+    }
+
+    /// Solves `A * x = rhs` for `x` in place of `rhs`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` does not have one entry per unknown.
+    pub fn solve_mut(&self, rhs: &mut DVector<T>) {
+        let size = self.size();
+        assert_eq!(rhs.len(), size, "one entry is needed per unknown");
+
+        let mut x = vec![T::zero(); size];
+        for (row, &step) in self.pivots.iter().enumerate() {
+            x[step] = rhs[row].clone();
+        }
+
+        for step in 0..size {
+            let value = x[step].clone();
+            for (&row, l) in self.lower.below_diagonal(step) {
+                x[row] -= l.clone() * value.clone();
+            }
+        }
+        for step in (0..size).rev() {
+            let (rows, values) = self.upper.column(step);
+            let (diagonal, values) = values.split_last().expect("U has a diagonal");
+            x[step] /= diagonal.clone();
+
+            let value = x[step].clone();
+            for (&row, u) in rows.iter().zip(values) {
+                x[row] -= u.clone() * value.clone();
+            }
+        }
+
+        for (step, &column) in self.ordering.iter().enumerate() {
+            rhs[column] = x[step].clone();
+        }
+    }
+
+    /// Solves `Aᴴ * x = rhs` for `x`, where `Aᴴ` is the conjugate transpose.
+    fn adjoint_solve(&self, rhs: &DVector<T>) -> DVector<T> {
+        // Aᴴ = Q * Uᴴ * Lᴴ * P
+        let size = self.size();
+        let mut x: Vec<T> = self
+            .ordering
+            .iter()
+            .map(|&column| rhs[column].clone())
+            .collect();
+
+        for step in 0..size {
+            let (rows, values) = self.upper.column(step);
+            let (diagonal, values) = values.split_last().expect("U has a diagonal");
+
+            let mut value = x[step].clone();
+            for (&row, u) in rows.iter().zip(values) {
+                value -= u.clone().conjugate() * x[row].clone();
+            }
+            x[step] = value / diagonal.clone().conjugate();
+        }
+        for step in (0..size).rev() {
+            let mut value = x[step].clone();
+            for (&row, l) in self.lower.below_diagonal(step) {
+                value -= l.clone().conjugate() * x[row].clone();
+            }
+            x[step] = value;
+        }
+
+        DVector::from_iterator(size, self.pivots.iter().map(|&step| x[step].clone()))
+    }
+
+    /// An estimate of the 1-norm condition number `‖A‖ * ‖A⁻¹‖`.
+    ///
+    /// Roughly, `log10` of the condition number is the number of significant digits
+    /// the solution loses to rounding. It is estimated with Hager's method,
+    /// which takes a handful of solves instead of forming the inverse.
+    pub fn condition_number(&self) -> T::RealField {
+        let size = self.size();
+        if size == 0 {
+            return one::<T::RealField>();
+        }
+
+        let mut x = DVector::from_element(
+            size,
+            T::from_real(one::<T::RealField>() / convert(size as f64)),
+        );
+        let mut inverse_norm = zero::<T::RealField>();
+        for _ in 0..5 {
+            let y = self.solve(&x);
+            inverse_norm = y.lp_norm(1);
+
+            let sign = y.map(|value| match value.clone().modulus() == zero() {
+                true => T::one(),
+                false => value.clone().unscale(value.modulus()),
+            });
+            let z = self.adjoint_solve(&sign);
+
+            let (index, largest) = z.iter().enumerate().fold(
+                (0, zero::<T::RealField>()),
+                |(index, largest), (i, value)| match value.clone().modulus() > largest {
+                    true => (i, value.clone().modulus()),
+                    false => (index, largest),
+                },
+            );
+            if largest <= z.dotc(&x).real() {
+                break;
+            }
+
+            x = DVector::zeros(size);
+            x[index] = T::one();
+        }
+
+        self.norm.clone() * inverse_norm
+    }
+}
+
+/// A triangular factor, stored column by column, with its rows in pivot order.
+#[derive(Debug, Clone)]
+struct Triangle<T> {
+    column_starts: Vec<usize>,
+    rows: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T: ComplexField> Triangle<T> {
+    fn new(size: usize) -> Self {
+        let mut column_starts = Vec::with_capacity(size + 1);
+        column_starts.push(0);
+        Self {
+            column_starts,
+            rows: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, row: usize, value: T) {
+        self.rows.push(row);
+        self.values.push(value);
+    }
+
+    fn end_column(&mut self) {
+        self.column_starts.push(self.rows.len());
+    }
+
+    fn column(&self, column: usize) -> (&[usize], &[T]) {
+        let range = self.column_starts[column]..self.column_starts[column + 1];
+        (&self.rows[range.clone()], &self.values[range])
+    }
+
+    /// The entries of a column of L, skipping its unit diagonal.
+    fn below_diagonal(&self, column: usize) -> impl Iterator<Item = (&usize, &T)> {
+        let (rows, values) = self.column(column);
+        rows[1..].iter().zip(&values[1..])
+    }
+
+    /// Pushes every row reachable from `start` through the columns of L onto `reach`,
+    /// after the rows it reaches, while L is still being factored.
+    fn depth_first(
+        &self,
+        start: usize,
+        pivots: &[Option<usize>],
+        marked: &mut [bool],
+        stack: &mut Vec<(usize, Range<usize>)>,
+        reach: &mut Vec<usize>,
+    ) {
+        let children = |row: usize| match pivots[row] {
+            // The first entry of a column of L is its unit diagonal
+            Some(step) => self.column_starts[step] + 1..self.column_starts[step + 1],
+            None => 0..0,
+        };
+
+        marked[start] = true;
+        stack.push((start, children(start)));
+        while let Some((row, remaining)) = stack.last_mut() {
+            match remaining.next() {
+                Some(entry) => {
+                    let next = self.rows[entry];
+                    if !marked[next] {
+                        marked[next] = true;
+                        stack.push((next, children(next)));
+                    }
+                }
+                None => {
+                    reach.push(*row);
+                    stack.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::{Complex, ComplexField, DMatrix, DVector};
+
+    use crate::sparse::{CscMatrix, TripletMatrix};
+
+    use super::{Factorization, SingularMatrix};
+
+    /// Solving needs row swaps when the first pivot is zero.
+    #[test]
+    fn pivoting() {
+        let matrix = DMatrix::from_row_slice(3, 3, &[0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 0.0]);
+        let lu = Factorization::new(&CscMatrix::from(&matrix)).unwrap();
+
+        let x = lu.solve(&DVector::from_vec(vec![2.0, 2.0, 2.0]));
+
+        assert_relative_eq!(x, DVector::from_vec(vec![1.0, 1.0, 1.0]), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn complex() {
+        let j = Complex::new(0.0, 1.0);
+        let one = Complex::new(1.0, 0.0);
+        let matrix = DMatrix::from_row_slice(2, 2, &[one, j, j, one]);
+        let lu = Factorization::new(&CscMatrix::from(&matrix)).unwrap();
+
+        let x = lu.solve(&DVector::from_vec(vec![one + j, one + j]));
+
+        assert_relative_eq!((x[0] - one).modulus(), 0.0, epsilon = 1e-12);
+        assert_relative_eq!((x[1] - one).modulus(), 0.0, epsilon = 1e-12);
+    }
+
+    /// The estimate is exact for diagonal matrices.
+    #[test]
+    fn condition_number() {
+        let matrix = DMatrix::from_diagonal(&DVector::from_vec(vec![1e-3, 1.0, 1e6]));
+        let lu = Factorization::new(&CscMatrix::from(&matrix)).unwrap();
+
+        assert_relative_eq!(lu.condition_number(), 1e9, max_relative = 1e-9);
+    }
+
+    /// An ill-conditioned matrix has a condition number close to 1 / the distance to singular.
+    #[test]
+    fn ill_conditioned() {
+        let matrix = DMatrix::from_row_slice(2, 2, &[1.0, 1.0, 1.0, 1.0 + 1e-10]);
+        let lu = Factorization::new(&CscMatrix::from(&matrix)).unwrap();
+
+        assert!(lu.condition_number() > 1e10);
+    }
+
+    #[test]
+    fn singular_matrix_error() {
+        let matrix = DMatrix::from_row_slice(3, 3, &[1.0, 2.0, 0.0, 2.0, 4.0, 0.0, 0.0, 0.0, 1.0]);
+
+        assert_eq!(
+            Factorization::new(&CscMatrix::from(&matrix)).err(),
+            Some(SingularMatrix { unknown: 1 })
+        );
+    }
+
+    /// An arrow matrix factors without any fill-in when its dense row and column are eliminated late.
+    #[test]
+    fn fill_reducing_ordering() {
+        let mut triplets = TripletMatrix::new(100);
+        for unknown in 0..100 {
+            triplets.add(unknown, unknown, 4.0);
+            triplets.add(0, unknown, 1.0);
+            triplets.add(unknown, 0, 1.0);
+        }
+        let matrix = triplets.to_csc();
+
+        let lu = Factorization::new(&matrix).unwrap();
+        let natural = Factorization::with_ordering(&matrix, (0..100).collect()).unwrap();
+
+        assert_eq!(lu.entry_count(), matrix.entry_count() + 100);
+        assert_eq!(natural.entry_count(), 100 * 100 + 100);
+
+        let x = DVector::from_element(100, 1.0);
+        let mut rhs = DVector::from_element(100, 5.0);
+        rhs[0] = 105.0;
+        assert_relative_eq!(lu.solve(&rhs), x, epsilon = 1e-12);
+        assert_relative_eq!(natural.solve(&rhs), x, epsilon = 1e-12);
+    }
+}
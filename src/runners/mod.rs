@@ -1,12 +1,103 @@
+use std::sync::Arc;
+
+use nalgebra::{ComplexField, DVector};
 use thiserror::Error;
 
+use crate::{
+    elements::ParameterError,
+    sparse::{minimum_degree, CscMatrix},
+    Circuit, NodeId,
+};
+
+use self::{lu::Factorization, operating_point::OperatingPoint};
+
+pub mod ac;
 pub mod dc_op;
+pub mod dc_sweep;
+pub mod integration;
+pub mod lu;
+mod newton;
+pub mod operating_point;
+pub mod options;
+pub mod step;
+pub mod sweep;
 pub mod transient;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum RunnerError {
     #[error("at least one node is required to execute a runner")]
     ZeroNode,
-    #[error("the circuit is malformed and cannot inverse the matrix")]
-    MalformedCircuit,
+    #[error("the circuit cannot be solved for {0}, check for floating nodes and loops of voltage sources")]
+    SingularMatrix(String),
+    #[error("the time step must be positive and no larger than the stop time, the start time must be before the stop time, and the maximum step must be positive")]
+    InvalidTimeStep,
+    #[error("the time step became too small at {time}s, check for abrupt changes the circuit cannot follow")]
+    TimeStepTooSmall { time: f64 },
+    #[error("the circuit did not converge within {iterations} iterations, check {}", unknowns.join(", "))]
+    NoConvergence {
+        iterations: usize,
+        /// The labels of the unknowns that failed to settle, such as `v(out)`.
+        unknowns: Vec<String>,
+    },
+    #[error("the sweep must have at least one point and finite bounds, and logarithmic sweeps must start above zero and must not end before they start")]
+    InvalidSweep,
+    #[error("element {0} is not a DC voltage or current source")]
+    NotASource(usize),
+    #[error("node {0} is not part of the circuit")]
+    UnknownNode(usize),
+    #[error("element {element}: {error}")]
+    Parameter {
+        element: usize,
+        error: ParameterError,
+    },
+}
+
+/// The label of every node of the `circuit`, including ground, for the points of a runner.
+pub(crate) fn node_labels(circuit: &Circuit) -> Arc<[String]> {
+    (0..circuit.node_count())
+        .map(|node| circuit.node_label(NodeId(node)))
+        .collect()
+}
+
+/// A solution of the `circuit` with the node `voltages` and zero everywhere else,
+/// for a runner to start from.
+pub(crate) fn starting_point<'a>(
+    circuit: &Circuit,
+    voltages: impl IntoIterator<Item = (&'a NodeId, &'a f64)>,
+) -> Result<OperatingPoint, RunnerError> {
+    let mut solution = DVector::zeros(circuit.node_count() - 1 + circuit.branch_count());
+    for (node, &voltage) in voltages {
+        if node.0 >= circuit.node_count() {
+            return Err(RunnerError::UnknownNode(node.0));
+        }
+        if node.0 > 0 {
+            solution[node.0 - 1] = voltage;
+        }
+    }
+
+    Ok(OperatingPoint::new(node_labels(circuit), solution))
+}
+
+/// The label of an unknown of the solution vector, such as `v(out)` for a node voltage
+/// and `i(0)` for a branch current, given the `labels` of every node.
+pub(crate) fn unknown_label(labels: &[String], unknown: usize) -> String {
+    match labels.get(unknown + 1) {
+        Some(label) => format!("v({label})"),
+        None => format!("i({})", unknown + 1 - labels.len()),
+    }
+}
+
+/// Factors `a_matrix`, using the node `labels` to report which unknown makes it singular.
+///
+/// The columns are eliminated in `ordering`, such as that of a previous factorization
+/// of a matrix with the same pattern, or in minimum degree order when it is `None`.
+pub(crate) fn factor<T: ComplexField>(
+    a_matrix: &CscMatrix<T>,
+    ordering: Option<&[usize]>,
+    labels: &[String],
+) -> Result<Factorization<T>, RunnerError> {
+    let ordering = ordering.map_or_else(|| minimum_degree(a_matrix), <[usize]>::to_vec);
+
+    Factorization::with_ordering(a_matrix, ordering)
+        .map_err(|error| RunnerError::SingularMatrix(unknown_label(labels, error.unknown)))
 }
@@ -0,0 +1,300 @@
+use std::sync::Arc;
+
+use nalgebra::DVector;
+
+use crate::{
+    elements::{stamp::StampContext, Element},
+    sparse::CscMatrix,
+    Circuit, NodeId,
+};
+
+use super::{
+    factor, lu::Factorization, operating_point::OperatingPoint, unknown_label, RunnerError,
+};
+
+/// The conductance in Siemens that holds a node at a forced voltage, which is large enough
+/// to outweigh the circuit around it, as in SPICE.
+const FORCING_CONDUCTANCE: f64 = 1e10;
+
+/// Solves a circuit by Newton-Raphson iteration, which is a single linear solve
+/// when every element is linear.
+pub(crate) struct Newton<'a> {
+    circuit: &'a Circuit,
+    labels: Arc<[String]>,
+    nonlinear: bool,
+    /// The values every element keeps between iterations.
+    states: Vec<Vec<f64>>,
+    /// The last factored matrix, which is reused while it stays the same,
+    /// as it does at every time point of a linear circuit.
+    factored: Option<(CscMatrix<f64>, Factorization<f64>)>,
+    /// The time of the transient analysis being solved, if any.
+    time: Option<f64>,
+    /// The nodes held at a voltage, with the voltage.
+    forced: Vec<(NodeId, f64)>,
+}
+
+impl<'a> Newton<'a> {
+    pub(crate) fn new(circuit: &'a Circuit, labels: Arc<[String]>) -> Self {
+        Self {
+            circuit,
+            labels,
+            nonlinear: circuit
+                .elements()
+                .iter()
+                .any(|element| element.is_nonlinear()),
+            states: circuit
+                .elements()
+                .iter()
+                .map(|element| vec![0.0; element.state_len()])
+                .collect(),
+            factored: None,
+            time: None,
+            forced: Vec::new(),
+        }
+    }
+
+    /// Solves at `time` of a transient analysis from now on,
+    /// where independent sources take the value of their waveform.
+    pub(crate) fn set_time(&mut self, time: f64) {
+        self.time = Some(time);
+    }
+
+    /// Holds every node at its voltage through a conductance of [`FORCING_CONDUCTANCE`]
+    /// to a source of that voltage, as the initial conditions of a transient analysis do
+    /// to the operating point it starts from.
+    pub(crate) fn force(&mut self, voltages: impl IntoIterator<Item = (NodeId, f64)>) {
+        self.forced.extend(voltages);
+    }
+
+    /// Sets the state of every element from a `solution` of the circuit,
+    /// so that the next solve does not start from zero.
+    pub(crate) fn initialize(&mut self, solution: &OperatingPoint) {
+        for (element, state) in self.circuit.elements().iter().zip(&mut self.states) {
+            element.initialize_state(solution, state);
+        }
+    }
+
+    /// Iterates from the `initial` estimate until the solution settles, for at most
+    /// `max_iterations` but at least once, stamping every element at the latest estimate through `stamp`,
+    /// along with its index in the circuit.
+    ///
+    /// An iteration has settled when every unknown moved by no more than `RELTOL` of
+    /// its value plus `VNTOL` for voltages or `ABSTOL` for currents,
+    /// and no element had to limit its estimate.
+    pub(crate) fn solve<F>(
+        &mut self,
+        initial: OperatingPoint,
+        max_iterations: usize,
+        mut stamp: F,
+    ) -> Result<OperatingPoint, RunnerError>
+    where
+        F: FnMut(usize, &dyn Element, &mut StampContext<f64>, &OperatingPoint, &mut [f64]),
+    {
+        let circuit = self.circuit;
+        let (n, m) = (circuit.node_count(), circuit.branch_count());
+
+        let mut estimate = initial;
+        let mut unsettled = Vec::new();
+        let iterations = max_iterations.max(1);
+        for _ in 0..iterations {
+            let mut context = StampContext::new(n, m);
+            if let Some(time) = self.time {
+                context.set_time(time);
+            }
+            for (index, (element, state)) in
+                circuit.elements().iter().zip(&mut self.states).enumerate()
+            {
+                stamp(index, element.as_ref(), &mut context, &estimate, state);
+            }
+            for &(node, voltage) in &self.forced {
+                context.add_conductance(node, NodeId(0), FORCING_CONDUCTANCE);
+                context.add_current(node, NodeId(0), FORCING_CONDUCTANCE * voltage);
+            }
+            let limited = context.is_limited();
+            let (a_matrix, z_vector) = context.into_system();
+
+            let solution = self
+                .factor(a_matrix.to_csc())?
+                .solve(&DVector::from_vec(z_vector));
+            let solution = OperatingPoint::new(self.labels.clone(), solution);
+            if !self.nonlinear {
+                return Ok(solution);
+            }
+
+            unsettled = self.unsettled(&estimate, &solution);
+            estimate = solution;
+            if unsettled.is_empty() && !limited {
+                return Ok(estimate);
+            }
+        }
+
+        Err(RunnerError::NoConvergence {
+            iterations,
+            unknowns: unsettled
+                .into_iter()
+                .map(|unknown| unknown_label(&self.labels, unknown))
+                .collect(),
+        })
+    }
+
+    /// Factors `a_matrix`, unless it is the same as the last one.
+    fn factor(&mut self, a_matrix: CscMatrix<f64>) -> Result<&Factorization<f64>, RunnerError> {
+        if self
+            .factored
+            .as_ref()
+            .is_none_or(|(matrix, _)| *matrix != a_matrix)
+        {
+            // The pattern is the same at every iteration, so the column order is reused
+            let ordering = self.factored.as_ref().map(|(_, lu)| lu.ordering());
+            let lu = factor(&a_matrix, ordering, &self.labels)?;
+            self.factored = Some((a_matrix, lu));
+        }
+
+        Ok(&self
+            .factored
+            .as_ref()
+            .expect("the matrix was just factored")
+            .1)
+    }
+
+    /// The unknowns that moved by more than the tolerances from `previous` to `next`.
+    fn unsettled(&self, previous: &OperatingPoint, next: &OperatingPoint) -> Vec<usize> {
+        let options = &self.circuit.options;
+        let node_count = self.labels.len() - 1;
+
+        previous
+            .solution()
+            .iter()
+            .zip(next.solution().iter())
+            .enumerate()
+            .filter(|(unknown, (previous, next))| {
+                let absolute = match *unknown < node_count {
+                    true => options.vntol,
+                    false => options.abstol,
+                };
+                let tolerance = options.reltol * previous.abs().max(next.abs()) + absolute;
+                (*next - *previous).abs() > tolerance
+            })
+            .map(|(unknown, _)| unknown)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+
+    use crate::{
+        elements::{dc_current_source::DCCurrentSource, stamp::StampContext, Element, Terminal},
+        runners::{dc_op::dc_op, operating_point::OperatingPoint, RunnerError},
+        Circuit, NodeId,
+    };
+
+    /// A conductor to ground with a current of `v/R + k * v^2`.
+    #[derive(Debug, Clone)]
+    struct Quadratic {
+        terminals: [Terminal; 1],
+        resistance: f64,
+        k: f64,
+    }
+
+    impl Element for Quadratic {
+        fn terminals(&self) -> &[Terminal] {
+            &self.terminals
+        }
+
+        fn stamp(&self, _context: &mut StampContext<f64>) {}
+
+        fn is_nonlinear(&self) -> bool {
+            true
+        }
+
+        fn stamp_linearized(
+            &self,
+            context: &mut StampContext<f64>,
+            estimate: &OperatingPoint,
+            _state: &mut [f64],
+        ) {
+            let node = self.terminals[0].node;
+            let voltage = estimate.voltage(node);
+            let current = voltage / self.resistance + self.k * voltage * voltage;
+            let conductance = self.resistance.recip() + 2.0 * self.k * voltage;
+
+            context.add_conductance(node, NodeId(0), conductance);
+            context.add_current(NodeId(0), node, current - conductance * voltage);
+        }
+
+        fn dc_voltage(&self) -> f64 {
+            0.0
+        }
+
+        fn ac_voltage(&self) -> Complex<f64> {
+            Complex::ZERO
+        }
+
+        fn dc_current(&self) -> f64 {
+            0.0
+        }
+
+        fn ac_current(&self) -> Complex<f64> {
+            Complex::ZERO
+        }
+
+        fn resistance(&self) -> f64 {
+            self.resistance
+        }
+
+        fn impedance_at_omega(&self, _omega: f64) -> Complex<f64> {
+            Complex::new(self.resistance, 0.0)
+        }
+    }
+
+    /// 3A into `v/1Ω + v^2` settles at 1.3028V.
+    fn circuit() -> Circuit {
+        let mut circuit = Circuit::default();
+        let ground = circuit.node("0");
+        let out = circuit.node("out");
+        circuit.add_element(Box::new(DCCurrentSource::new(3.0, out, ground)));
+        circuit.add_element(Box::new(Quadratic {
+            terminals: [Terminal::new_positive(out)],
+            resistance: 1.0,
+            k: 1.0,
+        }));
+
+        circuit
+    }
+
+    #[test]
+    fn converges() {
+        let circuit = circuit();
+
+        let op = dc_op(&circuit).unwrap();
+
+        let expected = (13.0f64.sqrt() - 1.0) / 2.0;
+        assert_relative_eq!(op.voltage_of("out").unwrap(), expected, max_relative = 1e-6);
+    }
+
+    #[test]
+    fn no_convergence_error() {
+        let mut circuit = circuit();
+        circuit.options.dc_iterations = 2;
+
+        assert_eq!(
+            dc_op(&circuit),
+            Err(RunnerError::NoConvergence {
+                iterations: 2,
+                unknowns: vec!["v(out)".to_string()],
+            })
+        );
+        // A limit of no iterations still runs one
+        circuit.options.dc_iterations = 0;
+        assert_eq!(
+            dc_op(&circuit),
+            Err(RunnerError::NoConvergence {
+                iterations: 1,
+                unknowns: vec!["v(out)".to_string()],
+            })
+        );
+    }
+}
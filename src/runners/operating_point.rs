@@ -0,0 +1,124 @@
+use std::{fmt, sync::Arc};
+
+use nalgebra::{ComplexField, DVector};
+
+use crate::{units, BranchId, ElementId, NodeId};
+
+use super::unknown_label;
+
+/// The solved node voltages and branch currents of a circuit.
+///
+/// `T` is `f64` for the DC and transient runners, and a complex phasor for the AC runner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatingPoint<T = f64> {
+    /// The label of every node, including ground, shared between the points of a runner.
+    labels: Arc<[String]>,
+    solution: DVector<T>,
+}
+
+impl<T: ComplexField> OperatingPoint<T> {
+    /// Wraps a `solution` vector of the node voltages, excluding ground,
+    /// followed by the branch currents.
+    pub(crate) fn new(labels: Arc<[String]>, solution: DVector<T>) -> Self {
+        Self { labels, solution }
+    }
+
+    /// The voltage of `node` relative to ground, where ground itself is always 0V.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node` is not part of the solved circuit.
+    pub fn voltage(&self, node: NodeId) -> T {
+        match node.0 {
+            0 => T::zero(),
+            node => {
+                assert!(
+                    node < self.labels.len(),
+                    "node {node} is not in the circuit"
+                );
+                self.solution[node - 1].clone()
+            }
+        }
+    }
+
+    /// The voltage of the node labelled `name`, or `None` if there is no such node.
+    pub fn voltage_of(&self, name: &str) -> Option<T> {
+        let node = self.labels.iter().position(|label| label == name)?;
+        Some(self.voltage(NodeId(node)))
+    }
+
+    /// The voltage of `positive` relative to `negative`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either node is not part of the solved circuit.
+    pub fn voltage_between(&self, positive: NodeId, negative: NodeId) -> T {
+        self.voltage(positive) - self.voltage(negative)
+    }
+
+    /// The current through the branch of `element`, flowing from its positive to its
+    /// negative terminal, or `None` if the element has no branch current.
+    pub fn branch_current(&self, element: ElementId) -> Option<T> {
+        element.branch().and_then(|branch| self.branch(branch))
+    }
+
+    /// The current of `branch`, or `None` if it is not part of the solved circuit.
+    pub fn branch(&self, branch: BranchId) -> Option<T> {
+        self.solution.get(self.labels.len() - 1 + branch.0).cloned()
+    }
+
+    /// The raw solution vector: the node voltages, excluding ground,
+    /// followed by the branch currents.
+    pub fn solution(&self) -> &DVector<T> {
+        &self.solution
+    }
+
+    /// The label of every node, including ground, as given by
+    /// [`Circuit::node_label`](crate::Circuit::node_label).
+    pub fn node_labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// The number of branch currents.
+    pub fn branch_count(&self) -> usize {
+        self.solution.len() + 1 - self.labels.len()
+    }
+
+    /// The column names of every unknown in [`OperatingPoint::solution`],
+    /// such as `v(out)` for a node voltage and `i(0)` for a branch current.
+    pub(crate) fn unknown_labels(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.solution.len()).map(|unknown| unknown_label(&self.labels, unknown))
+    }
+}
+
+/// Lists every node voltage and branch current, one per line.
+///
+/// ```
+/// use spice_rs::{
+///     elements::{dc_voltage_source::DCVoltageSource, resistor::Resistor},
+///     runners::dc_op::dc_op,
+///     Circuit,
+/// };
+///
+/// let mut circuit = Circuit::default();
+/// let vin = circuit.node("in");
+/// let ground = circuit.node("0");
+/// circuit.add_element(Box::new(DCVoltageSource::new(5.0, vin, ground)));
+/// circuit.add_element(Box::new(Resistor::new(2e3, vin, ground)));
+///
+/// let op = dc_op(&circuit).unwrap();
+/// assert_eq!(op.to_string(), "v(in) = 5 V\ni(0) = -2.5 mA\n");
+/// ```
+impl fmt::Display for OperatingPoint<f64> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let units = (1..self.labels.len())
+            .map(|_| "V")
+            .chain((0..self.branch_count()).map(|_| "A"));
+
+        for ((label, value), unit) in self.unknown_labels().zip(self.solution.iter()).zip(units) {
+            writeln!(f, "{label} = {}", units::format(*value, unit))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use super::integration::Method;
+
+/// The tolerances and iteration limits of the runners, as set by a SPICE `.options` line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    /// The relative tolerance of every voltage and current, `RELTOL`.
+    pub reltol: f64,
+    /// The absolute tolerance of node voltages in Volts, `VNTOL`.
+    pub vntol: f64,
+    /// The absolute tolerance of branch currents in Amperes, `ABSTOL`.
+    pub abstol: f64,
+    /// The absolute tolerance of charges in Coulombs, `CHGTOL`.
+    pub chgtol: f64,
+    /// How much the local truncation error of a transient time step may exceed
+    /// the tolerances before the step is shortened, `TRTOL`.
+    pub trtol: f64,
+    /// How a transient analysis integrates charges and fluxes, `METHOD`.
+    pub method: Method,
+    /// The highest order of [`Method::Gear`] integration, from 1 to 6, `MAXORD`.
+    pub max_order: usize,
+    /// The most Newton-Raphson iterations of a DC operating point, `ITL1`.
+    pub dc_iterations: usize,
+    /// The most Newton-Raphson iterations of a single time point, `ITL4`.
+    pub transient_iterations: usize,
+}
+
+impl Default for Options {
+    /// The defaults of SPICE.
+    fn default() -> Self {
+        Self {
+            reltol: 1e-3,
+            vntol: 1e-6,
+            abstol: 1e-12,
+            chgtol: 1e-14,
+            trtol: 7.0,
+            method: Method::default(),
+            max_order: 2,
+            dc_iterations: 100,
+            transient_iterations: 10,
+        }
+    }
+}
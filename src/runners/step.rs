@@ -0,0 +1,259 @@
+use crate::{Circuit, ElementId};
+
+use super::{sweep::Sweep, RunnerError};
+
+/// What a parametric sweep changes at every step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stepped {
+    /// The parameter called `name` of the `element`, such as `r` of a resistor,
+    /// as in [`Element::set_parameter`](crate::elements::Element::set_parameter).
+    Parameter { element: ElementId, name: String },
+    /// The temperature in °C of every element that has one,
+    /// as in [`Circuit::set_temperature`].
+    Temperature,
+}
+
+/// A parameter and the values to step it through, as in a SPICE `.step` line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterStep {
+    pub stepped: Stepped,
+    pub sweep: Sweep,
+}
+
+/// The result of an analysis at every value of a stepped parameter, which is a family
+/// of operating points or curves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResult<R> {
+    /// The value of the stepped parameter at every step.
+    pub values: Vec<f64>,
+    pub results: Vec<R>,
+}
+
+impl<R> StepResult<R> {
+    /// The number of steps.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Every value of the stepped parameter paired with the result at that value.
+    pub fn iter(&self) -> impl Iterator<Item = (f64, &R)> {
+        self.values.iter().copied().zip(self.results.iter())
+    }
+}
+
+/// Parametric sweep, which repeats an `analysis` such as [`dc_op`](super::dc_op::dc_op),
+/// [`ac`](super::ac::ac) or [`transient`](super::transient::transient) at every value
+/// of a parameter.
+///
+/// Every step runs on its own copy of the circuit with the parameter set, so the circuit
+/// itself stays as it was.
+///
+/// ```
+/// use spice_rs::{
+///     elements::{dc_voltage_source::DCVoltageSource, resistor::Resistor},
+///     runners::{
+///         dc_op::dc_op,
+///         step::{step, ParameterStep, Stepped},
+///         sweep::Sweep,
+///     },
+///     Circuit,
+/// };
+///
+/// let mut circuit = Circuit::default();
+/// let ground = circuit.push_node();
+/// let vin = circuit.push_node();
+/// let vout = circuit.push_node();
+/// circuit.add_element(Box::new(DCVoltageSource::new(1.0, vin, ground)));
+/// circuit.add_element(Box::new(Resistor::new(1e3, vin, vout)));
+/// let bottom = circuit.add_element(Box::new(Resistor::new(1e3, vout, ground)));
+///
+/// let parameter = ParameterStep {
+///     stepped: Stepped::Parameter { element: bottom, name: "r".to_string() },
+///     sweep: Sweep::List(vec![1e3, 3e3]),
+/// };
+/// let result = step(&circuit, &parameter, dc_op).unwrap();
+///
+/// assert_eq!(result.results[0].voltage(vout), 0.5);
+/// assert_eq!(result.results[1].voltage(vout), 0.75);
+/// ```
+pub fn step<R>(
+    circuit: &Circuit,
+    step: &ParameterStep,
+    mut analysis: impl FnMut(&Circuit) -> Result<R, RunnerError>,
+) -> Result<StepResult<R>, RunnerError> {
+    let values = step.sweep.values()?;
+
+    let mut result = StepResult {
+        values: Vec::new(),
+        results: Vec::new(),
+    };
+    for value in values {
+        let mut circuit = circuit.clone();
+        match &step.stepped {
+            Stepped::Parameter { element, name } => circuit
+                .set_parameter(*element, name, value)
+                .map_err(|error| RunnerError::Parameter {
+                    element: element.index(),
+                    error,
+                })?,
+            Stepped::Temperature => circuit.set_temperature(value),
+        }
+
+        result.results.push(analysis(&circuit)?);
+        result.values.push(value);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::ComplexField;
+
+    use crate::{
+        elements::{
+            capacitor::Capacitor, dc_current_source::DCCurrentSource,
+            dc_voltage_source::DCVoltageSource, diode::Diode, resistor::Resistor,
+            waveform::Waveform, ParameterError,
+        },
+        runners::{ac::ac, dc_op::dc_op, sweep::Sweep, transient::transient, RunnerError},
+        Circuit,
+    };
+
+    use super::{step, ParameterStep, Stepped};
+
+    /// The corner frequency of a low-pass filter falls as its capacitance grows,
+    /// which is a family of AC curves.
+    #[test]
+    fn capacitance_across_ac() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vin = circuit.push_node();
+        let vout = circuit.push_node();
+        circuit.add_element(Box::new(
+            DCVoltageSource::new(0.0, vin, ground).with_ac(1.0, 0.0),
+        ));
+        circuit.add_element(Box::new(Resistor::new(1e3, vin, vout)));
+        let capacitor = circuit.add_element(Box::new(Capacitor::new(1e-6, vout, ground)));
+
+        let parameter = ParameterStep {
+            stepped: Stepped::Parameter {
+                element: capacitor,
+                name: "c".to_string(),
+            },
+            sweep: Sweep::Decade {
+                start: 1e-7,
+                stop: 1e-5,
+                points: 1,
+            },
+        };
+        let frequency = 1.0 / (std::f64::consts::TAU * 1e-3);
+        let result = step(&circuit, &parameter, |circuit| {
+            ac(circuit, Sweep::List(vec![frequency]))
+        })
+        .unwrap();
+
+        assert_eq!(result.len(), 3);
+        // The middle step puts the corner at the analyzed frequency
+        let gains: Vec<f64> = result
+            .iter()
+            .map(|(_, ac)| ac.points[0].voltage(vout).modulus())
+            .collect();
+        assert_relative_eq!(gains[1], 0.5f64.sqrt(), epsilon = 1e-9);
+        assert!(gains[0] > gains[1] && gains[1] > gains[2]);
+        assert_eq!(circuit.parameter(capacitor, "c"), Some(1e-6));
+    }
+
+    /// A larger resistor charges the capacitor more slowly after a step.
+    #[test]
+    fn resistance_across_transient() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let vin = circuit.push_node();
+        let vout = circuit.push_node();
+        let edge = Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 1.0,
+            delay: 0.0,
+            rise: 1e-7,
+            fall: 1e-7,
+            width: 1.0,
+            period: f64::INFINITY,
+        };
+        circuit.add_element(Box::new(
+            DCVoltageSource::new(0.0, vin, ground).with_waveform(edge),
+        ));
+        let resistor = circuit.add_element(Box::new(Resistor::new(1e3, vin, vout)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, vout, ground)));
+
+        let parameter = ParameterStep {
+            stepped: Stepped::Parameter {
+                element: resistor,
+                name: "r".to_string(),
+            },
+            sweep: Sweep::List(vec![1e2, 1e3]),
+        };
+        let result = step(&circuit, &parameter, |circuit| {
+            transient(circuit, 1e-5, 1e-3)
+        })
+        .unwrap();
+        let fast = result.results[0].points.last().unwrap().voltage(vout);
+        let slow = result.results[1].points.last().unwrap().voltage(vout);
+        assert!(fast > 0.99);
+        assert_relative_eq!(slow, 1.0 - (-1.0f64).exp(), epsilon = 0.01);
+    }
+
+    /// The forward voltage of a diode at a constant current falls by about 2mV/°C.
+    #[test]
+    fn temperature() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let anode = circuit.push_node();
+        circuit.add_element(Box::new(DCCurrentSource::new(1e-3, anode, ground)));
+        circuit.add_element(Box::new(Diode::new(anode, ground)));
+
+        let parameter = ParameterStep {
+            stepped: Stepped::Temperature,
+            sweep: Sweep::Linear {
+                start: 0.0,
+                stop: 100.0,
+                points: 3,
+            },
+        };
+        let result = step(&circuit, &parameter, dc_op).unwrap();
+
+        assert_eq!(result.values, vec![0.0, 50.0, 100.0]);
+        let voltages: Vec<f64> = result.results.iter().map(|op| op.voltage(anode)).collect();
+        let coefficient = (voltages[2] - voltages[0]) / 100.0;
+        assert!(-2.5e-3 < coefficient && coefficient < -1.5e-3);
+    }
+
+    #[test]
+    fn parameter_error() {
+        let mut circuit = Circuit::default();
+        let ground = circuit.push_node();
+        let v1 = circuit.push_node();
+        let resistor = circuit.add_element(Box::new(Resistor::new(1.0, v1, ground)));
+
+        let parameter = ParameterStep {
+            stepped: Stepped::Parameter {
+                element: resistor,
+                name: "c".to_string(),
+            },
+            sweep: Sweep::List(vec![1.0]),
+        };
+
+        assert_eq!(
+            step(&circuit, &parameter, dc_op),
+            Err(RunnerError::Parameter {
+                element: 0,
+                error: ParameterError::Unknown("c".to_string()),
+            })
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use super::RunnerError;
+
+/// The points a runner steps through, such as the frequencies of an AC analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sweep {
+    /// `points` values evenly spaced from `start` to `stop`, which may be below `start`.
+    Linear {
+        start: f64,
+        stop: f64,
+        points: usize,
+    },
+    /// `points` values per decade from `start` to `stop`.
+    Decade {
+        start: f64,
+        stop: f64,
+        points: usize,
+    },
+    /// `points` values per octave from `start` to `stop`.
+    Octave {
+        start: f64,
+        stop: f64,
+        points: usize,
+    },
+    /// Each of the values, in the order given.
+    List(Vec<f64>),
+}
+
+impl Sweep {
+    /// Every value of the sweep, in order.
+    ///
+    /// ```
+    /// use spice_rs::runners::sweep::Sweep;
+    ///
+    /// let sweep = Sweep::Decade { start: 1.0, stop: 100.0, points: 1 };
+    /// assert_eq!(sweep.values().unwrap(), vec![1.0, 10.0, 100.0]);
+    /// ```
+    pub fn values(&self) -> Result<Vec<f64>, RunnerError> {
+        match *self {
+            Self::List(ref values) if values.is_empty() => Err(RunnerError::InvalidSweep),
+            Self::List(ref values) => Ok(values.clone()),
+            Self::Linear {
+                start,
+                stop,
+                points,
+            } => {
+                if points == 0 || !(start.is_finite() && stop.is_finite()) {
+                    return Err(RunnerError::InvalidSweep);
+                }
+                if points == 1 {
+                    return Ok(vec![start]);
+                }
+
+                let step = (stop - start) / (points - 1) as f64;
+                Ok((0..points).map(|i| start + step * i as f64).collect())
+            }
+            Self::Decade {
+                start,
+                stop,
+                points,
+            } => logarithmic(start, stop, points, 10.0),
+            Self::Octave {
+                start,
+                stop,
+                points,
+            } => logarithmic(start, stop, points, 2.0),
+        }
+    }
+}
+
+/// `points` values per multiple of `base` from `start` up to and including `stop`.
+fn logarithmic(start: f64, stop: f64, points: usize, base: f64) -> Result<Vec<f64>, RunnerError> {
+    if points == 0 || !(start.is_finite() && stop.is_finite()) || start <= 0.0 || stop < start {
+        return Err(RunnerError::InvalidSweep);
+    }
+
+    let ratio = base.powf((points as f64).recip());
+    // Allow for rounding so that `stop` itself is part of the sweep
+    let limit = stop * (1.0 + 1e-4);
+
+    let mut values = Vec::new();
+    let mut i = 0;
+    loop {
+        let value = start * ratio.powi(i);
+        if value > limit {
+            break;
+        }
+        values.push(value);
+        i += 1;
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use crate::runners::RunnerError;
+
+    use super::Sweep;
+
+    #[test]
+    fn linear() {
+        let sweep = Sweep::Linear {
+            start: 1.0,
+            stop: 2.0,
+            points: 5,
+        };
+
+        assert_eq!(sweep.values().unwrap(), vec![1.0, 1.25, 1.5, 1.75, 2.0]);
+        let descending = Sweep::Linear {
+            start: 2.0,
+            stop: 1.0,
+            points: 3,
+        };
+        assert_eq!(descending.values().unwrap(), vec![2.0, 1.5, 1.0]);
+    }
+
+    #[test]
+    fn octave() {
+        let sweep = Sweep::Octave {
+            start: 100.0,
+            stop: 400.0,
+            points: 2,
+        };
+        let values = sweep.values().unwrap();
+
+        assert_eq!(values.len(), 5);
+        assert_relative_eq!(values[1], 141.42, epsilon = 0.01);
+        assert_relative_eq!(values[4], 400.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn list() {
+        let sweep = Sweep::List(vec![3.0, 1.0, 2.0]);
+
+        assert_eq!(sweep.values().unwrap(), vec![3.0, 1.0, 2.0]);
+        assert_eq!(
+            Sweep::List(Vec::new()).values(),
+            Err(RunnerError::InvalidSweep)
+        );
+    }
+
+    #[test]
+    fn invalid_sweep_error() {
+        let sweep = Sweep::Decade {
+            start: 0.0,
+            stop: 1e3,
+            points: 10,
+        };
+
+        assert_eq!(sweep.values(), Err(RunnerError::InvalidSweep));
+    }
+
+    /// A sweep to an infinite or undefined value, such as `1e400`, would never end.
+    #[test]
+    fn non_finite_sweep_error() {
+        for stop in [f64::INFINITY, f64::NAN] {
+            let decade = Sweep::Decade {
+                start: 1.0,
+                stop,
+                points: 10,
+            };
+            let linear = Sweep::Linear {
+                start: 0.0,
+                stop,
+                points: 10,
+            };
+
+            assert_eq!(decade.values(), Err(RunnerError::InvalidSweep));
+            assert_eq!(linear.values(), Err(RunnerError::InvalidSweep));
+        }
+    }
+}
@@ -1 +1,795 @@
+use std::ops::Range;
 
+use crate::Circuit;
+
+use super::{
+    dc_op::initial_op,
+    integration::{Integration, Integrator},
+    newton::Newton,
+    node_labels,
+    operating_point::OperatingPoint,
+    starting_point, RunnerError,
+};
+
+/// The times of a transient analysis, as on a SPICE `.tran tstep tstop [tstart [tmax]]` line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeSpan {
+    /// The time step in seconds, whose every multiple is a time point.
+    pub tstep: f64,
+    /// The time the analysis stops at.
+    pub tstop: f64,
+    /// The time the result starts at. The circuit is still solved from `t = 0`,
+    /// but the time points before it are left out.
+    pub tstart: f64,
+    /// The longest step between time points, which is `tstep` if it is not given.
+    pub tmax: Option<f64>,
+}
+
+impl TimeSpan {
+    /// From `t = 0` to `tstop`, with steps of at most `tstep`.
+    pub fn new(tstep: f64, tstop: f64) -> Self {
+        Self {
+            tstep,
+            tstop,
+            tstart: 0.0,
+            tmax: None,
+        }
+    }
+
+    /// Leaves out the time points before `tstart`.
+    #[must_use]
+    pub fn with_start(mut self, tstart: f64) -> Self {
+        self.tstart = tstart;
+        self
+    }
+
+    /// Caps every step at `tmax`.
+    #[must_use]
+    pub fn with_max_step(mut self, tmax: f64) -> Self {
+        self.tmax = Some(tmax);
+        self
+    }
+
+    /// The longest step between time points.
+    fn max_step(&self) -> f64 {
+        self.tmax.map_or(self.tstep, |tmax| tmax.min(self.tstep))
+    }
+
+    fn validate(&self) -> Result<(), RunnerError> {
+        let valid = self.tstep > 0.0
+            && self.tstep <= self.tstop
+            && (0.0..self.tstop).contains(&self.tstart)
+            && self.tmax.is_none_or(|tmax| tmax > 0.0);
+        match valid {
+            true => Ok(()),
+            false => Err(RunnerError::InvalidTimeStep),
+        }
+    }
+}
+
+/// The solution of a circuit at every time point of a transient analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransientResult {
+    pub times: Vec<f64>,
+    pub points: Vec<OperatingPoint>,
+}
+
+impl TransientResult {
+    /// The number of time points.
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// The solution at the time point closest to `time`, such as a multiple of the time step
+    /// or a breakpoint, which are always time points.
+    pub fn at(&self, time: f64) -> Option<&OperatingPoint> {
+        let index = self
+            .times
+            .iter()
+            .map(|t| (t - time).abs())
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?
+            .0;
+
+        self.points.get(index)
+    }
+
+    /// Every time point paired with the solution at that time.
+    pub fn iter(&self) -> impl Iterator<Item = (f64, &OperatingPoint)> {
+        self.times.iter().copied().zip(self.points.iter())
+    }
+
+    /// Exports the result as CSV, with a `time` column followed by a column for every
+    /// node voltage, such as `v(out)`, and every branch current, such as `i(0)`.
+    pub fn to_csv(&self) -> String {
+        let Some(first) = self.points.first() else {
+            return "time\n".to_string();
+        };
+
+        let mut csv = std::iter::once("time".to_string())
+            .chain(first.unknown_labels())
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push('\n');
+
+        for (time, point) in self.iter() {
+            let row = std::iter::once(time)
+                .chain(point.solution().iter().copied())
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            csv.push_str(&row);
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+/// Transient analysis to calculate the response of a circuit over time.
+///
+/// Starts from the DC operating point at `t = 0` and steps to `tstop` using the integration
+/// [`Method`](super::integration::Method) of the circuit's options, through every multiple
+/// of `tstep`. Steps are shortened wherever the local truncation error of a charge or flux
+/// would grow past its tolerance, and wherever Newton-Raphson iteration fails to converge.
+/// Independent sources follow their [`Waveform`](crate::elements::waveform::Waveform), and
+/// the breakpoints of every element, such as the edges of a pulse, are stepped onto exactly.
+pub fn transient(
+    circuit: &Circuit,
+    tstep: f64,
+    tstop: f64,
+) -> Result<TransientResult, RunnerError> {
+    transient_over(circuit, &TimeSpan::new(tstep, tstop))
+}
+
+/// Transient analysis as in [`transient`], over a [`TimeSpan`] that may also start the
+/// result later than `t = 0` and cap the steps below the time step.
+///
+/// ```
+/// use spice_rs::{
+///     elements::{dc_voltage_source::DCVoltageSource, resistor::Resistor},
+///     runners::transient::{transient_over, TimeSpan},
+///     Circuit,
+/// };
+///
+/// let mut circuit = Circuit::default();
+/// let ground = circuit.push_node();
+/// let v1 = circuit.push_node();
+/// circuit.add_element(Box::new(DCVoltageSource::new(1.0, v1, ground)));
+/// circuit.add_element(Box::new(Resistor::new(1e3, v1, ground)));
+///
+/// let span = TimeSpan::new(1e-3, 1e-2).with_start(5e-3).with_max_step(1e-4);
+/// let result = transient_over(&circuit, &span).unwrap();
+///
+/// assert_eq!(result.times[0], 5e-3);
+/// assert!(result.times.windows(2).all(|pair| pair[1] - pair[0] < 1e-4 + 1e-12));
+/// ```
+pub fn transient_over(circuit: &Circuit, span: &TimeSpan) -> Result<TransientResult, RunnerError> {
+    span.validate()?;
+
+    let initial = initial_op(circuit)?;
+    integrate(circuit, initial, span)
+}
+
+/// Transient analysis that uses the initial conditions instead of the operating point,
+/// like the `UIC` option of a SPICE `.tran` line.
+///
+/// Starts at the [`initial_conditions`](crate::Circuit::initial_conditions) of the nodes,
+/// and at 0V and 0A everywhere else, without solving the circuit at `t = 0`. Capacitors and
+/// inductors with an initial voltage or current of their own, such as from
+/// [`Capacitor::with_initial_voltage`](crate::elements::capacitor::Capacitor::with_initial_voltage),
+/// start with the charge or flux it gives them instead. The circuit is otherwise stepped
+/// as in [`transient`].
+///
+/// ```
+/// use approx::assert_relative_eq;
+/// use spice_rs::{
+///     elements::{capacitor::Capacitor, resistor::Resistor},
+///     runners::transient::transient_uic,
+///     Circuit,
+/// };
+///
+/// let mut circuit = Circuit::default();
+/// let ground = circuit.push_node();
+/// let v1 = circuit.push_node();
+/// circuit.add_element(Box::new(Resistor::new(1e3, v1, ground)));
+/// circuit.add_element(Box::new(
+///     Capacitor::new(1e-6, v1, ground).with_initial_voltage(1.0),
+/// ));
+///
+/// // The capacitor discharges for one time constant
+/// let result = transient_uic(&circuit, 1e-4, 1e-3).unwrap();
+/// let voltage = result.points.last().unwrap().voltage(v1);
+/// assert_relative_eq!(voltage, (-1.0f64).exp(), epsilon = 1e-3);
+/// ```
+pub fn transient_uic(
+    circuit: &Circuit,
+    tstep: f64,
+    tstop: f64,
+) -> Result<TransientResult, RunnerError> {
+    transient_uic_over(circuit, &TimeSpan::new(tstep, tstop))
+}
+
+/// Transient analysis as in [`transient_uic`], over a [`TimeSpan`] as in [`transient_over`].
+pub fn transient_uic_over(
+    circuit: &Circuit,
+    span: &TimeSpan,
+) -> Result<TransientResult, RunnerError> {
+    span.validate()?;
+    if circuit.node_count() == 0 {
+        return Err(RunnerError::ZeroNode);
+    }
+
+    let initial = starting_point(circuit, &circuit.initial_conditions)?;
+    let charges = circuit
+        .elements()
+        .iter()
+        .flat_map(|element| element.initial_charges(&initial))
+        .collect();
+    integrate_from(circuit, initial, charges, span)
+}
+
+/// Steps the circuit from the `initial` solution at `t = 0` to `tstop` of the `span`,
+/// through every multiple of `tstep`.
+///
+/// The first step, and the first one after every breakpoint, is a tenth of `tstep`, as the
+/// circuit may have just changed abruptly. It is taken by backward Euler, and every step
+/// after it by an order higher, up to the order of the integration method, as the charges at
+/// the time points before a breakpoint are not used. From then on, every step is checked
+/// against [`truncation_step`] and taken again if it was too long, and every step grows by
+/// at most twice, up to `tmax`. A step that fails to converge is taken again an eighth as long.
+pub(crate) fn integrate(
+    circuit: &Circuit,
+    initial: OperatingPoint,
+    span: &TimeSpan,
+) -> Result<TransientResult, RunnerError> {
+    let charges = charges_at(circuit, &initial);
+    integrate_from(circuit, initial, charges, span)
+}
+
+/// Steps the circuit as in [`integrate`] over the `span`, starting with the `initial_charges`
+/// of every element one after another instead of those at the `initial` solution.
+///
+/// The start of the span is a breakpoint, and the time points before it are left out.
+fn integrate_from(
+    circuit: &Circuit,
+    initial: OperatingPoint,
+    initial_charges: Vec<f64>,
+    span: &TimeSpan,
+) -> Result<TransientResult, RunnerError> {
+    let TimeSpan {
+        tstep,
+        tstop,
+        tstart,
+        ..
+    } = *span;
+    let max_step = span.max_step();
+    let options = &circuit.options;
+    let mut newton = Newton::new(circuit, node_labels(circuit));
+    newton.initialize(&initial);
+
+    // The charges of every element one after another, with the range of every element's
+    let mut offset = 0;
+    let ranges: Vec<Range<usize>> = circuit
+        .elements()
+        .iter()
+        .map(|element| {
+            let range = offset..offset + element.charges(&initial).len();
+            offset = range.end;
+            range
+        })
+        .collect();
+    let mut charges = vec![initial_charges];
+    // The circuit is taken to be settled at the initial solution
+    let mut derivatives = vec![vec![0.0; offset]];
+    let mut times = vec![0.0];
+    let mut points = vec![initial];
+
+    let first_step = (tstep / 10.0).min(max_step);
+    // Steps shorter than this only come from rounding, or from a circuit that cannot be followed
+    let min_step = tstep * 1e-9;
+    let mut breakpoints: Vec<f64> = circuit
+        .elements()
+        .iter()
+        .flat_map(|element| element.breakpoints(tstop))
+        .chain([tstart])
+        .filter(|&breakpoint| breakpoint > min_step && breakpoint < tstop - min_step)
+        .chain([tstop])
+        .collect();
+    breakpoints.sort_by(f64::total_cmp);
+    let mut breakpoints = breakpoints.into_iter().peekable();
+
+    let mut step = first_step;
+    // The time point after which the charges change smoothly, which is the last breakpoint
+    let mut start = 0;
+    let mut grid = 1;
+    while let Some(&breakpoint) = breakpoints.peek() {
+        let last = times[times.len() - 1];
+        // Breakpoints and multiples of the time step within reach of the previous time point
+        // have been reached already
+        if breakpoint < last + min_step {
+            breakpoints.next();
+            continue;
+        }
+        while (grid as f64) * tstep < last + min_step {
+            grid += 1;
+        }
+
+        let at_breakpoint = (grid as f64) * tstep > breakpoint - min_step;
+        let target = match at_breakpoint {
+            true => breakpoint,
+            false => (grid as f64) * tstep,
+        };
+        let landing = last + step > target - min_step;
+        let time = match landing {
+            true => target,
+            false => last + step,
+        };
+        let taken = time - last;
+        let previous = &points[points.len() - 1];
+
+        // Every order needs one more time point since the last breakpoint,
+        // and its truncation error one more again
+        let past = times.len() - start;
+        let integrator = options.method.integrator((past - 1).min(options.max_order));
+        let steps: Vec<f64> = std::iter::once(taken)
+            .chain(times.windows(2).rev().map(|pair| pair[1] - pair[0]))
+            .take(integrator.order())
+            .collect();
+        let coefficients = integrator.coefficients(&steps);
+        let mut history: Vec<f64> = derivatives[derivatives.len() - 1]
+            .iter()
+            .map(|derivative| coefficients.derivative * derivative)
+            .collect();
+        for (a, past_charges) in coefficients.charges[1..].iter().zip(charges.iter().rev()) {
+            for (value, charge) in history.iter_mut().zip(past_charges) {
+                *value += a * charge;
+            }
+        }
+
+        newton.set_time(time);
+        let solution = match newton.solve(
+            previous.clone(),
+            options.transient_iterations,
+            |index, element, context, estimate, state| {
+                let integration =
+                    Integration::new(coefficients.charges[0], &history[ranges[index].clone()]);
+                element.stamp_transient(context, estimate, state, &integration)
+            },
+        ) {
+            Ok(solution) => solution,
+            Err(RunnerError::NoConvergence { .. }) if taken / 8.0 >= min_step => {
+                newton.initialize(previous);
+                step = taken / 8.0;
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        let charge = charges_at(circuit, &solution);
+        let derivative: Vec<f64> = charge
+            .iter()
+            .zip(&history)
+            .map(|(charge, history)| coefficients.charges[0] * charge + history)
+            .collect();
+
+        // The charges are differenced back to the last breakpoint at most
+        let next_step = match past > integrator.order() {
+            false => 2.0 * step,
+            true => {
+                let span = integrator.order() + 1;
+                let recent_charges: Vec<&[f64]> = std::iter::once(&charge)
+                    .chain(charges.iter().rev().take(span))
+                    .map(Vec::as_slice)
+                    .collect();
+                let recent_times: Vec<f64> = std::iter::once(time)
+                    .chain(times.iter().rev().take(span).copied())
+                    .collect();
+                let ideal = truncation_step(
+                    circuit,
+                    integrator.as_ref(),
+                    &recent_charges,
+                    [&derivative, &derivatives[derivatives.len() - 1]],
+                    &recent_times,
+                );
+                if ideal < 0.9 * taken {
+                    if ideal < min_step {
+                        return Err(RunnerError::TimeStepTooSmall { time: last });
+                    }
+                    newton.initialize(previous);
+                    step = ideal;
+                    continue;
+                }
+                ideal.min(2.0 * step)
+            }
+        };
+        step = next_step.min(max_step);
+        times.push(time);
+        points.push(solution);
+        charges.push(charge);
+        derivatives.push(derivative);
+
+        if landing && at_breakpoint {
+            breakpoints.next();
+            start = times.len() - 1;
+            step = step.min(first_step);
+        }
+    }
+
+    let kept = times.partition_point(|&time| time < tstart - min_step);
+    Ok(TransientResult {
+        times: times.split_off(kept),
+        points: points.split_off(kept),
+    })
+}
+
+/// The charges of every element at a `solution`, one element after another.
+fn charges_at(circuit: &Circuit, solution: &OperatingPoint) -> Vec<f64> {
+    circuit
+        .elements()
+        .iter()
+        .flat_map(|element| element.charges(solution))
+        .collect()
+}
+
+/// The longest step after the latest of the `times` that keeps the local truncation error
+/// of every charge and flux within `TRTOL` times its tolerance, estimated from the divided
+/// difference of one order higher than the `integrator` of the `charges` at those times.
+///
+/// The `charges` and `times` start at the latest time point and go one past the order back,
+/// and the `derivatives` are those at the latest two time points. The error in the current
+/// is held to `RELTOL` of the current plus `ABSTOL`, or to `RELTOL` of the charge,
+/// or `CHGTOL`, over the step, whichever is larger.
+fn truncation_step(
+    circuit: &Circuit,
+    integrator: &dyn Integrator,
+    charges: &[&[f64]],
+    derivatives: [&[f64]; 2],
+    times: &[f64],
+) -> f64 {
+    let options = &circuit.options;
+    let order = integrator.order();
+
+    let mut step = f64::INFINITY;
+    for (index, (i0, i1)) in derivatives[0].iter().zip(derivatives[1]).enumerate() {
+        let mut differences: Vec<f64> = charges.iter().map(|charges| charges[index]).collect();
+        for span in 1..=order + 1 {
+            for i in 0..=order + 1 - span {
+                differences[i] =
+                    (differences[i] - differences[i + 1]) / (times[i] - times[i + span]);
+            }
+        }
+        let error = integrator.error_constant() * differences[0].abs();
+        if error == 0.0 {
+            continue;
+        }
+
+        let (q0, q1) = (charges[0][index], charges[1][index]);
+        let tolerance = (options.reltol * i0.abs().max(i1.abs()) + options.abstol).max(
+            options.reltol * q0.abs().max(q1.abs()).max(options.chgtol) / (times[0] - times[1]),
+        );
+        step = step.min((options.trtol * tolerance / error).powf((order as f64).recip()));
+    }
+
+    step
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::DVector;
+
+    use crate::{
+        elements::{
+            capacitor::Capacitor, dc_voltage_source::DCVoltageSource, diode::Diode,
+            inductor::Inductor, resistor::Resistor, waveform::Waveform,
+        },
+        runners::{integration::Method, node_labels, operating_point::OperatingPoint, RunnerError},
+        Circuit,
+    };
+
+    use super::{integrate, transient, transient_over, transient_uic, TimeSpan};
+
+    /// A circuit driven by DC sources stays at its operating point.
+    #[test]
+    fn steady_state() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        circuit.add_element(Box::new(DCVoltageSource::new(10.0, v1, v0)));
+        circuit.add_element(Box::new(Resistor::new(10.0, v1, v2)));
+        circuit.add_element(Box::new(Capacitor::new(1e-3, v2, v0)));
+
+        let result = transient(&circuit, 1e-3, 1e-2).unwrap();
+
+        // Every multiple of the time step is a time point
+        for grid in 0..=10 {
+            let time = grid as f64 * 1e-3;
+            assert!(result.times.iter().any(|&t| (t - time).abs() < 1e-12));
+        }
+        assert_eq!(result.times.last(), Some(&1e-2));
+        assert!(result.to_csv().starts_with("time,v(1),v(2),i(0)\n0,10,10,"));
+        for (_, point) in result.iter() {
+            assert_relative_eq!(point.voltage(v1), 10.0, epsilon = 0.01);
+            assert_relative_eq!(point.voltage(v2), 10.0, epsilon = 0.01);
+        }
+    }
+
+    /// A charged 1µF capacitor discharging through a 1kΩ resistor for one time constant.
+    #[test]
+    fn rc_discharge() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        circuit.add_element(Box::new(Resistor::new(1e3, v1, v0)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, v1, v0)));
+
+        let initial = OperatingPoint::new(node_labels(&circuit), DVector::from_vec(vec![1.0]));
+        let result = integrate(&circuit, initial, &TimeSpan::new(1e-6, 1e-3)).unwrap();
+
+        let last = result.points.last().unwrap();
+        assert_relative_eq!(last.voltage(v1), (-1.0f64).exp(), epsilon = 0.001);
+    }
+
+    /// An inductor with 1A flowing through it discharging through a 1Ω resistor
+    /// for one time constant.
+    #[test]
+    fn rl_discharge() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        circuit.add_element(Box::new(Resistor::new(1.0, v1, v0)));
+        let inductor = circuit.add_element(Box::new(Inductor::new(1e-3, v1, v0)));
+
+        let initial =
+            OperatingPoint::new(node_labels(&circuit), DVector::from_vec(vec![-1.0, 1.0]));
+        let result = integrate(&circuit, initial, &TimeSpan::new(1e-6, 1e-3)).unwrap();
+
+        let last = result.points.last().unwrap();
+        assert_relative_eq!(last.voltage(v1), -(-1.0f64).exp(), epsilon = 0.001);
+        assert_relative_eq!(
+            last.branch_current(inductor).unwrap(),
+            (-1.0f64).exp(),
+            epsilon = 0.001
+        );
+    }
+
+    /// A UIC transient starts from the initial voltage of a node and the initial current
+    /// of an inductor, while a transient from the operating point holds the node there.
+    #[test]
+    fn initial_conditions() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        circuit.add_element(Box::new(Resistor::new(1e3, v1, v0)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, v1, v0)));
+        circuit.add_element(Box::new(Resistor::new(1.0, v2, v0)));
+        let inductor = circuit.add_element(Box::new(
+            Inductor::new(1e-3, v2, v0).with_initial_current(1.0),
+        ));
+        circuit.initial_conditions.insert(v1, 1.0);
+
+        let result = transient_uic(&circuit, 1e-5, 1e-3).unwrap();
+
+        assert_eq!(result.points[0].voltage(v1), 1.0);
+        let last = result.points.last().unwrap();
+        assert_relative_eq!(last.voltage(v1), (-1.0f64).exp(), epsilon = 0.001);
+        assert_relative_eq!(
+            last.branch_current(inductor).unwrap(),
+            (-1.0f64).exp(),
+            epsilon = 0.001
+        );
+
+        // The initial current of the inductor is left out of the operating point
+        let result = transient(&circuit, 1e-5, 1e-3).unwrap();
+
+        let first = &result.points[0];
+        assert_relative_eq!(first.voltage(v1), 1.0, epsilon = 1e-6);
+        assert_relative_eq!(first.branch_current(inductor).unwrap(), 0.0);
+        let last = result.points.last().unwrap();
+        assert_relative_eq!(last.voltage(v1), (-1.0f64).exp(), epsilon = 0.001);
+    }
+
+    /// An LC tank ringing at 5kHz for 10 periods, which backward Euler damps away,
+    /// trapezoidal integration keeps ringing, and second order Gear damps in between.
+    #[test]
+    fn methods() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        circuit.add_element(Box::new(Capacitor::new(1e-6, v1, v0)));
+        circuit.add_element(Box::new(Inductor::new(1e-3, v1, v0)));
+
+        let mut amplitude = |method: Method, max_order: usize| {
+            circuit.options.method = method;
+            circuit.options.max_order = max_order;
+            let initial =
+                OperatingPoint::new(node_labels(&circuit), DVector::from_vec(vec![1.0, 0.0]));
+            let result = integrate(&circuit, initial, &TimeSpan::new(1e-5, 2e-3)).unwrap();
+
+            // Over the last period
+            result
+                .iter()
+                .filter(|&(time, _)| time > 1.8e-3)
+                .map(|(_, point)| point.voltage(v1).abs())
+                .fold(0.0, f64::max)
+        };
+
+        assert!(amplitude(Method::BackwardEuler, 2) < 0.1);
+        assert_relative_eq!(amplitude(Method::Trapezoidal, 2), 1.0, epsilon = 0.01);
+        let gear = amplitude(Method::Gear, 2);
+        assert!(0.5 < gear && gear < 0.9);
+        assert_relative_eq!(amplitude(Method::Gear, 6), 1.0, epsilon = 0.02);
+        // Gear of the first order is backward Euler
+        assert_eq!(
+            amplitude(Method::Gear, 1),
+            amplitude(Method::BackwardEuler, 2)
+        );
+    }
+
+    /// A charging capacitor seen from its start time on, stepped no further than its
+    /// maximum step, follows the same curve as from `t = 0`.
+    #[test]
+    fn start_and_max_step() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        let edge = Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 1.0,
+            delay: 0.0,
+            rise: 1e-7,
+            fall: 1e-7,
+            width: 1.0,
+            period: f64::INFINITY,
+        };
+        circuit.add_element(Box::new(
+            DCVoltageSource::new(0.0, v1, v0).with_waveform(edge),
+        ));
+        circuit.add_element(Box::new(Resistor::new(1e3, v1, v2)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, v2, v0)));
+
+        let span = TimeSpan::new(1e-4, 2e-3)
+            .with_start(1.05e-3)
+            .with_max_step(2e-5);
+        let result = transient_over(&circuit, &span).unwrap();
+
+        assert_eq!(result.times[0], 1.05e-3);
+        assert_eq!(result.times.last(), Some(&2e-3));
+        assert!(result
+            .times
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] <= 2e-5 * (1.0 + 1e-9)));
+        let charging = |time: f64| 1.0 - (-time / 1e-3).exp();
+        for (time, point) in result.iter() {
+            assert_relative_eq!(point.voltage(v2), charging(time), epsilon = 1e-3);
+        }
+    }
+
+    /// A pulse starting between two time points, whose edges become extra time points,
+    /// while the DC voltage is ignored.
+    #[test]
+    fn pulse_breakpoints() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let pulse = Waveform::Pulse {
+            initial: 0.0,
+            pulsed: 1.0,
+            delay: 2.5e-4,
+            rise: 0.0,
+            fall: 0.0,
+            width: 5e-4,
+            period: f64::INFINITY,
+        };
+        circuit.add_element(Box::new(
+            DCVoltageSource::new(5.0, v1, v0).with_waveform(pulse),
+        ));
+        circuit.add_element(Box::new(Resistor::new(1e3, v1, v0)));
+
+        let result = transient(&circuit, 1e-4, 1e-3).unwrap();
+
+        assert!(result
+            .times
+            .windows(2)
+            .all(|pair| pair[1] > pair[0] && pair[1] - pair[0] <= 1e-4 + 1e-12));
+        let voltage_at = |time: f64| {
+            let index = result
+                .times
+                .iter()
+                .position(|&t| (t - time).abs() < 1e-12)
+                .unwrap();
+            result.points[index].voltage(v1)
+        };
+        assert_eq!(voltage_at(0.0), 0.0);
+        assert_eq!(voltage_at(2.5e-4), 0.0);
+        assert_eq!(voltage_at(3e-4), 1.0);
+        assert_eq!(voltage_at(7.5e-4), 1.0);
+        assert_eq!(voltage_at(8e-4), 0.0);
+    }
+
+    /// A capacitor charged through a resistor after a step, with a time step as long as the
+    /// time constant, which the truncation error shortens until the charging curve is followed.
+    #[test]
+    fn truncation_error() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        let step = Waveform::PiecewiseLinear(vec![(0.0, 0.0), (1e-9, 1.0)]);
+        circuit.add_element(Box::new(
+            DCVoltageSource::new(0.0, v1, v0).with_waveform(step),
+        ));
+        circuit.add_element(Box::new(Resistor::new(1e3, v1, v2)));
+        circuit.add_element(Box::new(Capacitor::new(1e-6, v2, v0)));
+
+        let result = transient(&circuit, 1e-3, 5e-3).unwrap();
+
+        // A single backward Euler step per time constant would reach 0.5 instead of 0.63
+        for time in [1e-3, 2e-3, 5e-3] {
+            let voltage = result.at(time).unwrap().voltage(v2);
+            assert_relative_eq!(voltage, 1.0 - (-time / 1e-3).exp(), epsilon = 0.02);
+        }
+        let steps: Vec<f64> = result
+            .times
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect();
+        assert!(steps.iter().any(|&step| step < 1e-4));
+        // The steps grow again as the capacitor settles
+        assert!(steps[steps.len() - 3..].iter().all(|&step| step > 1e-4));
+    }
+
+    /// A diode switched on by an abrupt edge, which does not converge in the few iterations
+    /// allowed unless the step is shortened.
+    #[test]
+    fn non_convergence() {
+        let mut circuit = Circuit::default();
+        let v0 = circuit.push_node();
+        let v1 = circuit.push_node();
+        let v2 = circuit.push_node();
+        let edge = Waveform::PiecewiseLinear(vec![(0.0, 0.0), (1e-6, 10.0)]);
+        circuit.add_element(Box::new(
+            DCVoltageSource::new(0.0, v1, v0).with_waveform(edge),
+        ));
+        circuit.add_element(Box::new(Resistor::new(10.0, v1, v2)));
+        circuit.add_element(Box::new(Diode::new(v2, v0)));
+        circuit.options.transient_iterations = 3;
+
+        let result = transient(&circuit, 1e-6, 2e-6).unwrap();
+
+        let voltage = result.at(2e-6).unwrap().voltage(v2);
+        assert!(0.7 < voltage && voltage < 1.0);
+        // Shorter than the first step after the edge, so a step failed and was taken again
+        assert!(result.times[1] < 1e-7);
+    }
+
+    #[test]
+    fn invalid_time_step_error() {
+        let mut circuit = Circuit::default();
+        circuit.push_node();
+
+        assert_eq!(
+            transient(&circuit, 0.0, 1.0),
+            Err(RunnerError::InvalidTimeStep)
+        );
+        assert_eq!(
+            transient(&circuit, 2.0, 1.0),
+            Err(RunnerError::InvalidTimeStep)
+        );
+        assert_eq!(
+            transient_over(&circuit, &TimeSpan::new(0.1, 1.0).with_start(1.0)),
+            Err(RunnerError::InvalidTimeStep)
+        );
+        assert_eq!(
+            transient_over(&circuit, &TimeSpan::new(0.1, 1.0).with_max_step(0.0)),
+            Err(RunnerError::InvalidTimeStep)
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! Sparse matrices for the MNA system, which has only a handful of entries per row.
+
+use nalgebra::{zero, ComplexField, DMatrix, RealField};
+
+/// A square matrix assembled from `(row, column, value)` entries,
+/// where entries at the same position are summed.
+///
+/// Elements stamp themselves onto a `TripletMatrix`, which is then compressed
+/// into a [`CscMatrix`] to be factored.
+///
+/// ```
+/// use spice_rs::sparse::TripletMatrix;
+///
+/// let mut matrix = TripletMatrix::new(2);
+/// matrix.add(0, 0, 1.0);
+/// matrix.add(1, 0, -1.0);
+/// matrix.add(0, 0, 2.0);
+///
+/// let matrix = matrix.to_csc();
+/// assert_eq!(matrix.get(0, 0), 3.0);
+/// assert_eq!(matrix.get(1, 0), -1.0);
+/// assert_eq!(matrix.get(0, 1), 0.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripletMatrix<T> {
+    size: usize,
+    entries: Vec<(usize, usize, T)>,
+}
+
+impl<T: ComplexField> TripletMatrix<T> {
+    /// An empty `size` by `size` matrix.
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            entries: Vec::new(),
+        }
+    }
+
+    /// The number of rows, which is also the number of columns.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Adds `value` to the entry at `row` and `column`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` or `column` is outside of the matrix.
+    pub fn add(&mut self, row: usize, column: usize, value: T) {
+        assert!(
+            row < self.size && column < self.size,
+            "({row}, {column}) is outside of a {0}x{0} matrix",
+            self.size
+        );
+        self.entries.push((row, column, value));
+    }
+
+    /// Compresses the entries into columns, summing the entries at the same position.
+    ///
+    /// Entries that sum to zero are kept, so that the pattern only depends on which
+    /// positions were stamped and not on their values.
+    pub fn to_csc(&self) -> CscMatrix<T> {
+        let mut column_starts = vec![0; self.size + 1];
+        for (_, column, _) in &self.entries {
+            column_starts[column + 1] += 1;
+        }
+        for column in 0..self.size {
+            column_starts[column + 1] += column_starts[column];
+        }
+
+        // Bucket the entries by column, then sort and merge every column by row
+        let mut next = column_starts.clone();
+        let mut bucketed = vec![(0, T::zero()); self.entries.len()];
+        for (row, column, value) in &self.entries {
+            bucketed[next[*column]] = (*row, value.clone());
+            next[*column] += 1;
+        }
+
+        let mut matrix = CscMatrix {
+            size: self.size,
+            column_starts: vec![0; self.size + 1],
+            rows: Vec::with_capacity(self.entries.len()),
+            values: Vec::with_capacity(self.entries.len()),
+        };
+        for column in 0..self.size {
+            let entries = &mut bucketed[column_starts[column]..column_starts[column + 1]];
+            entries.sort_by_key(|(row, _)| *row);
+
+            for (row, value) in entries.iter() {
+                match matrix.rows.last() {
+                    Some(last)
+                        if *last == *row && matrix.rows.len() > matrix.column_starts[column] =>
+                    {
+                        *matrix
+                            .values
+                            .last_mut()
+                            .expect("rows and values have the same length") += value.clone();
+                    }
+                    _ => {
+                        matrix.rows.push(*row);
+                        matrix.values.push(value.clone());
+                    }
+                }
+            }
+            matrix.column_starts[column + 1] = matrix.rows.len();
+        }
+
+        matrix
+    }
+}
+
+/// A square matrix in compressed sparse column form, with the rows of every column sorted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CscMatrix<T> {
+    pub(crate) size: usize,
+    /// Where the entries of every column start, followed by the total number of entries.
+    pub(crate) column_starts: Vec<usize>,
+    pub(crate) rows: Vec<usize>,
+    pub(crate) values: Vec<T>,
+}
+
+impl<T: ComplexField> CscMatrix<T> {
+    /// The number of rows, which is also the number of columns.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of stored entries.
+    pub fn entry_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The rows and values of the stored entries of `column`.
+    pub fn column(&self, column: usize) -> (&[usize], &[T]) {
+        let range = self.column_starts[column]..self.column_starts[column + 1];
+        (&self.rows[range.clone()], &self.values[range])
+    }
+
+    /// The entry at `row` and `column`, which is zero if it is not stored.
+    pub fn get(&self, row: usize, column: usize) -> T {
+        let (rows, values) = self.column(column);
+        match rows.binary_search(&row) {
+            Ok(index) => values[index].clone(),
+            Err(_) => T::zero(),
+        }
+    }
+
+    /// The largest absolute column sum.
+    pub fn one_norm(&self) -> T::RealField {
+        (0..self.size)
+            .map(|column| {
+                self.column(column)
+                    .1
+                    .iter()
+                    .fold(zero::<T::RealField>(), |sum, value| {
+                        sum + value.clone().modulus()
+                    })
+            })
+            .fold(zero::<T::RealField>(), |a, b| a.max(b))
+    }
+}
+
+impl<T: ComplexField> From<&DMatrix<T>> for CscMatrix<T> {
+    /// Stores the non-zero entries of a dense, square `matrix`.
+    fn from(matrix: &DMatrix<T>) -> Self {
+        assert!(matrix.is_square(), "only square matrices are supported");
+
+        let mut triplets = TripletMatrix::new(matrix.nrows());
+        for (column, values) in matrix.column_iter().enumerate() {
+            for (row, value) in values.iter().enumerate() {
+                if *value != T::zero() {
+                    triplets.add(row, column, value.clone());
+                }
+            }
+        }
+
+        triplets.to_csc()
+    }
+}
+
+/// A fill-reducing elimination order for the columns of `matrix`.
+///
+/// This is an approximate minimum degree ordering of the pattern of `A + Aᵀ`:
+/// the unknown coupled to the fewest others is eliminated first,
+/// which keeps the factors of a sparse matrix sparse.
+///
+/// As in AMD, eliminated unknowns are kept as elements of a quotient graph,
+/// which stands in for the clique they leave behind, and degrees are bounded
+/// from above instead of computed exactly. This keeps the ordering close to linear time.
+/// Ties go to the most recently updated unknown, and the lowest unknown at the start.
+pub fn minimum_degree<T: ComplexField>(matrix: &CscMatrix<T>) -> Vec<usize> {
+    let size = matrix.size();
+
+    // The uneliminated unknowns and the elements every unknown is coupled to
+    let mut variables: Vec<Vec<usize>> = vec![Vec::new(); size];
+    let mut elements: Vec<Vec<usize>> = vec![Vec::new(); size];
+    // The unknowns of every element, where an element is the unknown it was eliminated as
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); size];
+    for column in 0..size {
+        for &row in matrix.column(column).0 {
+            if row != column {
+                variables[row].push(column);
+                variables[column].push(row);
+            }
+        }
+    }
+    for adjacent in &mut variables {
+        adjacent.sort_unstable();
+        adjacent.dedup();
+    }
+
+    let mut degrees: Vec<usize> = variables.iter().map(Vec::len).collect();
+    let mut lists = DegreeLists::new(size);
+    for unknown in (0..size).rev() {
+        lists.insert(unknown, degrees[unknown]);
+    }
+    let mut absorbed = vec![false; size];
+    let mut in_pivot = vec![false; size];
+    // |Le \ Lp| of every element e next to the pivot element p, or None before it is counted
+    let mut outside: Vec<Option<usize>> = vec![None; size];
+    let mut ordering = Vec::with_capacity(size);
+
+    while let Some(pivot) = lists.pop_minimum() {
+        ordering.push(pivot);
+
+        // The new element couples the unknowns of the pivot and of every element it absorbs
+        let mut pivot_members = Vec::new();
+        let adjacent = std::mem::take(&mut variables[pivot]);
+        let absorbing = std::mem::take(&mut elements[pivot]);
+        for &unknown in adjacent
+            .iter()
+            .chain(absorbing.iter().flat_map(|&element| &members[element]))
+        {
+            if unknown != pivot && !in_pivot[unknown] {
+                in_pivot[unknown] = true;
+                pivot_members.push(unknown);
+            }
+        }
+        for element in absorbing {
+            absorbed[element] = true;
+            members[element] = Vec::new();
+        }
+
+        // Count how much of every other neighbouring element lies outside of the new one
+        let mut counted = Vec::new();
+        for &unknown in &pivot_members {
+            for &element in &elements[unknown] {
+                if absorbed[element] {
+                    continue;
+                }
+                let count = outside[element].get_or_insert_with(|| {
+                    counted.push(element);
+                    members[element].len()
+                });
+                *count -= 1;
+            }
+        }
+
+        let remaining = size - ordering.len();
+        for &unknown in &pivot_members {
+            variables[unknown].retain(|&other| other != pivot && !in_pivot[other]);
+            // Elements entirely inside of the new one are absorbed by it
+            elements[unknown].retain(|&element| {
+                !absorbed[element] && outside[element].is_some_and(|count| count > 0)
+            });
+
+            let external: usize = elements[unknown]
+                .iter()
+                .map(|&element| outside[element].unwrap_or_default())
+                .sum();
+            let approximate = variables[unknown].len() + pivot_members.len() - 1 + external;
+            lists.remove(unknown, degrees[unknown]);
+            degrees[unknown] = approximate
+                .min(degrees[unknown] + pivot_members.len() - 1)
+                .min(remaining - 1);
+            lists.insert(unknown, degrees[unknown]);
+            elements[unknown].push(pivot);
+        }
+        for element in counted {
+            if outside[element] == Some(0) {
+                absorbed[element] = true;
+                members[element] = Vec::new();
+            }
+            outside[element] = None;
+        }
+        for &unknown in &pivot_members {
+            in_pivot[unknown] = false;
+        }
+        members[pivot] = pivot_members;
+    }
+
+    ordering
+}
+
+/// The uneliminated unknowns, in a doubly linked list for every degree.
+struct DegreeLists {
+    heads: Vec<Option<usize>>,
+    next: Vec<Option<usize>>,
+    previous: Vec<Option<usize>>,
+    /// No list below this degree has any unknowns.
+    minimum: usize,
+}
+
+impl DegreeLists {
+    fn new(size: usize) -> Self {
+        Self {
+            heads: vec![None; size.max(1)],
+            next: vec![None; size],
+            previous: vec![None; size],
+            minimum: 0,
+        }
+    }
+
+    fn insert(&mut self, unknown: usize, degree: usize) {
+        self.previous[unknown] = None;
+        self.next[unknown] = self.heads[degree];
+        if let Some(head) = self.heads[degree] {
+            self.previous[head] = Some(unknown);
+        }
+        self.heads[degree] = Some(unknown);
+        self.minimum = self.minimum.min(degree);
+    }
+
+    fn remove(&mut self, unknown: usize, degree: usize) {
+        match self.previous[unknown] {
+            Some(previous) => self.next[previous] = self.next[unknown],
+            None => self.heads[degree] = self.next[unknown],
+        }
+        if let Some(next) = self.next[unknown] {
+            self.previous[next] = self.previous[unknown];
+        }
+    }
+
+    /// Removes and returns the most recently inserted unknown of the lowest degree.
+    fn pop_minimum(&mut self) -> Option<usize> {
+        while self.minimum < self.heads.len() {
+            if let Some(unknown) = self.heads[self.minimum] {
+                self.remove(unknown, self.minimum);
+                return Some(unknown);
+            }
+            self.minimum += 1;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DMatrix;
+
+    use super::{minimum_degree, CscMatrix, TripletMatrix};
+
+    #[test]
+    fn compression() {
+        let mut triplets = TripletMatrix::new(3);
+        triplets.add(2, 1, 1.0);
+        triplets.add(0, 1, 2.0);
+        triplets.add(2, 1, 3.0);
+        triplets.add(1, 2, -1.0);
+        triplets.add(1, 2, 1.0);
+
+        let matrix = triplets.to_csc();
+
+        assert_eq!(matrix.column(0), (&[][..], &[][..]));
+        assert_eq!(matrix.column(1), (&[0, 2][..], &[2.0, 4.0][..]));
+        // Cancelled entries stay in the pattern
+        assert_eq!(matrix.column(2), (&[1][..], &[0.0][..]));
+        assert_eq!(matrix.entry_count(), 3);
+        assert_eq!(matrix.get(2, 1), 4.0);
+        assert_eq!(matrix.get(1, 1), 0.0);
+    }
+
+    #[test]
+    fn from_dense() {
+        let dense = DMatrix::from_row_slice(2, 2, &[1.0, 0.0, -2.0, 3.0]);
+        let matrix = CscMatrix::from(&dense);
+
+        assert_eq!(matrix.entry_count(), 3);
+        assert_eq!(matrix.column(0), (&[0, 1][..], &[1.0, -2.0][..]));
+        assert_eq!(matrix.one_norm(), 3.0);
+    }
+
+    /// An arrow matrix only stays sparse when its dense row and column are not eliminated
+    /// until they are coupled to a single other unknown.
+    #[test]
+    fn arrow_ordering() {
+        let mut triplets = TripletMatrix::new(4);
+        for unknown in 0..4 {
+            triplets.add(unknown, unknown, 4.0);
+            triplets.add(0, unknown, 1.0);
+            triplets.add(unknown, 0, 1.0);
+        }
+
+        assert_eq!(minimum_degree(&triplets.to_csc()), vec![1, 2, 0, 3]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Engineering notation for component values, as written in SPICE decks and datasheets.
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+#[error("`{0}` is not a valid value")]
+pub struct InvalidValue(pub String);
+
+/// The SPICE scale suffixes, longest first so that `meg` is not read as `m`.
+const SUFFIXES: [(&str, f64); 11] = [
+    ("meg", 1e6),
+    ("mil", 25.4e-6),
+    ("t", 1e12),
+    ("g", 1e9),
+    ("k", 1e3),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+];
+
+/// The prefixes used when formatting, from 10^-15 up to 10^12.
+const PREFIXES: [&str; 10] = ["f", "p", "n", "µ", "m", "", "k", "M", "G", "T"];
+
+/// Parses a value written in SPICE engineering notation.
+///
+/// A number may be followed by one of the case insensitive scale suffixes
+/// `f`, `p`, `n`, `u`, `m`, `k`, `meg`, `g`, `t` and `mil`.
+/// The suffix can also stand in for the decimal point, so `4k7` is `4.7k`,
+/// where `r` is used when there is no scale, as in `4r7`.
+/// Any letters after the number and suffix are units and are ignored,
+/// so `10uF` is `10u`, but beware that `1F` is one femto, not one Farad.
+///
+/// ```
+/// use spice_rs::units::parse;
+///
+/// assert_eq!(parse("4k7").unwrap(), 4700.0);
+/// assert_eq!(parse("10MEG").unwrap(), 10e6);
+/// assert_eq!(parse("1.5kOhm").unwrap(), 1500.0);
+/// assert_eq!(parse("100p").unwrap(), 100e-12);
+/// assert_eq!(parse("-1.5e3V").unwrap(), -1500.0);
+/// ```
+pub fn parse(text: &str) -> Result<f64, InvalidValue> {
+    let invalid = || InvalidValue(text.to_string());
+
+    let number_length = number_length(text);
+    let (number, rest) = text.split_at(number_length);
+    let mut value: f64 = number.parse().map_err(|_| invalid())?;
+
+    let rest = rest.to_lowercase();
+    let (scale, rest) = SUFFIXES
+        .iter()
+        .find_map(|(suffix, scale)| rest.strip_prefix(suffix).map(|rest| (*scale, rest)))
+        .or_else(|| rest.strip_prefix('r').map(|rest| (1.0, rest)))
+        .unwrap_or((1.0, rest.as_str()));
+
+    // The digits after a suffix that stands in for the decimal point
+    let fraction_length = rest.chars().take_while(char::is_ascii_digit).count();
+    if fraction_length > 0 {
+        if number.contains(['.', 'e', 'E']) {
+            return Err(invalid());
+        }
+        let fraction: f64 = format!("0.{}", &rest[..fraction_length])
+            .parse()
+            .map_err(|_| invalid())?;
+        value += fraction.copysign(value);
+    }
+
+    if !rest[fraction_length..].chars().all(char::is_alphabetic) {
+        return Err(invalid());
+    }
+
+    Ok(value * scale)
+}
+
+/// The length of the leading decimal number of `text`, including any exponent.
+fn number_length(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut length = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        length += 1;
+    }
+    while length < bytes.len() && (bytes[length].is_ascii_digit() || bytes[length] == b'.') {
+        length += 1;
+    }
+
+    // Only an `e` followed by digits is an exponent
+    if matches!(bytes.get(length), Some(b'e' | b'E')) {
+        let mut exponent = length + 1;
+        if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+            exponent += 1;
+        }
+        if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+            while bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+                exponent += 1;
+            }
+            length = exponent;
+        }
+    }
+
+    length
+}
+
+/// Formats `value` in engineering notation with up to 4 significant digits,
+/// followed by an SI prefix and the `unit`.
+///
+/// ```
+/// use spice_rs::units::format;
+///
+/// assert_eq!(format(3300.0, "Ω"), "3.3 kΩ");
+/// assert_eq!(format(0.0125, "A"), "12.5 mA");
+/// assert_eq!(format(-2.2e-6, "F"), "-2.2 µF");
+/// assert_eq!(format(5.0, "V"), "5 V");
+/// ```
+pub fn format(value: f64, unit: &str) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return format!("{value} {unit}");
+    }
+
+    let mut exponent = (value.abs().log10() / 3.0).floor() as i32;
+    exponent = exponent.clamp(-5, 4);
+    let mut mantissa = value / 10f64.powi(exponent * 3);
+    let mut text = significant(mantissa);
+
+    // Rounding can carry the mantissa up to the next prefix, such as 999.99 to 1000
+    if text.trim_start_matches('-').len() > 3 && mantissa.abs() >= 999.95 && exponent < 4 {
+        exponent += 1;
+        mantissa = value / 10f64.powi(exponent * 3);
+        text = significant(mantissa);
+    }
+
+    format!("{text} {}{unit}", PREFIXES[(exponent + 5) as usize])
+}
+
+/// Formats `value` with 4 significant digits, without trailing zeros.
+fn significant(value: f64) -> String {
+    let integer_digits = (value.abs().log10().floor() as i32 + 1).max(1);
+    let decimals = (4 - integer_digits).max(0) as usize;
+    let text = format!("{value:.decimals$}");
+
+    match text.contains('.') {
+        true => text.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::{format, parse, InvalidValue};
+
+    #[test]
+    fn suffixes() {
+        assert_relative_eq!(parse("1f").unwrap(), 1e-15);
+        assert_relative_eq!(parse("1P").unwrap(), 1e-12);
+        assert_relative_eq!(parse("1n").unwrap(), 1e-9);
+        assert_relative_eq!(parse("1µ").unwrap(), 1e-6);
+        assert_relative_eq!(parse("1m").unwrap(), 1e-3);
+        assert_relative_eq!(parse("1Meg").unwrap(), 1e6);
+        assert_relative_eq!(parse("1g").unwrap(), 1e9);
+        assert_relative_eq!(parse("1T").unwrap(), 1e12);
+        assert_relative_eq!(parse("1mil").unwrap(), 25.4e-6);
+        assert_relative_eq!(parse(".5").unwrap(), 0.5);
+        assert_relative_eq!(parse("1e-3").unwrap(), 1e-3);
+        assert_relative_eq!(parse("1e").unwrap(), 1.0);
+    }
+
+    #[test]
+    fn decimal_point_suffixes() {
+        assert_relative_eq!(parse("4k7").unwrap(), 4.7e3);
+        assert_relative_eq!(parse("2u2F").unwrap(), 2.2e-6);
+        assert_relative_eq!(parse("1meg5").unwrap(), 1.5e6);
+        assert_relative_eq!(parse("4R7").unwrap(), 4.7);
+        assert_relative_eq!(parse("-4k7").unwrap(), -4.7e3);
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert_eq!(parse("k"), Err(InvalidValue("k".to_string())));
+        assert_eq!(parse("1.5k7"), Err(InvalidValue("1.5k7".to_string())));
+        assert_eq!(parse("10k!"), Err(InvalidValue("10k!".to_string())));
+        assert_eq!(parse(""), Err(InvalidValue("".to_string())));
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(format(4700.0, "Ω"), "4.7 kΩ");
+        assert_eq!(format(1.0e6, "Hz"), "1 MHz");
+        assert_eq!(format(123.456, "V"), "123.5 V");
+        assert_eq!(format(999.99, "V"), "1 kV");
+        assert_eq!(format(100e-12, "F"), "100 pF");
+        assert_eq!(format(0.0, "A"), "0 A");
+        assert_eq!(format(1e15, "Ω"), "1000 TΩ");
+    }
+}